@@ -0,0 +1,88 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// Captured outputs longer than this many bytes are compressed unless configured otherwise.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 4096;
+
+/// The output of a command, as embedded in a job result.
+///
+/// Outputs above the compression threshold are gzipped and base64 encoded to keep results small;
+/// `compressed` tells consumers to reverse that, see [`CapturedOutput::decode`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub compressed: bool,
+    pub data: String,
+}
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl CapturedOutput {
+    /// Captures `output`, compressing it if it is longer than `threshold` bytes. `None` never
+    /// compresses.
+    pub fn new(output: &str, threshold: Option<usize>) -> Result<Self, CaptureError> {
+        match threshold {
+            Some(threshold) if output.len() > threshold => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(output.as_bytes())?;
+                Ok(Self {
+                    compressed: true,
+                    data: BASE64.encode(encoder.finish()?),
+                })
+            }
+            _ => Ok(Self {
+                compressed: false,
+                data: output.to_string(),
+            }),
+        }
+    }
+
+    /// The output as captured, decompressed if needed.
+    pub fn decode(&self) -> Result<String, CaptureError> {
+        if !self.compressed {
+            return Ok(self.data.clone());
+        }
+        let compressed = BASE64.decode(&self.data)?;
+        let mut output = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut output)?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_output_round_trips_through_compression() {
+        let output = "Deploying HypERC20Collateral to holesky...\n".repeat(1000);
+        let captured = CapturedOutput::new(&output, Some(DEFAULT_COMPRESSION_THRESHOLD)).unwrap();
+        assert!(captured.compressed);
+        assert!(captured.data.len() < output.len() / 10);
+
+        let json = serde_json::to_string(&captured).unwrap();
+        let decoded: CapturedOutput = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.decode().unwrap(), output);
+
+        let small = CapturedOutput::new("ok", Some(DEFAULT_COMPRESSION_THRESHOLD)).unwrap();
+        assert_eq!(
+            small,
+            CapturedOutput {
+                compressed: false,
+                data: "ok".to_string()
+            }
+        );
+        assert!(!CapturedOutput::new(&output, None).unwrap().compressed);
+    }
+}
@@ -0,0 +1,482 @@
+use crate::runner::{shell_quote, GasOverrides, DEFAULT_BINARY};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The oldest `hyperlane` CLI whose commands and output the jobs are written against.
+pub const MIN_CLI_VERSION: Version = Version::new(5, 0, 0);
+
+/// Builds the `hyperlane` commands the jobs run, so that the binary, the registry, and for
+/// commands sending transactions the gas overrides and signing key, are passed the same way
+/// everywhere.
+#[derive(Clone, Debug)]
+pub struct HyperlaneCli {
+    binary: String,
+    registry: Option<String>,
+    local_registry: Option<PathBuf>,
+    key_file: Option<PathBuf>,
+    gas_overrides: GasOverrides,
+}
+
+/// The argv of a `hyperlane` command, see [`HyperlaneCli`].
+///
+/// Displays as a command line for `sh`, every argument quoted as needed (see [`shell_quote`]).
+/// A signing key is never part of it: signed commands read theirs into `HYP_KEY` from a key
+/// file, see [`HyperlaneCli::with_key_file`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperlaneCommand {
+    argv: Vec<String>,
+    key_file: Option<PathBuf>,
+}
+
+impl HyperlaneCommand {
+    pub fn argv(&self) -> &[String] {
+        &self.argv
+    }
+
+    /// The file the command reads `HYP_KEY` from, if it signs transactions with a configured
+    /// key.
+    pub fn key_file(&self) -> Option<&Path> {
+        self.key_file.as_deref()
+    }
+}
+
+impl fmt::Display for HyperlaneCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Set for the CLI alone, not for the rest of the shell command
+        if let Some(key_file) = &self.key_file {
+            write!(
+                f,
+                "HYP_KEY=\"$(cat {})\" ",
+                shell_quote(&key_file.to_string_lossy())
+            )?;
+        }
+        let quoted: Vec<String> = self.argv.iter().map(|arg| shell_quote(arg)).collect();
+        f.write_str(&quoted.join(" "))
+    }
+}
+
+/// A `major.minor.patch` release of the `hyperlane` CLI. Pre-release and build suffixes are
+/// ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Invalid version `{0}`, expected `<major>.<minor>.<patch>`")]
+pub struct InvalidVersion(pub String);
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// The first version printed in `output`, that of `hyperlane --version`. Whatever the CLI
+    /// prints around it, such as update notices, is skipped.
+    pub fn from_output(output: &str) -> Option<Self> {
+        output.split_whitespace().find_map(|word| word.parse().ok())
+    }
+}
+
+impl FromStr for Version {
+    type Err = InvalidVersion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidVersion(s.to_string());
+        let release = s
+            .strip_prefix('v')
+            .unwrap_or(s)
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default();
+        let parts = release
+            .split('.')
+            .map(|part| part.parse().map_err(|_| invalid()))
+            .collect::<Result<Vec<u64>, _>>()?;
+        match parts[..] {
+            [major, minor, patch] => Ok(Self::new(major, minor, patch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Default for HyperlaneCli {
+    fn default() -> Self {
+        Self {
+            binary: DEFAULT_BINARY.to_string(),
+            registry: None,
+            local_registry: None,
+            key_file: None,
+            gas_overrides: GasOverrides::default(),
+        }
+    }
+}
+
+impl HyperlaneCli {
+    /// Runs `binary` in place of the `hyperlane` on `PATH`.
+    pub fn with_binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Points every command at the registry at `registry`, a URL or a local path, through
+    /// `--registry`, instead of the CLI's default.
+    pub fn with_registry(mut self, registry: impl Into<String>) -> Self {
+        self.registry = Some(registry.into());
+        self
+    }
+
+    /// Layers the local registry in `dir` over the registry, through `--overrides`, instead of
+    /// the CLI's default `~/.hyperlane`. This is where `registry init` writes chain metadata and
+    /// deployments write their addresses.
+    pub fn with_local_registry(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.local_registry = Some(dir.into());
+        self
+    }
+
+    /// Signs with the key in the file at `key_file`, see [`KeyFile`](crate::runner::KeyFile),
+    /// instead of the `HYP_KEY` of the environment. The key is read into `HYP_KEY` by the shell
+    /// running the command, so it never appears on a command line.
+    pub fn with_key_file(mut self, key_file: impl Into<PathBuf>) -> Self {
+        self.key_file = Some(key_file.into());
+        self
+    }
+
+    /// Caps the fees of the transactions sent by [`HyperlaneCli::signed`] commands.
+    pub fn with_gas_overrides(mut self, gas_overrides: GasOverrides) -> Self {
+        self.gas_overrides = gas_overrides;
+        self
+    }
+
+    pub fn binary(&self) -> &str {
+        &self.binary
+    }
+
+    pub fn key_file(&self) -> Option<&Path> {
+        self.key_file.as_deref()
+    }
+
+    /// The `hyperlane` command with the given arguments, e.g. `command(&["warp", "deploy"])`.
+    pub fn command(&self, args: &[&str]) -> HyperlaneCommand {
+        let mut argv = vec![self.binary.clone()];
+        argv.extend(args.iter().map(|arg| arg.to_string()));
+        if let Some(registry) = &self.registry {
+            argv.extend(["--registry".to_string(), registry.clone()]);
+        }
+        if let Some(local_registry) = &self.local_registry {
+            argv.extend(["--overrides".to_string(), path_arg(local_registry)]);
+        }
+        HyperlaneCommand {
+            argv,
+            key_file: None,
+        }
+    }
+
+    /// Like [`HyperlaneCli::command`], for commands that send transactions: these are passed
+    /// `--yes`, so the CLI doesn't ask for confirmation, and the configured gas overrides and
+    /// signing key, if any.
+    pub fn signed(&self, args: &[&str]) -> HyperlaneCommand {
+        let HyperlaneCommand { mut argv, .. } = self.command(args);
+        argv.push("--yes".to_string());
+        if let Some(max_fee) = self.gas_overrides.max_fee_per_gas {
+            argv.extend(["--max-fee-per-gas".to_string(), max_fee.to_string()]);
+        }
+        if let Some(max_priority_fee) = self.gas_overrides.max_priority_fee_per_gas {
+            argv.extend([
+                "--max-priority-fee-per-gas".to_string(),
+                max_priority_fee.to_string(),
+            ]);
+        }
+        HyperlaneCommand {
+            argv,
+            key_file: self.key_file.clone(),
+        }
+    }
+
+    /// `--version`, which prints the version of the CLI. The registry doesn't matter to it.
+    pub fn version(&self) -> HyperlaneCommand {
+        HyperlaneCommand {
+            argv: vec![self.binary.clone(), "--version".to_string()],
+            key_file: None,
+        }
+    }
+
+    pub fn registry_init(&self) -> HyperlaneCommand {
+        self.command(&["registry", "init"])
+    }
+
+    pub fn registry_list(&self) -> HyperlaneCommand {
+        self.command(&["registry", "list"])
+    }
+
+    pub fn registry_addresses(&self) -> HyperlaneCommand {
+        self.command(&["registry", "addresses"])
+    }
+
+    /// `core init --advanced`, writing the core config to `config`, or reading it from there if
+    /// it already exists.
+    pub fn core_init(&self, config: &Path) -> HyperlaneCommand {
+        self.command(&["core", "init", "--advanced", "--config", &path_arg(config)])
+    }
+
+    pub fn core_deploy(&self, config: &Path) -> HyperlaneCommand {
+        self.signed(&["core", "deploy", "--config", &path_arg(config)])
+    }
+
+    pub fn core_read(&self, chain: &str) -> HyperlaneCommand {
+        self.read("core", chain)
+    }
+
+    pub fn core_apply(&self, chain: &str, input: &str) -> HyperlaneCommand {
+        self.apply("core", chain, input)
+    }
+
+    pub fn warp_deploy(&self, config: &Path) -> HyperlaneCommand {
+        self.signed(&["warp", "deploy", "--config", &path_arg(config)])
+    }
+
+    pub fn warp_read(&self, chain: &str) -> HyperlaneCommand {
+        self.read("warp", chain)
+    }
+
+    pub fn warp_apply(&self, chain: &str, input: &str) -> HyperlaneCommand {
+        self.apply("warp", chain, input)
+    }
+
+    /// `<kind> read` of the config of `chain`, where `kind` is `core` or `warp`.
+    pub fn read(&self, kind: &str, chain: &str) -> HyperlaneCommand {
+        self.command(&[kind, "read", "--chain", chain])
+    }
+
+    /// `<kind> apply` of `input` to `chain`, where `kind` is `core` or `warp`.
+    pub fn apply(&self, kind: &str, chain: &str, input: &str) -> HyperlaneCommand {
+        self.signed(&[kind, "apply", "--chain", chain, "--input", input])
+    }
+}
+
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcommands() {
+        let cli = HyperlaneCli::default();
+        let config = Path::new("/tmp/configs/warp route.yaml");
+        assert_eq!(cli.version().argv(), ["hyperlane", "--version"]);
+        assert_eq!(
+            cli.registry_init().argv(),
+            ["hyperlane", "registry", "init"]
+        );
+        assert_eq!(
+            cli.registry_list().argv(),
+            ["hyperlane", "registry", "list"]
+        );
+        assert_eq!(
+            cli.registry_addresses().argv(),
+            ["hyperlane", "registry", "addresses"]
+        );
+        assert_eq!(
+            cli.core_init(Path::new("configs/core-config.yaml")).argv(),
+            [
+                "hyperlane",
+                "core",
+                "init",
+                "--advanced",
+                "--config",
+                "configs/core-config.yaml"
+            ]
+        );
+        assert_eq!(
+            cli.core_deploy(Path::new("configs/core-config.yaml"))
+                .argv(),
+            [
+                "hyperlane",
+                "core",
+                "deploy",
+                "--config",
+                "configs/core-config.yaml",
+                "--yes"
+            ]
+        );
+        assert_eq!(
+            cli.core_read("holesky").argv(),
+            ["hyperlane", "core", "read", "--chain", "holesky"]
+        );
+        assert_eq!(
+            cli.core_apply("holesky", "owner: '0x01'").argv(),
+            [
+                "hyperlane",
+                "core",
+                "apply",
+                "--chain",
+                "holesky",
+                "--input",
+                "owner: '0x01'",
+                "--yes"
+            ]
+        );
+        assert_eq!(
+            cli.warp_deploy(config).argv(),
+            [
+                "hyperlane",
+                "warp",
+                "deploy",
+                "--config",
+                "/tmp/configs/warp route.yaml",
+                "--yes"
+            ]
+        );
+        assert_eq!(
+            cli.warp_read("sepolia").argv(),
+            ["hyperlane", "warp", "read", "--chain", "sepolia"]
+        );
+        assert_eq!(
+            cli.warp_apply("sepolia", "{}").argv(),
+            [
+                "hyperlane",
+                "warp",
+                "apply",
+                "--chain",
+                "sepolia",
+                "--input",
+                "{}",
+                "--yes"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!("5.3.0".parse(), Ok(Version::new(5, 3, 0)));
+        assert_eq!("v12.0.1-beta.2".parse(), Ok(Version::new(12, 0, 1)));
+        assert_eq!("5.3.0+build.7".parse(), Ok(Version::new(5, 3, 0)));
+        for invalid in ["5.3", "5.3.0.1", "five.3.0", "", "v"] {
+            assert_eq!(
+                invalid.parse::<Version>(),
+                Err(InvalidVersion(invalid.to_string()))
+            );
+        }
+        assert!(Version::new(4, 9, 9) < MIN_CLI_VERSION);
+        assert!(Version::new(5, 10, 0) > Version::new(5, 9, 1));
+        assert_eq!(Version::new(5, 3, 0).to_string(), "5.3.0");
+
+        let output = "\nA new version of the CLI is available\n5.3.0\n";
+        assert_eq!(Version::from_output(output), Some(Version::new(5, 3, 0)));
+        assert_eq!(Version::from_output("sh: hyperlane: not found"), None);
+    }
+
+    #[test]
+    fn test_commands_display_quoted() {
+        let cli = HyperlaneCli::default().with_binary("/opt/hyperlane/bin/hyperlane");
+        assert_eq!(
+            cli.warp_deploy(Path::new("/tmp/it's.yaml")).to_string(),
+            r"/opt/hyperlane/bin/hyperlane warp deploy --config '/tmp/it'\''s.yaml' --yes"
+        );
+        assert_eq!(
+            cli.warp_read("holesky").to_string(),
+            "/opt/hyperlane/bin/hyperlane warp read --chain holesky"
+        );
+    }
+
+    #[test]
+    fn test_signed_commands_read_the_key_file() {
+        let cli = HyperlaneCli::default();
+        assert_eq!(
+            cli.signed(&["warp", "deploy"]).to_string(),
+            "hyperlane warp deploy --yes"
+        );
+
+        let cli = cli.with_key_file("/run/keys/signing key");
+        let warp_deploy = cli.signed(&["warp", "deploy"]);
+        assert_eq!(
+            warp_deploy.to_string(),
+            r#"HYP_KEY="$(cat '/run/keys/signing key')" hyperlane warp deploy --yes"#
+        );
+        assert_eq!(warp_deploy.argv(), ["hyperlane", "warp", "deploy", "--yes"]);
+        assert_eq!(cli.registry_init().to_string(), "hyperlane registry init");
+        assert_eq!(cli.registry_init().key_file(), None);
+    }
+
+    #[test]
+    fn test_commands_use_the_configured_registry() {
+        let cli = HyperlaneCli::default()
+            .with_registry("https://github.com/hyperlane-xyz/hyperlane-registry");
+        assert_eq!(
+            cli.registry_list().to_string(),
+            "hyperlane registry list --registry https://github.com/hyperlane-xyz/hyperlane-registry"
+        );
+        assert_eq!(
+            cli.signed(&["warp", "deploy"]).to_string(),
+            "hyperlane warp deploy --registry https://github.com/hyperlane-xyz/hyperlane-registry \
+             --yes"
+        );
+    }
+
+    #[test]
+    fn test_commands_use_the_local_registry() {
+        let cli = HyperlaneCli::default().with_local_registry("/var/lib/blueprint/7/.registry");
+        assert_eq!(
+            cli.registry_init().argv(),
+            [
+                "hyperlane",
+                "registry",
+                "init",
+                "--overrides",
+                "/var/lib/blueprint/7/.registry"
+            ]
+        );
+        assert_eq!(
+            cli.signed(&["warp", "deploy"]).to_string(),
+            "hyperlane warp deploy --overrides /var/lib/blueprint/7/.registry --yes"
+        );
+        assert_eq!(cli.version().argv(), ["hyperlane", "--version"]);
+    }
+
+    #[test]
+    fn test_signed_commands_pass_gas_overrides() {
+        let cli = HyperlaneCli::default()
+            .with_gas_overrides(GasOverrides {
+                max_fee_per_gas: Some(30_000_000_000),
+                max_priority_fee_per_gas: Some(2_000_000_000),
+            })
+            .with_key_file("/run/keys/key");
+        assert_eq!(
+            cli.core_deploy(Path::new("configs/core-config.yaml"))
+                .to_string(),
+            "HYP_KEY=\"$(cat /run/keys/key)\" hyperlane core deploy --config configs/core-config.yaml \
+             --yes --max-fee-per-gas 30000000000 --max-priority-fee-per-gas 2000000000"
+        );
+        assert_eq!(
+            cli.core_read("holesky").to_string(),
+            "hyperlane core read --chain holesky"
+        );
+
+        let cli = cli.with_gas_overrides(GasOverrides {
+            max_priority_fee_per_gas: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(
+            cli.signed(&["warp", "deploy"]).to_string(),
+            "HYP_KEY=\"$(cat /run/keys/key)\" hyperlane warp deploy --yes \
+             --max-priority-fee-per-gas 1"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::preflight::{PreflightError, RpcEndpoints};
+use crate::WarpRouteJobError;
+use alloy_primitives::{Address, Bytes};
+use alloy_provider::Provider;
+use async_trait::async_trait;
+use gadget_sdk::info;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long [`wait_for_code`] polls for by default before giving up.
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Looks up the code deployed at an address.
+#[async_trait]
+pub trait CodeProvider: Debug + Send + Sync {
+    async fn code(&self, chain: &str, address: Address) -> Result<Bytes, PreflightError>;
+}
+
+/// Queries code over JSON-RPC (`eth_getCode`), with one endpoint per chain.
+#[derive(Clone, Debug, Default)]
+pub struct RpcCodeProvider {
+    endpoints: RpcEndpoints,
+}
+
+impl RpcCodeProvider {
+    pub fn new(endpoints: RpcEndpoints) -> Self {
+        Self { endpoints }
+    }
+}
+
+#[async_trait]
+impl CodeProvider for RpcCodeProvider {
+    async fn code(&self, chain: &str, address: Address) -> Result<Bytes, PreflightError> {
+        self.endpoints
+            .provider(chain)?
+            .get_code_at(address)
+            .await
+            .map_err(|e| PreflightError::Rpc {
+                chain: chain.to_string(),
+                message: e.to_string(),
+            })
+    }
+}
+
+/// Settings for [`wait_for_code`].
+#[derive(Clone, Debug)]
+pub struct ConfirmationPolling {
+    pub provider: Arc<dyn CodeProvider>,
+    /// The delay before the second poll, doubled after every poll that finds no code.
+    pub initial_delay: Duration,
+    /// The longest delay between two polls.
+    pub max_delay: Duration,
+    /// How long to poll for before failing with [`WarpRouteJobError::ConfirmationTimeout`].
+    pub timeout: Duration,
+}
+
+impl ConfirmationPolling {
+    /// Polls the chains of `endpoints` for up to `CONFIRMATION_TIMEOUT_SECS` seconds, or
+    /// [`DEFAULT_CONFIRMATION_TIMEOUT`].
+    ///
+    /// Returns `None` without `endpoints`.
+    pub fn from_env(endpoints: Option<&RpcEndpoints>) -> Result<Option<Self>, PreflightError> {
+        let Some(endpoints) = endpoints.cloned() else {
+            return Ok(None);
+        };
+        let timeout = match std::env::var("CONFIRMATION_TIMEOUT_SECS") {
+            Ok(value) => {
+                Duration::from_secs(value.parse().map_err(|_| PreflightError::InvalidSetting {
+                    name: "CONFIRMATION_TIMEOUT_SECS",
+                    value,
+                })?)
+            }
+            Err(_) => DEFAULT_CONFIRMATION_TIMEOUT,
+        };
+
+        Ok(Some(Self {
+            provider: Arc::new(RpcCodeProvider::new(endpoints)),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(16),
+            timeout,
+        }))
+    }
+}
+
+/// Polls `chain` until there is code at `address`, backing off exponentially between polls.
+///
+/// `hyperlane core deploy` returns once its transactions are submitted, so the contracts it
+/// deployed may not be visible to the next command yet.
+pub async fn wait_for_code(
+    polling: &ConfirmationPolling,
+    chain: &str,
+    address: Address,
+) -> Result<(), WarpRouteJobError> {
+    let deadline = Instant::now() + polling.timeout;
+    let mut delay = polling.initial_delay;
+    loop {
+        if !polling.provider.code(chain, address).await?.is_empty() {
+            info!("Contract {address} is deployed on `{chain}`");
+            return Ok(());
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(WarpRouteJobError::ConfirmationTimeout {
+                chain: chain.to_string(),
+                address,
+            });
+        }
+        info!("No code at {address} on `{chain}` yet, polling again in {delay:?}");
+        tokio::time::sleep(delay.min(deadline - now)).await;
+        delay = (delay * 2).min(polling.max_delay);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const MAILBOX: Address = Address::new([0x46; 20]);
+
+    /// Returns empty code for the first `empty_polls` polls, then some code.
+    #[derive(Debug, Default)]
+    pub(crate) struct MockCodeProvider {
+        empty_polls: usize,
+        polls: AtomicUsize,
+    }
+
+    impl MockCodeProvider {
+        pub(crate) fn new(empty_polls: usize) -> Self {
+            Self {
+                empty_polls,
+                polls: AtomicUsize::new(0),
+            }
+        }
+
+        pub(crate) fn polls(&self) -> usize {
+            self.polls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl CodeProvider for MockCodeProvider {
+        async fn code(&self, _chain: &str, _address: Address) -> Result<Bytes, PreflightError> {
+            let poll = self.polls.fetch_add(1, Ordering::SeqCst);
+            Ok(if poll < self.empty_polls {
+                Bytes::new()
+            } else {
+                Bytes::from_static(&[0x60, 0x80])
+            })
+        }
+    }
+
+    pub(crate) fn mock_polling(provider: Arc<MockCodeProvider>) -> ConfirmationPolling {
+        ConfirmationPolling {
+            provider,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(40),
+            timeout: Duration::from_millis(200),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_polls_until_code_is_deployed() {
+        let provider = Arc::new(MockCodeProvider::new(2));
+        let started = std::time::Instant::now();
+        wait_for_code(&mock_polling(provider.clone()), "holesky", MAILBOX)
+            .await
+            .unwrap();
+
+        assert_eq!(provider.polls(), 3);
+        // Backed off 10ms, then 20ms
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn test_missing_code_times_out() {
+        let provider = Arc::new(MockCodeProvider::new(usize::MAX));
+        assert!(matches!(
+            wait_for_code(&mock_polling(provider), "holesky", MAILBOX).await,
+            Err(WarpRouteJobError::ConfirmationTimeout { chain, address })
+                if chain == "holesky" && address == MAILBOX
+        ));
+    }
+}
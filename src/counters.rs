@@ -0,0 +1,67 @@
+use crate::hyperlane::WarpRouteConfig;
+
+/// Counts deployments by `result` and `token_type`, see [`route_token_types`].
+pub const DEPLOYMENTS: &str = "warp_route_deploy_total";
+
+/// Counts the reconciliations of a chain's config, i.e. its read and apply, by `chain` and
+/// `result`.
+pub const CHAIN_APPLIES: &str = "chain_apply_total";
+
+/// The `result` label of a successful run.
+pub const SUCCESS: &str = "success";
+
+/// The `result` label of a failed run.
+pub const FAILURE: &str = "failure";
+
+/// The `token_type` label of a deployment whose config couldn't be parsed.
+pub const UNKNOWN_TOKEN_TYPE: &str = "unknown";
+
+/// The token types of the chains of `config`, without repeats, sorted and separated by commas,
+/// e.g. `collateral,synthetic`.
+pub fn route_token_types(config: &WarpRouteConfig) -> String {
+    let mut token_types: Vec<String> = config
+        .iter_chains()
+        .filter_map(|(_, chain)| serde_json::to_value(chain.token_type()).ok())
+        .filter_map(|token_type| token_type.as_str().map(str::to_string))
+        .collect();
+    token_types.sort_unstable();
+    token_types.dedup();
+    token_types.join(",")
+}
+
+/// Counts a deployment of a route of `token_type` in [`DEPLOYMENTS`]. Does nothing unless the
+/// `metrics` feature is enabled.
+pub fn record_deployment(token_type: &str, success: bool) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        DEPLOYMENTS,
+        "result" => result_label(success),
+        "token_type" => token_type.to_string()
+    )
+    .increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (token_type, success);
+}
+
+/// Counts a reconciliation of `chain` in [`CHAIN_APPLIES`]. Does nothing unless the `metrics`
+/// feature is enabled.
+pub fn record_chain_apply(chain: &str, success: bool) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        CHAIN_APPLIES,
+        "chain" => chain.to_string(),
+        "result" => result_label(success)
+    )
+    .increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (chain, success);
+}
+
+#[cfg(feature = "metrics")]
+fn result_label(success: bool) -> &'static str {
+    if success {
+        SUCCESS
+    } else {
+        FAILURE
+    }
+}
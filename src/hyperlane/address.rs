@@ -0,0 +1,141 @@
+use alloy_primitives::Address;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An account or contract address on any chain Hyperlane supports.
+///
+/// EVM chains use 20-byte hex addresses, Cosmos chains bech32 (`neutron1...`) and Sealevel chains
+/// base58. The form is recognised from the address itself, so EVM configs read as before.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChainAddress {
+    Evm(Address),
+    Bech32(String),
+    Base58(String),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("`{0}` is not an EVM, bech32 or base58 address")]
+pub struct InvalidChainAddress(pub String);
+
+impl ChainAddress {
+    /// The EVM address, if this is one.
+    pub fn as_evm(&self) -> Option<Address> {
+        match self {
+            ChainAddress::Evm(address) => Some(*address),
+            _ => None,
+        }
+    }
+}
+
+impl From<Address> for ChainAddress {
+    fn from(address: Address) -> Self {
+        ChainAddress::Evm(address)
+    }
+}
+
+impl FromStr for ChainAddress {
+    type Err = InvalidChainAddress;
+
+    /// Only the shape of bech32 and base58 addresses is checked, not their checksum or length
+    /// in bytes; the `hyperlane` CLI rejects those that don't decode.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidChainAddress(s.to_string());
+        if s.starts_with("0x") || s.starts_with("0X") {
+            return s.parse().map(ChainAddress::Evm).map_err(|_| invalid());
+        }
+        if is_bech32(s) {
+            return Ok(ChainAddress::Bech32(s.to_string()));
+        }
+        if is_base58(s) {
+            return Ok(ChainAddress::Base58(s.to_string()));
+        }
+        Err(invalid())
+    }
+}
+
+/// `<hrp>1<data>` in a single case, with the data drawn from the bech32 charset.
+fn is_bech32(s: &str) -> bool {
+    const CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    if s != s.to_lowercase() && s != s.to_uppercase() {
+        return false;
+    }
+    let s = s.to_lowercase();
+    let Some((hrp, data)) = s.rsplit_once('1') else {
+        return false;
+    };
+    !hrp.is_empty()
+        && hrp.bytes().all(|b| (33..=126).contains(&b))
+        && data.len() >= 6
+        && data.chars().all(|c| CHARSET.contains(c))
+}
+
+/// Sealevel public keys, 32 bytes in base58, are 32 to 44 characters long.
+fn is_base58(s: &str) -> bool {
+    const ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    (32..=44).contains(&s.len()) && s.chars().all(|c| ALPHABET.contains(c))
+}
+
+impl fmt::Display for ChainAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainAddress::Evm(address) => fmt::Display::fmt(address, f),
+            ChainAddress::Bech32(address) | ChainAddress::Base58(address) => f.write_str(address),
+        }
+    }
+}
+
+impl Serialize for ChainAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ChainAddress::Evm(address) => address.serialize(serializer),
+            ChainAddress::Bech32(address) | ChainAddress::Base58(address) => {
+                serializer.serialize_str(address)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address = String::deserialize(deserializer)?;
+        address.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chain_addresses() {
+        let evm: ChainAddress = "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            evm.as_evm(),
+            Some(
+                "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+                    .parse()
+                    .unwrap()
+            )
+        );
+
+        let cosmos = "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4";
+        assert_eq!(cosmos.parse(), Ok(ChainAddress::Bech32(cosmos.to_string())));
+
+        let sealevel = "EitxJuv2iBjsg2d7jVy2LDC1e2zBrx4GB5Y9h2Ko3A9Y";
+        assert_eq!(
+            sealevel.parse(),
+            Ok(ChainAddress::Base58(sealevel.to_string()))
+        );
+
+        for invalid in ["0x1234", "mailbox", "neutron1Sjzzd4gwkggy", ""] {
+            assert_eq!(
+                invalid.parse::<ChainAddress>(),
+                Err(InvalidChainAddress(invalid.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use super::{
+    ChainAddress, ChainConfig, ChainName, ConfigError, InterchainSecurityModule, TokenType,
+    WarpRouteConfig, XErc20Config,
+};
+use std::collections::BTreeMap;
+
+/// Builds a [`WarpRouteConfig`] chain by chain.
+///
+/// Each setter applies to the chain most recently added with [`add_chain`](Self::add_chain), e.g.
+/// `.add_chain("holesky").token_type(TokenType::Collateral).token(token).mailbox(mailbox)...`.
+#[derive(Clone, Debug, Default)]
+pub struct WarpRouteConfigBuilder {
+    chains: Vec<(ChainName, ChainConfigBuilder)>,
+    /// Set when a setter is called before any chain was added.
+    missing_chain: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ChainConfigBuilder {
+    token_type: Option<TokenType>,
+    token: Option<ChainAddress>,
+    vault: Option<ChainAddress>,
+    lockbox: Option<ChainAddress>,
+    xerc20: Option<XErc20Config>,
+    decimals: Option<u8>,
+    scale: Option<u64>,
+    mailbox: Option<ChainAddress>,
+    interchain_gas_paymaster: Option<ChainAddress>,
+    owner: Option<ChainAddress>,
+    interchain_security_module: Option<InterchainSecurityModule>,
+    is_nft: Option<bool>,
+}
+
+impl WarpRouteConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts configuring `name`; the following setters apply to it.
+    pub fn add_chain(mut self, name: impl Into<ChainName>) -> Self {
+        self.chains
+            .push((name.into(), ChainConfigBuilder::default()));
+        self
+    }
+
+    pub fn token_type(self, token_type: TokenType) -> Self {
+        self.update("type", |chain| chain.token_type = Some(token_type))
+    }
+
+    pub fn token(self, token: impl Into<ChainAddress>) -> Self {
+        self.update("token", |chain| chain.token = Some(token.into()))
+    }
+
+    pub fn vault(self, vault: impl Into<ChainAddress>) -> Self {
+        self.update("vault", |chain| chain.vault = Some(vault.into()))
+    }
+
+    pub fn lockbox(self, lockbox: impl Into<ChainAddress>) -> Self {
+        self.update("lockbox", |chain| chain.lockbox = Some(lockbox.into()))
+    }
+
+    pub fn xerc20(self, xerc20: XErc20Config) -> Self {
+        self.update("xERC20", |chain| chain.xerc20 = Some(xerc20))
+    }
+
+    pub fn decimals(self, decimals: u8) -> Self {
+        self.update("decimals", |chain| chain.decimals = Some(decimals))
+    }
+
+    pub fn scale(self, scale: u64) -> Self {
+        self.update("scale", |chain| chain.scale = Some(scale))
+    }
+
+    pub fn mailbox(self, mailbox: impl Into<ChainAddress>) -> Self {
+        self.update("mailbox", |chain| chain.mailbox = Some(mailbox.into()))
+    }
+
+    pub fn interchain_gas_paymaster(self, igp: impl Into<ChainAddress>) -> Self {
+        self.update("interchainGasPaymaster", |chain| {
+            chain.interchain_gas_paymaster = Some(igp.into())
+        })
+    }
+
+    pub fn owner(self, owner: impl Into<ChainAddress>) -> Self {
+        self.update("owner", |chain| chain.owner = Some(owner.into()))
+    }
+
+    /// Secures the chain with a trusted relayer ISM. Without this, the owner is the trusted
+    /// relayer, as with `hyperlane warp init`.
+    pub fn trusted_relayer(self, relayer: impl Into<ChainAddress>) -> Self {
+        self.update("interchainSecurityModule", |chain| {
+            chain.interchain_security_module =
+                Some(InterchainSecurityModule::trusted_relayer(relayer))
+        })
+    }
+
+    /// Without this, `isNft` follows the token type, see [`TokenType::is_nft`].
+    pub fn is_nft(self, is_nft: bool) -> Self {
+        self.update("isNft", |chain| chain.is_nft = Some(is_nft))
+    }
+
+    fn update(mut self, field: &'static str, f: impl FnOnce(&mut ChainConfigBuilder)) -> Self {
+        match self.chains.last_mut() {
+            Some((_, chain)) => f(chain),
+            None => self.missing_chain = self.missing_chain.or(Some(field)),
+        }
+        self
+    }
+
+    /// Checks that every chain has its required fields, including those its token type
+    /// requires, and assembles the config.
+    pub fn build(self) -> Result<WarpRouteConfig, ConfigError> {
+        if let Some(field) = self.missing_chain {
+            return Err(ConfigError::NoChainSelected { field });
+        }
+
+        let mut config = WarpRouteConfig {
+            chains: BTreeMap::new(),
+        };
+        for (name, chain) in self.chains {
+            let chain_config = chain.build(&name)?;
+            chain_config.validate(&name)?;
+            config.merge(WarpRouteConfig {
+                chains: [(name, chain_config)].into(),
+            })?;
+        }
+        Ok(config)
+    }
+}
+
+impl ChainConfigBuilder {
+    fn build(self, name: &str) -> Result<ChainConfig, ConfigError> {
+        let missing = |field| ConfigError::MissingField {
+            chain: name.to_string(),
+            field,
+        };
+        let token_type = self.token_type.ok_or_else(|| missing("type"))?;
+        let owner = self.owner.ok_or_else(|| missing("owner"))?;
+        if self.token.is_none() && token_type.requires_token() {
+            return Err(ConfigError::MissingTokenField {
+                chain: name.to_string(),
+                field: "token",
+            });
+        }
+
+        Ok(ChainConfig {
+            interchain_security_module: self
+                .interchain_security_module
+                .unwrap_or_else(|| InterchainSecurityModule::trusted_relayer(owner.clone())),
+            is_nft: self.is_nft.unwrap_or(token_type.is_nft()),
+            mailbox: self.mailbox.ok_or_else(|| missing("mailbox"))?,
+            interchain_gas_paymaster: self
+                .interchain_gas_paymaster
+                .ok_or_else(|| missing("interchainGasPaymaster"))?,
+            owner,
+            token_type,
+            token: self.token,
+            vault: self.vault,
+            lockbox: self.lockbox,
+            xerc20: self.xerc20,
+            decimals: self.decimals,
+            scale: self.scale,
+            remote_routers: None,
+            destination_gas: None,
+            extra: BTreeMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    const MAILBOX: Address = Address::new([0x11; 20]);
+    const IGP: Address = Address::new([0x22; 20]);
+    const OWNER: Address = Address::new([0x33; 20]);
+    const TOKEN: Address = Address::new([0x44; 20]);
+
+    fn chain(builder: WarpRouteConfigBuilder, name: &str) -> WarpRouteConfigBuilder {
+        builder
+            .add_chain(name)
+            .mailbox(MAILBOX)
+            .interchain_gas_paymaster(IGP)
+            .owner(OWNER)
+    }
+
+    #[test]
+    fn test_build_collateral_to_synthetic_route() {
+        let builder = chain(WarpRouteConfigBuilder::new(), "holesky")
+            .token_type(TokenType::Collateral)
+            .token(TOKEN);
+        let config = chain(builder, "tangletestnet")
+            .token_type(TokenType::Synthetic)
+            .build()
+            .unwrap();
+
+        let expected = r#"
+        holesky:
+          interchainSecurityModule:
+            relayer: "0x3333333333333333333333333333333333333333"
+            type: trustedRelayerIsm
+          isNft: false
+          mailbox: "0x1111111111111111111111111111111111111111"
+          interchainGasPaymaster: "0x2222222222222222222222222222222222222222"
+          owner: "0x3333333333333333333333333333333333333333"
+          type: collateral
+          token: "0x4444444444444444444444444444444444444444"
+        tangletestnet:
+          interchainSecurityModule:
+            relayer: "0x3333333333333333333333333333333333333333"
+            type: trustedRelayerIsm
+          isNft: false
+          mailbox: "0x1111111111111111111111111111111111111111"
+          interchainGasPaymaster: "0x2222222222222222222222222222222222222222"
+          owner: "0x3333333333333333333333333333333333333333"
+          type: synthetic
+        "#;
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<serde_json::Value>(&yaml).unwrap(),
+            serde_yaml::from_str::<serde_json::Value>(expected).unwrap()
+        );
+        assert_eq!(WarpRouteConfig::from_yaml(expected).unwrap(), config);
+    }
+
+    #[test]
+    fn test_build_rejects_missing_fields() {
+        let missing_token = chain(WarpRouteConfigBuilder::new(), "holesky")
+            .token_type(TokenType::Collateral)
+            .build();
+        assert!(matches!(
+            missing_token,
+            Err(ConfigError::MissingTokenField { field: "token", .. })
+        ));
+
+        let missing_mailbox = WarpRouteConfigBuilder::new()
+            .add_chain("holesky")
+            .token_type(TokenType::Synthetic)
+            .interchain_gas_paymaster(IGP)
+            .owner(OWNER)
+            .build();
+        assert!(matches!(
+            missing_mailbox,
+            Err(ConfigError::MissingField { chain, field: "mailbox" }) if chain == "holesky"
+        ));
+
+        let missing_vault = chain(WarpRouteConfigBuilder::new(), "holesky")
+            .token_type(TokenType::CollateralVault)
+            .build();
+        assert!(matches!(
+            missing_vault,
+            Err(ConfigError::MissingTokenField { field: "vault", .. })
+        ));
+
+        assert!(matches!(
+            WarpRouteConfigBuilder::new().owner(OWNER).build(),
+            Err(ConfigError::NoChainSelected { field: "owner" })
+        ));
+
+        let duplicate = chain(
+            chain(WarpRouteConfigBuilder::new(), "holesky").token_type(TokenType::Synthetic),
+            "holesky",
+        )
+        .token_type(TokenType::Synthetic)
+        .build();
+        assert!(matches!(duplicate, Err(ConfigError::DuplicateChain { .. })));
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+
+/// The name of a chain, as in the registry, e.g. `holesky`.
+///
+/// Names are trimmed and lowercased on construction, so `"Holesky "` and `"holesky"` are the
+/// same chain. Maps keyed by `ChainName` can be looked up with a `&str`, which must already be
+/// normalized to match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(transparent)]
+pub struct ChainName(String);
+
+impl ChainName {
+    pub fn new(name: impl AsRef<str>) -> Self {
+        Self(name.as_ref().trim().to_lowercase())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `name` follows the naming convention of the registry, i.e. only has lowercase
+    /// ASCII letters and digits, e.g. `arbitrumsepolia`.
+    pub fn is_registry_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl fmt::Display for ChainName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for ChainName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ChainName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ChainName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ChainName {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for ChainName {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl PartialEq<str> for ChainName {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ChainName {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_names_are_normalized() {
+        assert_eq!(ChainName::new("Holesky "), ChainName::new("holesky"));
+        assert_eq!(
+            ChainName::from(" TangleTestnet\n").as_str(),
+            "tangletestnet"
+        );
+        assert_eq!(ChainName::from("Sepolia").to_string(), "sepolia");
+
+        let mut chains = HashMap::new();
+        chains.insert(ChainName::new("Holesky "), 1);
+        chains.insert(ChainName::new("holesky"), 2);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains.get("holesky"), Some(&2));
+    }
+
+    #[test]
+    fn test_registry_names() {
+        for name in ["holesky", "tangletestnet", "chain1", "42"] {
+            assert!(ChainName::is_registry_name(name), "{name}");
+        }
+        for name in [
+            "",
+            "Holesky",
+            "holesky testnet",
+            "base-sepolia",
+            "chain_1",
+            "héllo",
+        ] {
+            assert!(!ChainName::is_registry_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_serde_normalizes_and_round_trips() {
+        let name: ChainName = serde_json::from_str("\"Holesky \"").unwrap();
+        assert_eq!(name, "holesky");
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"holesky\"");
+
+        let name: ChainName = serde_yaml::from_str("sepolia").unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<ChainName>(&serde_yaml::to_string(&name).unwrap()).unwrap(),
+            name
+        );
+    }
+}
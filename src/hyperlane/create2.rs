@@ -0,0 +1,119 @@
+use super::{ConfigError, WarpRouteConfig};
+use alloy_primitives::{Address, B256};
+use std::collections::HashMap;
+
+/// How the routers of a warp route are deployed with CREATE2, for predicting their addresses
+/// before they are deployed, e.g. to fund or approve them ahead of time.
+///
+/// A CREATE2 address is `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`
+/// (EIP-1014). The init code of a router embeds its constructor arguments, such as the mailbox
+/// and token of its chain, so its hash is given for each chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Create2Deployment {
+    /// The contract the routers are deployed through, e.g. a CREATE2 factory.
+    pub deployer: Address,
+    /// The salt of every router of the route. [`WarpRouteConfig::content_hash`] makes one that
+    /// differs between routes.
+    pub salt: B256,
+    /// The keccak256 hash of the init code of the router of each chain, by chain name.
+    pub init_code_hashes: HashMap<String, B256>,
+}
+
+impl Create2Deployment {
+    /// The address the router of each chain of `config` will be deployed at, by chain name.
+    ///
+    /// Only EVM chains have CREATE2 addresses, so every chain of the route must have an init
+    /// code hash.
+    pub fn predict_addresses(
+        &self,
+        config: &WarpRouteConfig,
+    ) -> Result<HashMap<String, Address>, ConfigError> {
+        config
+            .chain_names()
+            .into_iter()
+            .map(|chain| {
+                let init_code_hash = self.init_code_hashes.get(chain).ok_or_else(|| {
+                    ConfigError::MissingInitCodeHash {
+                        chain: chain.to_string(),
+                    }
+                })?;
+                let address = self.deployer.create2(self.salt, *init_code_hash);
+                Ok((chain.to_string(), address))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{address, b256, keccak256};
+
+    const CONFIG: &str = r#"
+    holesky:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "collateral"
+      token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    sepolia:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    "#;
+
+    #[test]
+    fn test_predict_addresses() {
+        let config = WarpRouteConfig::from_yaml(CONFIG).unwrap();
+        // Examples 1 and 5 of EIP-1014
+        let mut deployment = Create2Deployment {
+            deployer: Address::ZERO,
+            salt: B256::ZERO,
+            init_code_hashes: HashMap::from([
+                ("holesky".to_string(), keccak256([0x00])),
+                ("sepolia".to_string(), keccak256([0x00])),
+            ]),
+        };
+        let addresses = deployment.predict_addresses(&config).unwrap();
+        assert_eq!(
+            addresses,
+            HashMap::from([
+                (
+                    "holesky".to_string(),
+                    address!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38")
+                ),
+                (
+                    "sepolia".to_string(),
+                    address!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38")
+                ),
+            ])
+        );
+
+        deployment.deployer = address!("00000000000000000000000000000000deadbeef");
+        deployment.salt = b256!("00000000000000000000000000000000000000000000000000000000cafebabe");
+        deployment
+            .init_code_hashes
+            .insert("sepolia".to_string(), keccak256([0xde, 0xad, 0xbe, 0xef]));
+        let addresses = deployment.predict_addresses(&config).unwrap();
+        assert_eq!(
+            addresses["sepolia"],
+            address!("60f3f640a8508fC6a86d45DF051962668E1e8AC7")
+        );
+        assert_ne!(addresses["holesky"], addresses["sepolia"]);
+
+        deployment.init_code_hashes.remove("holesky");
+        assert!(matches!(
+            deployment.predict_addresses(&config),
+            Err(ConfigError::MissingInitCodeHash { chain }) if chain == "holesky"
+        ));
+    }
+}
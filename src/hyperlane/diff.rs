@@ -0,0 +1,258 @@
+use super::{ChainConfig, ChainName, CoreConfig, WarpRouteConfig};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single field that differs between two configs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    /// Dotted path of the field, e.g. `interchainSecurityModule.type`.
+    pub field: String,
+    /// The current value, `None` if the field is unset.
+    pub old: Option<Value>,
+    /// The desired value, `None` if the field is to be unset.
+    pub new: Option<Value>,
+}
+
+/// How one chain of a route differs between two configs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainDiff {
+    Added,
+    Removed,
+    Changed(Vec<FieldChange>),
+}
+
+/// The per-chain differences between two [`WarpRouteConfig`]s, see [`WarpRouteConfig::diff`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub chains: BTreeMap<ChainName, ChainDiff>,
+}
+
+impl ConfigDiff {
+    /// Whether the two configs are identical, i.e. there is nothing to apply.
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+}
+
+impl WarpRouteConfig {
+    /// The changes that turn `self`, e.g. the config read from chain, into `desired`.
+    pub fn diff(&self, desired: &WarpRouteConfig) -> ConfigDiff {
+        let mut chains = BTreeMap::new();
+        for (name, current) in &self.chains {
+            match desired.chains.get(name) {
+                Some(desired) => {
+                    let changes = current.diff(desired);
+                    if !changes.is_empty() {
+                        chains.insert(name.clone(), ChainDiff::Changed(changes));
+                    }
+                }
+                None => {
+                    chains.insert(name.clone(), ChainDiff::Removed);
+                }
+            }
+        }
+        for name in desired.chains.keys() {
+            if !self.chains.contains_key(name) {
+                chains.insert(name.clone(), ChainDiff::Added);
+            }
+        }
+        ConfigDiff { chains }
+    }
+}
+
+impl ChainConfig {
+    /// The fields that differ between `self` and `desired`, sorted by path.
+    pub fn diff(&self, desired: &ChainConfig) -> Vec<FieldChange> {
+        diff_serialized(self, desired)
+    }
+
+    /// The fields this config, as submitted for deployment, sets to something other than
+    /// `deployed`, the config read back from chain, has. Fields left unset here, such as the
+    /// `remoteRouters` the deployment enrolls, are not compared.
+    pub fn readback_mismatches(&self, deployed: &ChainConfig) -> Vec<FieldChange> {
+        deployed
+            .diff(self)
+            .into_iter()
+            .filter(|change| change.new.is_some())
+            .collect()
+    }
+}
+
+impl CoreConfig {
+    /// The fields that differ between `self` and `desired`, sorted by path.
+    pub fn diff(&self, desired: &CoreConfig) -> Vec<FieldChange> {
+        diff_serialized(self, desired)
+    }
+}
+
+/// Compares the serialized forms of `current` and `desired` field by field.
+pub fn diff_serialized<T: Serialize>(current: &T, desired: &T) -> Vec<FieldChange> {
+    // Serializing plain config structs into a `Value` can't fail
+    let current = serde_json::to_value(current).unwrap_or_default();
+    let desired = serde_json::to_value(desired).unwrap_or_default();
+    let mut changes = Vec::new();
+    diff_values(Some(&current), Some(&desired), "", &mut changes);
+    changes
+}
+
+/// Compares two documents, e.g. the YAML read from chain and the YAML to apply.
+pub fn diff_values(
+    current: Option<&Value>,
+    desired: Option<&Value>,
+    path: &str,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (current, desired) {
+        (Some(Value::Object(current)), Some(Value::Object(desired))) => {
+            let mut keys: Vec<&String> = current.keys().chain(desired.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                diff_values(current.get(key), desired.get(key), &path, changes);
+            }
+        }
+        // Addresses compare equal regardless of checksum casing
+        (Some(Value::String(current)), Some(Value::String(desired)))
+            if current.eq_ignore_ascii_case(desired) => {}
+        (current, desired) if current == desired => {}
+        (current, desired) => changes.push(FieldChange {
+            field: path.to_string(),
+            old: current.cloned(),
+            new: desired.cloned(),
+        }),
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(unset)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+        for (chain, diff) in &self.chains {
+            match diff {
+                ChainDiff::Added => writeln!(f, "+ {chain}")?,
+                ChainDiff::Removed => writeln!(f, "- {chain}")?,
+                ChainDiff::Changed(changes) => {
+                    writeln!(f, "~ {chain}")?;
+                    for change in changes {
+                        writeln!(f, "    {change}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperlane::InterchainSecurityModule;
+    use alloy_primitives::Address;
+
+    const CONFIG: &str = r#"
+    holesky:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "collateral"
+      token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    tangletestnet:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    "#;
+
+    #[test]
+    fn test_identical_configs_have_empty_diff() {
+        let config = WarpRouteConfig::from_yaml(CONFIG).unwrap();
+        let diff = config.diff(&config.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no changes");
+    }
+
+    #[test]
+    fn test_owner_only_diff() {
+        let current = WarpRouteConfig::from_yaml(CONFIG).unwrap();
+        let mut desired = current.clone();
+        let new_owner = Address::new([0x11; 20]);
+        desired.update_owner(new_owner);
+
+        let diff = current.diff(&desired);
+        assert_eq!(diff.chains.len(), 2);
+        for chain in ["holesky", "tangletestnet"] {
+            let ChainDiff::Changed(changes) = &diff.chains[chain] else {
+                panic!("{chain} should have changed");
+            };
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].field, "owner");
+            assert_eq!(
+                changes[0].new,
+                Some(serde_json::to_value(new_owner).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_ism_diff() {
+        let current = WarpRouteConfig::from_yaml(CONFIG).unwrap();
+        let mut desired = current.clone();
+        let holesky = desired.chains.get_mut("holesky").unwrap();
+        // Only the type changes, the relayer is kept as a field of the pausable ISM
+        holesky.interchain_security_module = InterchainSecurityModule::PausableIsm(
+            [(
+                "relayer".to_string(),
+                "0x742d35cc6634c0532925a3b844bc454e4438f44e".into(),
+            )]
+            .into(),
+        );
+        desired.chains.remove("tangletestnet");
+
+        let diff = current.diff(&desired);
+        assert_eq!(
+            diff.chains["holesky"],
+            ChainDiff::Changed(vec![FieldChange {
+                field: "interchainSecurityModule.type".to_string(),
+                old: Some("trustedRelayerIsm".into()),
+                new: Some("pausableIsm".into()),
+            }])
+        );
+        assert_eq!(diff.chains["tangletestnet"], ChainDiff::Removed);
+        assert_eq!(
+            diff.to_string(),
+            "~ holesky\n    interchainSecurityModule.type: \"trustedRelayerIsm\" -> \"pausableIsm\"\n- tangletestnet\n"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Whether a chain is a mainnet or a testnet, as its `isTestnet` chain metadata in the registry
+/// says.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    #[default]
+    Testnet,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("Unknown network `{0}`, expected `mainnet` or `testnet`")]
+pub struct UnknownNetwork(pub String);
+
+impl Network {
+    /// The environment variable holding the URL of the registry to use for this network.
+    pub fn registry_url_var(self) -> &'static str {
+        match self {
+            Network::Mainnet => "MAINNET_REGISTRY_URL",
+            Network::Testnet => "TESTNET_REGISTRY_URL",
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = UnknownNetwork;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(UnknownNetwork(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        })
+    }
+}
+
+/// The Hyperlane domain id of well-known chains, as listed in the chain metadata of the
+/// canonical registry, along with their network. For these EVM chains the domain id is the
+/// chain id.
+///
+/// `remoteRouters` and `destinationGas` are keyed by domain id, while our configs name chains,
+/// so this is what the two are correlated with.
+const KNOWN_DOMAINS: &[(&str, u32, Network)] = &[
+    ("ethereum", 1, Network::Mainnet),
+    ("optimism", 10, Network::Mainnet),
+    ("bsc", 56, Network::Mainnet),
+    ("gnosis", 100, Network::Mainnet),
+    ("polygon", 137, Network::Mainnet),
+    ("tangletestnet", 3799, Network::Testnet),
+    ("tangle", 5845, Network::Mainnet),
+    ("base", 8453, Network::Mainnet),
+    ("holesky", 17000, Network::Testnet),
+    ("arbitrum", 42161, Network::Mainnet),
+    ("avalanche", 43114, Network::Mainnet),
+    ("basesepolia", 84532, Network::Testnet),
+    ("arbitrumsepolia", 421614, Network::Testnet),
+    ("sepolia", 11155111, Network::Testnet),
+    ("optimismsepolia", 11155420, Network::Testnet),
+];
+
+/// The domain id of the chain named `name`, if it is a well-known chain.
+pub fn domain_id_for_chain(name: &str) -> Option<u32> {
+    KNOWN_DOMAINS
+        .iter()
+        .find(|(chain, ..)| *chain == name)
+        .map(|&(_, domain_id, _)| domain_id)
+}
+
+/// The name of the chain with the domain id `domain_id`, if it is a well-known chain.
+pub fn chain_for_domain_id(domain_id: u32) -> Option<&'static str> {
+    KNOWN_DOMAINS
+        .iter()
+        .find(|(_, id, _)| *id == domain_id)
+        .map(|&(chain, ..)| chain)
+}
+
+/// The network of the chain named `name`, if it is a well-known chain.
+pub fn network_for_chain(name: &str) -> Option<Network> {
+    KNOWN_DOMAINS
+        .iter()
+        .find(|(chain, ..)| *chain == name)
+        .map(|&(.., network)| network)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_chains_map_to_their_domain_ids() {
+        assert_eq!(domain_id_for_chain("holesky"), Some(17000));
+        assert_eq!(domain_id_for_chain("tangletestnet"), Some(3799));
+        assert_eq!(domain_id_for_chain("ethereum"), Some(1));
+        assert_eq!(chain_for_domain_id(3799), Some("tangletestnet"));
+        assert_eq!(chain_for_domain_id(11155111), Some("sepolia"));
+
+        for &(chain, domain_id, _) in KNOWN_DOMAINS {
+            assert_eq!(chain_for_domain_id(domain_id), Some(chain));
+            assert_eq!(domain_id_for_chain(chain), Some(domain_id));
+        }
+    }
+
+    #[test]
+    fn test_unknown_chains_have_no_domain_id() {
+        assert_eq!(domain_id_for_chain("not-a-chain"), None);
+        assert_eq!(domain_id_for_chain("Holesky"), None);
+        assert_eq!(chain_for_domain_id(999_999_999), None);
+        assert_eq!(network_for_chain("not-a-chain"), None);
+    }
+
+    #[test]
+    fn test_networks() {
+        assert_eq!(network_for_chain("tangletestnet"), Some(Network::Testnet));
+        assert_eq!(network_for_chain("holesky"), Some(Network::Testnet));
+        assert_eq!(network_for_chain("tangle"), Some(Network::Mainnet));
+        assert_eq!(network_for_chain("ethereum"), Some(Network::Mainnet));
+
+        assert_eq!("mainnet".parse(), Ok(Network::Mainnet));
+        assert_eq!(Network::Testnet.to_string(), "testnet");
+        assert_eq!(
+            "devnet".parse::<Network>(),
+            Err(UnknownNetwork("devnet".to_string()))
+        );
+    }
+}
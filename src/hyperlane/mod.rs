@@ -1,34 +1,123 @@
-use alloy_primitives::Address;
+use alloy_primitives::{keccak256, Address, B256, U256};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+pub mod address;
+pub mod builder;
+pub mod chain_name;
+pub mod create2;
+pub mod diff;
+pub mod domain;
+pub mod output;
+pub mod registry;
+pub mod schema;
+pub mod validators;
+
+pub use address::ChainAddress;
+pub use builder::WarpRouteConfigBuilder;
+pub use chain_name::ChainName;
+pub use create2::Create2Deployment;
+pub use domain::{chain_for_domain_id, domain_id_for_chain, network_for_chain, Network};
+pub use registry::Registry;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct DefaultHook {
     address: Address,
     #[serde(rename = "type")]
-    hook_type: String,
+    hook_type: HookType,
+}
+
+impl DefaultHook {
+    pub fn new(address: Address, hook_type: HookType) -> Self {
+        Self { address, hook_type }
+    }
+}
+
+/// The kinds of post-dispatch hook the `hyperlane` CLI can deploy.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HookType {
+    MerkleTreeHook,
+    ProtocolFee,
+    InterchainGasPaymaster,
+    AggregationHook,
+    PausableHook,
+    DomainRoutingHook,
+    FallbackRoutingHook,
+    OpStackHook,
+    /// A hook type this crate doesn't know about yet, kept as written.
+    #[serde(untagged)]
+    Other(String),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct DefaultIsm {
     address: Address,
-    relayer: Address,
+    /// Only set for ISM types that use a relayer, see [`CoreConfig::validate`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    relayer: Option<Address>,
     #[serde(rename = "type")]
-    ism_type: String,
+    ism_type: IsmType,
+}
+
+impl DefaultIsm {
+    pub fn new(address: Address, relayer: impl Into<Option<Address>>, ism_type: IsmType) -> Self {
+        Self {
+            address,
+            relayer: relayer.into(),
+            ism_type,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct RequiredHook {
     address: Address,
-    beneficiary: Address,
-    #[serde(rename = "maxProtocolFee")]
-    max_protocol_fee: String,
-    owner: Address,
-    #[serde(rename = "protocolFee")]
-    protocol_fee: String,
+    /// The fields up to `hook_type` are only set for [`HookType::ProtocolFee`] hooks, see
+    /// [`CoreConfig::validate`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    beneficiary: Option<Address>,
+    #[serde(
+        rename = "maxProtocolFee",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    max_protocol_fee: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<Address>,
+    #[serde(
+        rename = "protocolFee",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    protocol_fee: Option<String>,
     #[serde(rename = "type")]
-    hook_type: String,
+    hook_type: HookType,
+}
+
+impl RequiredHook {
+    /// `max_protocol_fee` and `protocol_fee` are amounts in wei, as decimal strings.
+    pub fn new(
+        address: Address,
+        hook_type: HookType,
+        owner: Address,
+        beneficiary: Address,
+        protocol_fee: impl Into<String>,
+        max_protocol_fee: impl Into<String>,
+    ) -> Self {
+        Self {
+            address,
+            beneficiary: Some(beneficiary),
+            max_protocol_fee: Some(max_protocol_fee.into()),
+            owner: Some(owner),
+            protocol_fee: Some(protocol_fee.into()),
+            hook_type,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -42,11 +131,168 @@ pub struct CoreConfig {
     required_hook: RequiredHook,
 }
 
+/// The ISM securing a chain of a warp route, keyed by its `type`.
+///
+/// Aggregation and routing ISMs nest the ISMs they combine. The fields of ISM types without a
+/// config of their own are kept as written, but an unknown `type` fails to parse, as its fields
+/// can't be told apart from those of the module around it.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub struct InterchainSecurityModule {
-    relayer: Address,
-    #[serde(rename = "type")]
-    ism_type: String,
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InterchainSecurityModule {
+    TrustedRelayerIsm(TrustedRelayerIsmConfig),
+    MerkleRootMultisigIsm(MultisigIsmConfig),
+    MessageIdMultisigIsm(MultisigIsmConfig),
+    StorageMerkleRootMultisigIsm(MultisigIsmConfig),
+    StorageMessageIdMultisigIsm(MultisigIsmConfig),
+    AggregationIsm(AggregationIsmConfig),
+    StorageAggregationIsm(AggregationIsmConfig),
+    RoutingIsm(RoutingIsmConfig),
+    DomainRoutingIsm(RoutingIsmConfig),
+    FallbackRoutingIsm(RoutingIsmConfig),
+    DefaultFallbackRoutingIsm(RoutingIsmConfig),
+    PausableIsm(BTreeMap<String, serde_json::Value>),
+    OpStackIsm(BTreeMap<String, serde_json::Value>),
+    TestIsm(BTreeMap<String, serde_json::Value>),
+}
+
+impl InterchainSecurityModule {
+    /// A trusted relayer ISM, which accepts the messages `relayer` delivers.
+    pub fn trusted_relayer(relayer: impl Into<ChainAddress>) -> Self {
+        Self::TrustedRelayerIsm(TrustedRelayerIsmConfig {
+            relayer: relayer.into(),
+            extra: BTreeMap::new(),
+        })
+    }
+
+    pub fn ism_type(&self) -> IsmType {
+        match self {
+            Self::TrustedRelayerIsm(_) => IsmType::TrustedRelayerIsm,
+            Self::MerkleRootMultisigIsm(_) => IsmType::MerkleRootMultisigIsm,
+            Self::MessageIdMultisigIsm(_) => IsmType::MessageIdMultisigIsm,
+            Self::StorageMerkleRootMultisigIsm(_) => IsmType::StorageMerkleRootMultisigIsm,
+            Self::StorageMessageIdMultisigIsm(_) => IsmType::StorageMessageIdMultisigIsm,
+            Self::AggregationIsm(_) => IsmType::AggregationIsm,
+            Self::StorageAggregationIsm(_) => IsmType::StorageAggregationIsm,
+            Self::RoutingIsm(_) => IsmType::RoutingIsm,
+            Self::DomainRoutingIsm(_) => IsmType::DomainRoutingIsm,
+            Self::FallbackRoutingIsm(_) => IsmType::FallbackRoutingIsm,
+            Self::DefaultFallbackRoutingIsm(_) => IsmType::DefaultFallbackRoutingIsm,
+            Self::PausableIsm(_) => IsmType::PausableIsm,
+            Self::OpStackIsm(_) => IsmType::OpStackIsm,
+            Self::TestIsm(_) => IsmType::TestIsm,
+        }
+    }
+
+    /// Whether this is one of the multisig ISMs, which verify messages against `validators`.
+    pub fn is_multisig(&self) -> bool {
+        self.multisig().is_some()
+    }
+
+    /// The relayer of a trusted relayer ISM.
+    pub fn relayer(&self) -> Option<&ChainAddress> {
+        match self {
+            Self::TrustedRelayerIsm(ism) => Some(&ism.relayer),
+            _ => None,
+        }
+    }
+
+    /// The validators of a multisig ISM, empty for other types.
+    pub fn validators(&self) -> &[Address] {
+        self.multisig()
+            .map_or(&[], |multisig| multisig.validators.as_slice())
+    }
+
+    /// The ISMs an aggregation ISM combines, empty for other types.
+    pub fn modules(&self) -> &[InterchainSecurityModule] {
+        match self {
+            Self::AggregationIsm(ism) | Self::StorageAggregationIsm(ism) => &ism.modules,
+            _ => &[],
+        }
+    }
+
+    fn multisig(&self) -> Option<&MultisigIsmConfig> {
+        match self {
+            Self::MerkleRootMultisigIsm(ism)
+            | Self::MessageIdMultisigIsm(ism)
+            | Self::StorageMerkleRootMultisigIsm(ism)
+            | Self::StorageMessageIdMultisigIsm(ism) => Some(ism),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TrustedRelayerIsmConfig {
+    pub relayer: ChainAddress,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MultisigIsmConfig {
+    #[serde(default)]
+    pub validators: Vec<Address>,
+    /// How many of `validators` must sign a message.
+    pub threshold: u8,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AggregationIsmConfig {
+    pub modules: Vec<InterchainSecurityModule>,
+    /// How many of `modules` must verify a message.
+    pub threshold: u8,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RoutingIsmConfig {
+    pub owner: ChainAddress,
+    /// The ISM verifying the messages from each origin chain, by chain name.
+    pub domains: BTreeMap<String, InterchainSecurityModule>,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The kinds of ISM the `hyperlane` CLI can deploy.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IsmType {
+    TrustedRelayerIsm,
+    MerkleRootMultisigIsm,
+    MessageIdMultisigIsm,
+    StorageMerkleRootMultisigIsm,
+    StorageMessageIdMultisigIsm,
+    AggregationIsm,
+    StorageAggregationIsm,
+    RoutingIsm,
+    DomainRoutingIsm,
+    FallbackRoutingIsm,
+    DefaultFallbackRoutingIsm,
+    PausableIsm,
+    OpStackIsm,
+    TestIsm,
+    /// An ISM type this crate doesn't know about yet, kept as written.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl IsmType {
+    pub fn is_multisig(&self) -> bool {
+        matches!(
+            self,
+            IsmType::MerkleRootMultisigIsm
+                | IsmType::MessageIdMultisigIsm
+                | IsmType::StorageMerkleRootMultisigIsm
+                | IsmType::StorageMessageIdMultisigIsm
+        )
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -57,7 +303,9 @@ pub enum TokenType {
     SyntheticUri,
     Collateral,
     CollateralVault,
+    #[serde(rename = "xERC20", alias = "xErc20")]
     XErc20,
+    #[serde(rename = "xERC20Lockbox", alias = "xErc20Lockbox")]
     XErc20Lockbox,
     CollateralFiat,
     FastCollateral,
@@ -66,174 +314,2388 @@ pub enum TokenType {
     NativeScaled,
 }
 
+impl TokenType {
+    pub const ALL: [TokenType; 12] = [
+        TokenType::Synthetic,
+        TokenType::FastSynthetic,
+        TokenType::SyntheticUri,
+        TokenType::Collateral,
+        TokenType::CollateralVault,
+        TokenType::XErc20,
+        TokenType::XErc20Lockbox,
+        TokenType::CollateralFiat,
+        TokenType::FastCollateral,
+        TokenType::CollateralUri,
+        TokenType::Native,
+        TokenType::NativeScaled,
+    ];
+
+    /// Whether routes of this type wrap an existing token, whose address must be configured as
+    /// `token`.
+    pub fn requires_token(&self) -> bool {
+        match self {
+            TokenType::Collateral
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::XErc20 => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::SyntheticUri
+            | TokenType::CollateralVault
+            | TokenType::XErc20Lockbox
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+
+    /// Whether routes of this type deposit into an ERC-4626 vault, configured as `vault`.
+    pub fn requires_vault(&self) -> bool {
+        match self {
+            TokenType::CollateralVault => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::SyntheticUri
+            | TokenType::Collateral
+            | TokenType::XErc20
+            | TokenType::XErc20Lockbox
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+
+    /// Whether routes of this type go through an xERC20 lockbox, configured as `lockbox`.
+    pub fn requires_lockbox(&self) -> bool {
+        match self {
+            TokenType::XErc20Lockbox => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::SyntheticUri
+            | TokenType::Collateral
+            | TokenType::CollateralVault
+            | TokenType::XErc20
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+
+    /// Whether routes of this type bridge an xERC20 token, whose limits must be configured as
+    /// `xERC20`.
+    pub fn requires_xerc20_limits(&self) -> bool {
+        match self {
+            TokenType::XErc20 | TokenType::XErc20Lockbox => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::SyntheticUri
+            | TokenType::Collateral
+            | TokenType::CollateralVault
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+
+    /// Whether routes of this type scale the native token's amounts, configured as `decimals`
+    /// and `scale`.
+    pub fn requires_scale(&self) -> bool {
+        match self {
+            TokenType::NativeScaled => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::SyntheticUri
+            | TokenType::Collateral
+            | TokenType::CollateralVault
+            | TokenType::XErc20
+            | TokenType::XErc20Lockbox
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::Native => false,
+        }
+    }
+
+    /// Whether routes of this type carry NFTs (ERC-721) rather than fungible tokens.
+    pub fn is_nft(&self) -> bool {
+        match self {
+            TokenType::SyntheticUri | TokenType::CollateralUri => true,
+            TokenType::Synthetic
+            | TokenType::FastSynthetic
+            | TokenType::Collateral
+            | TokenType::CollateralVault
+            | TokenType::XErc20
+            | TokenType::XErc20Lockbox
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+
+    /// Whether routes of this type mint a new token on the chain rather than holding an
+    /// existing one.
+    pub fn is_synthetic(&self) -> bool {
+        match self {
+            TokenType::Synthetic | TokenType::FastSynthetic | TokenType::SyntheticUri => true,
+            TokenType::Collateral
+            | TokenType::CollateralVault
+            | TokenType::XErc20
+            | TokenType::XErc20Lockbox
+            | TokenType::CollateralFiat
+            | TokenType::FastCollateral
+            | TokenType::CollateralUri
+            | TokenType::Native
+            | TokenType::NativeScaled => false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ChainConfig {
     #[serde(rename = "interchainSecurityModule")]
     interchain_security_module: InterchainSecurityModule,
     #[serde(rename = "isNft")]
     is_nft: bool,
-    mailbox: Address,
+    mailbox: ChainAddress,
     #[serde(rename = "interchainGasPaymaster")]
-    interchain_gas_paymaster: Address,
-    owner: Address,
+    interchain_gas_paymaster: ChainAddress,
+    owner: ChainAddress,
     #[serde(rename = "type")]
     token_type: TokenType,
+    /// Explicit `null`s, as some config generators emit for synthetic chains, are read as `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<ChainAddress>,
+    /// The ERC-4626 vault of a [`TokenType::CollateralVault`] route.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vault: Option<ChainAddress>,
+    /// The lockbox of a [`TokenType::XErc20Lockbox`] route.
     #[serde(skip_serializing_if = "Option::is_none")]
-    token: Option<Address>,
+    lockbox: Option<ChainAddress>,
+    /// The limits of a [`TokenType::XErc20`] or [`TokenType::XErc20Lockbox`] route.
+    #[serde(rename = "xERC20", default, skip_serializing_if = "Option::is_none")]
+    xerc20: Option<XErc20Config>,
+    /// The decimals of the token, required by [`TokenType::NativeScaled`] routes. Other routes
+    /// may set it too, as `hyperlane warp init` does with the token's metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decimals: Option<u8>,
+    /// The factor a [`TokenType::NativeScaled`] route scales amounts by. Other routes may only
+    /// set it to [`DEFAULT_SCALE`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scale: Option<u64>,
+    /// The routers of the route on the other chains, by domain id, as `hyperlane warp read`
+    /// reports them.
+    #[serde(rename = "remoteRouters", skip_serializing_if = "Option::is_none")]
+    remote_routers: Option<BTreeMap<String, RemoteRouter>>,
+    /// The gas amount, as a decimal string, paid for handling a transfer on each destination
+    /// domain, by domain id.
+    #[serde(rename = "destinationGas", skip_serializing_if = "Option::is_none")]
+    destination_gas: Option<BTreeMap<String, String>>,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The router of a warp route on a remote domain.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RemoteRouter {
+    /// The router as a 32-byte hex string, the form Hyperlane uses for addresses on every
+    /// protocol, so EVM addresses come left-padded with zeroes.
+    address: String,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl RemoteRouter {
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The router as an EVM address, unless it isn't one, i.e. its first 12 bytes aren't zero.
+    pub fn evm_address(&self) -> Option<Address> {
+        let word: B256 = self.address.parse().ok()?;
+        word[..12]
+            .iter()
+            .all(|byte| *byte == 0)
+            .then(|| Address::from_word(word))
+    }
+}
+
+/// The xERC20 settings of a route bridging an xERC20 token. The token itself is the route's
+/// `token`, or the token of its `lockbox`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct XErc20Config {
+    /// The limits of the route's own router as a bridge of the token.
+    #[serde(rename = "warpRouteLimits")]
+    warp_route_limits: XErc20Limits,
+    /// Other bridges of the token whose limits the route sets.
+    #[serde(
+        rename = "extraBridges",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    extra_bridges: Vec<XErc20Bridge>,
+    /// Fields not modelled above, kept so that configs round-trip without data loss.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl XErc20Config {
+    pub fn new(warp_route_limits: XErc20Limits) -> Self {
+        Self {
+            warp_route_limits,
+            extra_bridges: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Also sets the limits of the bridge behind `lockbox`.
+    pub fn with_extra_bridge(
+        mut self,
+        lockbox: impl Into<ChainAddress>,
+        limits: XErc20Limits,
+    ) -> Self {
+        self.extra_bridges.push(XErc20Bridge {
+            lockbox: lockbox.into(),
+            limits,
+        });
+        self
+    }
+
+    pub fn warp_route_limits(&self) -> &XErc20Limits {
+        &self.warp_route_limits
+    }
+
+    pub fn extra_bridges(&self) -> &[XErc20Bridge] {
+        &self.extra_bridges
+    }
+}
+
+/// A bridge of an xERC20 token other than the route itself.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct XErc20Bridge {
+    pub lockbox: ChainAddress,
+    pub limits: XErc20Limits,
+}
+
+/// How much of an xERC20 token a bridge may mint and burn, in the token's smallest unit as
+/// decimal strings, depending on the token's implementation.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum XErc20Limits {
+    /// The ERC-7281 limits: a buffer refilling at a constant rate.
+    Standard {
+        #[serde(rename = "bufferCap")]
+        buffer_cap: String,
+        #[serde(rename = "rateLimitPerSecond")]
+        rate_limit_per_second: String,
+    },
+    /// Velodrome's xERC20: separate mint and burn limits.
+    Velo { mint: String, burn: String },
+}
+
+/// The `scale` of routes that don't scale amounts.
+pub const DEFAULT_SCALE: u64 = 1;
+
+impl ChainConfig {
+    pub fn token_type(&self) -> &TokenType {
+        &self.token_type
+    }
+
+    pub fn token(&self) -> Option<&ChainAddress> {
+        self.token.as_ref()
+    }
+
+    pub fn owner(&self) -> &ChainAddress {
+        &self.owner
+    }
+
+    pub fn mailbox(&self) -> &ChainAddress {
+        &self.mailbox
+    }
+
+    pub fn decimals(&self) -> Option<u8> {
+        self.decimals
+    }
+
+    pub fn scale(&self) -> Option<u64> {
+        self.scale
+    }
+
+    pub fn xerc20(&self) -> Option<&XErc20Config> {
+        self.xerc20.as_ref()
+    }
+
+    pub fn remote_routers(&self) -> Option<&BTreeMap<String, RemoteRouter>> {
+        self.remote_routers.as_ref()
+    }
+
+    pub fn destination_gas(&self) -> Option<&BTreeMap<String, String>> {
+        self.destination_gas.as_ref()
+    }
+
+    /// The router of the route on the chain named `chain`, if `chain` is a well-known chain (see
+    /// [`domain_id_for_chain`]) listed in `remoteRouters`.
+    pub fn remote_router_for_chain(&self, chain: &str) -> Option<&RemoteRouter> {
+        let domain_id = domain_id_for_chain(chain)?;
+        self.remote_routers.as_ref()?.get(&domain_id.to_string())
+    }
+
+    /// Checks that the token type specific fields are set exactly when the token type uses them,
+    /// and that `isNft` agrees with the token type.
+    pub fn validate(&self, chain: &str) -> Result<(), ConfigError> {
+        if self.is_nft != self.token_type.is_nft() {
+            return Err(ConfigError::NftFlagMismatch {
+                chain: chain.to_string(),
+                token_type: self.token_type.clone(),
+                is_nft: self.is_nft,
+            });
+        }
+
+        let fields = [
+            (
+                "vault",
+                self.vault.is_some(),
+                self.token_type.requires_vault(),
+            ),
+            (
+                "lockbox",
+                self.lockbox.is_some(),
+                self.token_type.requires_lockbox(),
+            ),
+            (
+                "xERC20",
+                self.xerc20.is_some(),
+                self.token_type.requires_xerc20_limits(),
+            ),
+        ];
+        for (field, is_set, is_required) in fields {
+            match (is_set, is_required) {
+                (false, true) => {
+                    return Err(ConfigError::MissingTokenField {
+                        chain: chain.to_string(),
+                        field,
+                    })
+                }
+                (true, false) => {
+                    return Err(ConfigError::UnexpectedTokenField {
+                        chain: chain.to_string(),
+                        field,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        if self.token_type.requires_scale() {
+            let fields = [
+                ("decimals", self.decimals.is_some()),
+                ("scale", self.scale.is_some()),
+            ];
+            if let Some((field, _)) = fields.into_iter().find(|(_, is_set)| !is_set) {
+                return Err(ConfigError::MissingTokenField {
+                    chain: chain.to_string(),
+                    field,
+                });
+            }
+        } else if self.scale.is_some_and(|scale| scale != DEFAULT_SCALE) {
+            return Err(ConfigError::UnexpectedTokenField {
+                chain: chain.to_string(),
+                field: "scale",
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "BTreeMap<String, ChainConfig>")]
 pub struct WarpRouteConfig {
+    /// Keyed in name order, so that iterating the chains, and serializing the config, is
+    /// reproducible.
     #[serde(flatten)]
-    chains: HashMap<String, ChainConfig>,
+    chains: BTreeMap<ChainName, ChainConfig>,
+}
+
+impl TryFrom<BTreeMap<String, ChainConfig>> for WarpRouteConfig {
+    type Error = ConfigError;
+
+    /// Normalizes the chain names, rejecting names that only differ in case or whitespace.
+    fn try_from(configs: BTreeMap<String, ChainConfig>) -> Result<Self, ConfigError> {
+        let mut chains = BTreeMap::new();
+        for (name, config) in configs {
+            let name = ChainName::new(name);
+            if chains.contains_key(&name) {
+                return Err(ConfigError::DuplicateChain {
+                    name: name.to_string(),
+                });
+            }
+            chains.insert(name, config);
+        }
+        Ok(Self { chains })
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
-    #[error("JSON deserialization error: {0}")]
-    JsonDeserializationError(#[from] serde_json::Error),
-    #[error("YAML deserialization error: {0}")]
-    YamlDeserializationError(#[from] serde_yaml::Error),
+    #[error("{}JSON deserialization error: {source}", context_prefix(context))]
+    JsonDeserializationError {
+        source: serde_json::Error,
+        /// What was being parsed, see [`ConfigError::with_context`].
+        context: Option<String>,
+    },
+    #[error("{}YAML deserialization error: {source}", context_prefix(context))]
+    YamlDeserializationError {
+        source: serde_yaml::Error,
+        /// What was being parsed, see [`ConfigError::with_context`].
+        context: Option<String>,
+    },
     #[error("Invalid UTF-8")]
     InvalidUtf8,
+    #[error("The config payload is {size} bytes, over the limit of {limit} bytes")]
+    PayloadTooLarge { size: usize, limit: usize },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Runner error: {0}")]
+    Runner(#[from] crate::runner::RunnerError),
+    #[error("Chain `{name}` is configured more than once")]
+    DuplicateChain { name: String },
+    #[error("Chain `{chain}` is missing required field `{field}`")]
+    MissingField { chain: String, field: &'static str },
+    #[error("`{field}` was set before any chain was added")]
+    NoChainSelected { field: &'static str },
+    #[error("Chain `{chain}` is missing `{field}`, which its token type requires")]
+    MissingTokenField { chain: String, field: &'static str },
+    #[error("Chain `{chain}` sets `{field}`, which its token type does not use")]
+    UnexpectedTokenField { chain: String, field: &'static str },
+    #[error(
+        "Chain `{chain}` sets `isNft: {is_nft}`, which contradicts its token type {token_type:?}"
+    )]
+    NftFlagMismatch {
+        chain: String,
+        token_type: TokenType,
+        is_nft: bool,
+    },
+    #[error("A warp route needs at least two chains, but the config has {count}")]
+    InsufficientChains { count: usize },
+    #[error("The route mixes NFT chains {nft_chains:?} with fungible chains {fungible_chains:?}")]
+    MixedNftRoute {
+        nft_chains: Vec<String>,
+        fungible_chains: Vec<String>,
+    },
+    #[error("Chain `{chain}` has unknown field `{field}`")]
+    UnknownField { chain: String, field: String },
+    #[error("Chain `{chain}` has no init code hash to predict its CREATE2 address from")]
+    MissingInitCodeHash { chain: String },
+    #[error("`{name}` is not a registry chain name, which only have lowercase letters and digits")]
+    InvalidChainName { name: String },
+    #[error("The config has no chain `{chain}`")]
+    MissingChain { chain: String },
+    #[error("The domain id of chain `{chain}` is unknown")]
+    UnknownDomain { chain: String },
+    #[error("The RPC URL of chain `{chain}` is not an HTTP URL: `{url}`")]
+    InvalidRpcUrl { chain: String, url: String },
+    #[error("The command output contains no config")]
+    NoConfigInOutput,
+    #[error("`{field}` must be an amount in wei, not `{amount}`")]
+    InvalidAmount { field: &'static str, amount: String },
+    #[error("The default trusted relayer ISM has no relayer")]
+    ZeroRelayer,
+    #[error("The protocol fee hook's beneficiary is the zero address, which would burn the fees")]
+    ZeroBeneficiary,
+    #[error(
+        "The protocol fee of {protocol_fee} wei exceeds the maximum of {max_protocol_fee} wei"
+    )]
+    ProtocolFeeExceedsMax {
+        protocol_fee: U256,
+        max_protocol_fee: U256,
+    },
+    #[error("The required hook is missing `{field}`, which its type requires")]
+    MissingHookField { field: &'static str },
+    #[error("Relayer {relayer} of the ISM on `{chain}` is not allowed")]
+    UnauthorizedRelayer {
+        chain: String,
+        relayer: ChainAddress,
+    },
+    /// Only a warning unless the caller opts into rejecting it, see
+    /// [`WarpRouteConfig::validate_distinct_mailboxes`].
+    #[error("Chains {chains:?} share the mailbox {mailbox}")]
+    DuplicateMailboxAcrossChains {
+        mailbox: ChainAddress,
+        chains: Vec<String>,
+    },
+    #[error("Validator {validator} has not announced itself on `{chain}`")]
+    ValidatorNotAnnounced { chain: String, validator: Address },
+    #[error("Failed to read the validators announced on `{chain}`: {message}")]
+    ValidatorLookup { chain: String, message: String },
+    #[error("error in '{}': {source}", path.display())]
+    InFile {
+        path: PathBuf,
+        source: Box<ConfigError>,
+    },
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(source: serde_json::Error) -> Self {
+        ConfigError::JsonDeserializationError {
+            source,
+            context: None,
+        }
+    }
+}
+
+impl From<serde_yaml::Error> for ConfigError {
+    fn from(source: serde_yaml::Error) -> Self {
+        ConfigError::YamlDeserializationError {
+            source,
+            context: None,
+        }
+    }
+}
+
+fn context_prefix(context: &Option<String>) -> String {
+    match context {
+        Some(context) => format!("failed parsing {context}: "),
+        None => String::new(),
+    }
+}
+
+impl ConfigError {
+    /// Attaches the file the error came from, so it can be told apart from other config files.
+    pub fn with_path(self, path: &Path) -> ConfigError {
+        ConfigError::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Labels a deserialization error with what was being parsed, e.g. "chain `holesky`", which
+    /// its message then starts with. Other errors are returned as they are.
+    pub fn with_context(self, label: impl Into<String>) -> ConfigError {
+        match self {
+            ConfigError::JsonDeserializationError { source, .. } => {
+                ConfigError::JsonDeserializationError {
+                    source,
+                    context: Some(label.into()),
+                }
+            }
+            ConfigError::YamlDeserializationError { source, .. } => {
+                ConfigError::YamlDeserializationError {
+                    source,
+                    context: Some(label.into()),
+                }
+            }
+            e => e,
+        }
+    }
+}
+
+/// The first of `chains` whose config doesn't deserialize, to tell which chain an error parsing
+/// a whole warp route config came from.
+fn failing_chain<'de, V: serde::Deserializer<'de>>(chains: BTreeMap<String, V>) -> Option<String> {
+    chains
+        .into_iter()
+        .find_map(|(chain, config)| ChainConfig::deserialize(config).is_err().then_some(chain))
+}
+
+/// Whether a config file should be parsed as JSON or YAML, going by its extension.
+enum ConfigFormat {
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Anything without a `.json` extension is treated as YAML, the format the `hyperlane` CLI
+    /// writes by default.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
 }
 
+/// The fields of a chain in a `hyperlane` warp route config that [`ChainConfig`] keeps without
+/// modelling them. Strict parsing rejects any other field.
+const UNMODELLED_CHAIN_FIELDS: &[&str] = &[
+    "contractVersion",
+    "foreignDeployment",
+    "gas",
+    "hook",
+    "name",
+    "proxyAdmin",
+    "symbol",
+    "totalSupply",
+];
+
 impl WarpRouteConfig {
+    /// A JSON Schema of warp route configs, for validating and completing them in editors.
+    ///
+    /// It describes what [`WarpRouteConfig::from_json`] accepts, except for the checks of
+    /// [`WarpRouteConfig::validate`] that span several fields, such as those of
+    /// [`ChainConfig::validate`].
+    pub fn json_schema() -> serde_json::Value {
+        schema::warp_route_config()
+    }
+
+    /// Errors in the config of a chain are labelled with the chain, see
+    /// [`ConfigError::with_context`].
     pub fn from_json(json: &str) -> Result<Self, ConfigError> {
-        serde_json::from_str(json).map_err(ConfigError::from)
+        serde_json::from_str(json).map_err(|e| {
+            let chain = serde_json::from_str::<BTreeMap<String, serde_json::Value>>(json)
+                .ok()
+                .and_then(failing_chain);
+            match chain {
+                Some(chain) => ConfigError::from(e).with_context(format!("chain `{chain}`")),
+                None => e.into(),
+            }
+        })
     }
 
+    /// Errors in the config of a chain are labelled with the chain, see
+    /// [`ConfigError::with_context`].
     pub fn from_yaml(yaml: &str) -> Result<Self, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(ConfigError::from)
+        serde_yaml::from_str(yaml).map_err(|e| {
+            let chain = serde_yaml::from_str::<BTreeMap<String, serde_yaml::Value>>(yaml)
+                .ok()
+                .and_then(failing_chain);
+            match chain {
+                Some(chain) => ConfigError::from(e).with_context(format!("chain `{chain}`")),
+                None => e.into(),
+            }
+        })
+    }
+
+    /// Like [`WarpRouteConfig::from_yaml`], parsing the YAML, or JSON, read from `reader`
+    /// without first collecting it into a string.
+    ///
+    /// Errors aren't labelled with the chain they come from, which would take a second pass over
+    /// the input.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, ConfigError> {
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+
+    /// Like [`WarpRouteConfig::from_json`], parsing the JSON as it is read from `reader`, see
+    /// [`WarpRouteConfig::from_reader`].
+    pub fn from_json_reader<R: Read>(reader: R) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Like [`WarpRouteConfig::from_reader`], for an async `reader`. The input is buffered
+    /// before it is parsed, as `serde_yaml` has no incremental parser.
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Self, ConfigError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_yaml::from_slice(&bytes)?)
+    }
+
+    /// Like [`WarpRouteConfig::from_json`], but fails with [`ConfigError::UnknownField`] on
+    /// fields that aren't part of a `hyperlane` warp route config, e.g. misspelled ones.
+    pub fn from_json_strict(json: &str) -> Result<Self, ConfigError> {
+        let config = Self::from_json(json)?;
+        config.deny_unknown_fields()?;
+        Ok(config)
+    }
+
+    /// Like [`WarpRouteConfig::from_yaml`], but fails with [`ConfigError::UnknownField`] on
+    /// fields that aren't part of a `hyperlane` warp route config, e.g. misspelled ones.
+    pub fn from_yaml_strict(yaml: &str) -> Result<Self, ConfigError> {
+        let config = Self::from_yaml(yaml)?;
+        config.deny_unknown_fields()?;
+        Ok(config)
+    }
+
+    /// The lenient parsers keep unknown fields in [`ChainConfig::extra`]; strict parsing only
+    /// allows those listed in [`UNMODELLED_CHAIN_FIELDS`] there.
+    fn deny_unknown_fields(&self) -> Result<(), ConfigError> {
+        for chain in self.chain_names() {
+            let unknown = self.chains[chain]
+                .extra
+                .keys()
+                .find(|field| !UNMODELLED_CHAIN_FIELDS.contains(&field.as_str()));
+            if let Some(field) = unknown {
+                return Err(ConfigError::UnknownField {
+                    chain: chain.to_string(),
+                    field: field.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, ConfigError> {
+        serde_json::to_string(self).map_err(ConfigError::from)
+    }
+
+    pub fn to_json_pretty(&self) -> Result<String, ConfigError> {
+        serde_json::to_string_pretty(self).map_err(ConfigError::from)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, ConfigError> {
+        serde_yaml::to_string(self).map_err(ConfigError::from)
+    }
+
+    /// Like [`WarpRouteConfig::to_yaml`], but every address that appears more than once is
+    /// written out once, as an anchor (`&addr0`), and referenced by alias (`*addr0`) elsewhere,
+    /// so that e.g. an owner shared by every chain can't diverge between copies.
+    pub fn to_yaml_with_anchors(&self) -> Result<String, ConfigError> {
+        let yaml = self.to_yaml()?;
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for line in yaml.lines() {
+            if let Some((_, value)) = split_scalar_line(line) {
+                if unquote(value).parse::<ChainAddress>().is_ok() {
+                    *counts.entry(value).or_default() += 1;
+                }
+            }
+        }
+
+        let mut anchors: HashMap<&str, String> = HashMap::new();
+        let mut output = String::with_capacity(yaml.len());
+        for line in yaml.lines() {
+            match split_scalar_line(line) {
+                Some((prefix, value)) if counts.get(value).is_some_and(|&count| count > 1) => {
+                    match anchors.get(value) {
+                        Some(anchor) => output.push_str(&format!("{prefix}*{anchor}")),
+                        None => {
+                            let anchor = format!("addr{}", anchors.len());
+                            output.push_str(&format!("{prefix}&{anchor} {value}"));
+                            anchors.insert(value, anchor);
+                        }
+                    }
+                }
+                _ => output.push_str(line),
+            }
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    /// Loads a config from a `.json`, `.yaml` or `.yml` file.
+    ///
+    /// Errors are wrapped in [`ConfigError::InFile`] to name the file that failed.
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let load = || {
+            let contents = std::fs::read_to_string(path)?;
+            match ConfigFormat::from_path(path) {
+                ConfigFormat::Json => Self::from_json(&contents),
+                ConfigFormat::Yaml => Self::from_yaml(&contents),
+            }
+        };
+        load().map_err(|e| e.with_path(path))
+    }
+
+    /// The config of the chain named `name`, which is normalized as [`ChainName`]s are.
+    pub fn chain_config(&self, name: &str) -> Option<&ChainConfig> {
+        self.chains.get(&ChainName::new(name))
+    }
+
+    /// Like [`WarpRouteConfig::chain_config`], for modifying the chain's config in place.
+    pub fn chain_config_mut(&mut self, name: &str) -> Option<&mut ChainConfig> {
+        self.chains.get_mut(&ChainName::new(name))
+    }
+
+    /// The chains of this route and their configs, in the order of
+    /// [`WarpRouteConfig::chain_names`].
+    pub fn iter_chains(&self) -> impl Iterator<Item = (&ChainName, &ChainConfig)> {
+        self.chains.iter()
+    }
+
+    pub fn update_chain_config(&mut self, chain_name: &str, new_config: ChainConfig) {
+        self.chains.insert(ChainName::new(chain_name), new_config);
+    }
+
+    /// Adds every chain of `other` to this config.
+    ///
+    /// Fails without modifying `self` if any chain of `other` is already configured here.
+    pub fn merge(&mut self, other: WarpRouteConfig) -> Result<(), ConfigError> {
+        if let Some(name) = other
+            .chain_names()
+            .into_iter()
+            .find(|name| self.chains.contains_key(*name))
+        {
+            return Err(ConfigError::DuplicateChain {
+                name: name.to_string(),
+            });
+        }
+        self.chains.extend(other.chains);
+        Ok(())
+    }
+
+    /// Assembles a config from per-chain fragments, see [`WarpRouteConfig::merge`].
+    pub fn from_fragments(fragments: Vec<WarpRouteConfig>) -> Result<Self, ConfigError> {
+        fragments.into_iter().try_fold(
+            WarpRouteConfig {
+                chains: BTreeMap::new(),
+            },
+            |mut config, fragment| {
+                config.merge(fragment)?;
+                Ok(config)
+            },
+        )
+    }
+
+    /// Sets the owner of the warp route contracts on every chain.
+    pub fn update_owner(&mut self, new_owner: impl Into<ChainAddress>) {
+        let new_owner = new_owner.into();
+        for chain_config in self.chains.values_mut() {
+            chain_config.owner = new_owner.clone();
+        }
+    }
+
+    /// Validates the name, see [`ChainName::is_registry_name`], and the config, see
+    /// [`ChainConfig::validate`], of every chain.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(name) = self
+            .chain_names()
+            .into_iter()
+            .find(|name| !ChainName::is_registry_name(name))
+        {
+            return Err(ConfigError::InvalidChainName {
+                name: name.to_string(),
+            });
+        }
+        self.chain_names()
+            .into_iter()
+            .try_for_each(|chain| self.chains[chain].validate(chain))?;
+
+        // A route carries either NFTs or fungible tokens, on every chain
+        let (nft_chains, fungible_chains): (Vec<&str>, Vec<&str>) = self
+            .chain_names()
+            .into_iter()
+            .partition(|chain| self.chains[*chain].is_nft);
+        if !nft_chains.is_empty() && !fungible_chains.is_empty() {
+            return Err(ConfigError::MixedNftRoute {
+                nft_chains: nft_chains.into_iter().map(str::to_string).collect(),
+                fungible_chains: fungible_chains.into_iter().map(str::to_string).collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the route has at least two chains, an origin and a destination.
+    pub fn validate_chain_count(&self) -> Result<(), ConfigError> {
+        match self.chains.len() {
+            count @ 0..=1 => Err(ConfigError::InsufficientChains { count }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the relayer of every trusted relayer ISM in the route is one of `allowlist`.
+    pub fn validate_relayers(&self, allowlist: &[Address]) -> Result<(), ConfigError> {
+        for chain in self.chain_names() {
+            let Some(relayer) = self.chains[chain].interchain_security_module.relayer() else {
+                continue;
+            };
+            let is_allowed = relayer
+                .as_evm()
+                .is_some_and(|relayer| allowlist.contains(&relayer));
+            if !is_allowed {
+                return Err(ConfigError::UnauthorizedRelayer {
+                    chain: chain.to_string(),
+                    relayer: relayer.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no two chains of the route have the same mailbox, which almost always means
+    /// the config of one chain was copied from another and not fully edited.
+    ///
+    /// [`WarpRouteConfig::validate`] doesn't run this check, leaving it to callers to decide
+    /// whether a shared mailbox is fatal.
+    pub fn validate_distinct_mailboxes(&self) -> Result<(), ConfigError> {
+        // A mailbox shared with an earlier chain was reported with that chain
+        for (i, (_, config)) in self.chains.iter().enumerate() {
+            let chains: Vec<String> = self
+                .chains
+                .iter()
+                .skip(i)
+                .filter(|(_, other)| other.mailbox == config.mailbox)
+                .map(|(name, _)| name.to_string())
+                .collect();
+            if chains.len() > 1 {
+                return Err(ConfigError::DuplicateMailboxAcrossChains {
+                    mailbox: config.mailbox.clone(),
+                    chains,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `chain`'s config enrolls each of `routers`, the routers of the route by chain
+    /// name, other than its own, as the remote router for that chain.
+    ///
+    /// Returns a description of every router that isn't enrolled or is enrolled with another
+    /// address; an empty list means the chain is connected to the whole route.
+    pub fn enrollment_problems(
+        &self,
+        chain: &str,
+        routers: &BTreeMap<String, Address>,
+    ) -> Vec<String> {
+        let chain = ChainName::new(chain);
+        let Some(chain_config) = self.chains.get(&chain) else {
+            return vec![format!("`{chain}` has no warp route config")];
+        };
+        let mut problems = Vec::new();
+        for (remote, router) in remote_routers(&chain, routers) {
+            if domain_id_for_chain(&remote).is_none() {
+                problems.push(format!("the domain id of `{remote}` is unknown"));
+                continue;
+            }
+            match chain_config.remote_router_for_chain(&remote) {
+                Some(enrolled) if enrolled.evm_address() == Some(*router) => {}
+                Some(enrolled) => problems.push(format!(
+                    "`{chain}` enrolls {} as the router of `{remote}` instead of {router}",
+                    enrolled.address()
+                )),
+                None => problems.push(format!("`{chain}` has no router enrolled for `{remote}`")),
+            }
+        }
+        problems
+    }
+
+    /// Enrolls the routers of `routers`, by chain name, as the remote routers of `chain`, so
+    /// that [`WarpRouteConfig::enrollment_problems`] finds none. Routers already enrolled for
+    /// other chains are kept.
+    pub fn enroll_routers(
+        &mut self,
+        chain: &str,
+        routers: &BTreeMap<String, Address>,
+    ) -> Result<(), ConfigError> {
+        let chain = ChainName::new(chain);
+        let chain_config =
+            self.chains
+                .get_mut(&chain)
+                .ok_or_else(|| ConfigError::MissingChain {
+                    chain: chain.to_string(),
+                })?;
+        let enrolled = chain_config
+            .remote_routers
+            .get_or_insert_with(BTreeMap::new);
+        for (remote, router) in remote_routers(&chain, routers) {
+            let domain_id =
+                domain_id_for_chain(&remote).ok_or_else(|| ConfigError::UnknownDomain {
+                    chain: remote.to_string(),
+                })?;
+            enrolled.insert(
+                domain_id.to_string(),
+                RemoteRouter {
+                    address: alloy_primitives::hex::encode_prefixed(router.into_word()),
+                    extra: BTreeMap::new(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the config of `chain`, as `hyperlane warp read` reports it, to `desired`. What the
+    /// deployed router knows that `desired` leaves unset, such as its remote routers and
+    /// destination gas, is kept.
+    pub fn apply_chain_config(
+        &mut self,
+        chain: &str,
+        desired: &ChainConfig,
+    ) -> Result<(), ConfigError> {
+        let current = self.chains.get_mut(&ChainName::new(chain)).ok_or_else(|| {
+            ConfigError::MissingChain {
+                chain: chain.to_string(),
+            }
+        })?;
+        let mut updated = desired.clone();
+        updated.token = updated.token.or(current.token.take());
+        updated.vault = updated.vault.or(current.vault.take());
+        updated.lockbox = updated.lockbox.or(current.lockbox.take());
+        updated.xerc20 = updated.xerc20.or(current.xerc20.take());
+        updated.decimals = updated.decimals.or(current.decimals);
+        updated.scale = updated.scale.or(current.scale);
+        updated.remote_routers = updated.remote_routers.or(current.remote_routers.take());
+        updated.destination_gas = updated.destination_gas.or(current.destination_gas.take());
+        for (key, value) in std::mem::take(&mut current.extra) {
+            updated.extra.entry(key).or_insert(value);
+        }
+        *current = updated;
+        Ok(())
+    }
+
+    /// Identifies the contents of this config: the keccak256 hash of, for each chain in name
+    /// order, the chain name followed by a zero byte and the chain's config as JSON. Configs
+    /// listing the same chains in a different order have the same hash.
+    pub fn content_hash(&self) -> Result<B256, ConfigError> {
+        let mut preimage = Vec::new();
+        for chain in self.chain_names() {
+            preimage.extend_from_slice(chain.as_bytes());
+            preimage.push(0);
+            preimage.extend_from_slice(&serde_json::to_vec(&self.chains[chain])?);
+        }
+        Ok(keccak256(preimage))
+    }
+
+    /// Names of the chains in this route, sorted so that per-chain commands are issued in a
+    /// reproducible order.
+    pub fn chain_names(&self) -> Vec<&str> {
+        self.chains.keys().map(ChainName::as_str).collect()
+    }
+}
+
+impl CoreConfig {
+    /// A JSON Schema of core configs, see [`WarpRouteConfig::json_schema`].
+    pub fn json_schema() -> serde_json::Value {
+        schema::core_config()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(json).map_err(ConfigError::from)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(yaml).map_err(ConfigError::from)
+    }
+
+    pub fn to_json(&self) -> Result<String, ConfigError> {
+        serde_json::to_string(self).map_err(ConfigError::from)
+    }
+
+    pub fn to_json_pretty(&self) -> Result<String, ConfigError> {
+        serde_json::to_string_pretty(self).map_err(ConfigError::from)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, ConfigError> {
+        serde_yaml::to_string(self).map_err(ConfigError::from)
+    }
+
+    /// Loads a config from a `.json`, `.yaml` or `.yml` file.
+    ///
+    /// Errors are wrapped in [`ConfigError::InFile`] to name the file that failed.
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let load = || {
+            let contents = std::fs::read_to_string(path)?;
+            match ConfigFormat::from_path(path) {
+                ConfigFormat::Json => Self::from_json(&contents),
+                ConfigFormat::Yaml => Self::from_yaml(&contents),
+            }
+        };
+        load().map_err(|e| e.with_path(path))
+    }
+
+    /// Parses the stdout of `hyperlane core read`.
+    ///
+    /// The CLI may surround the YAML config with log lines, possibly colored, in which case the
+    /// config is taken to be the block starting at the first top-level core config key, up to
+    /// the first line that is neither indented nor a `key:` line.
+    pub fn from_read_output(output: &str) -> Result<Self, ConfigError> {
+        if let Ok(config) = Self::from_yaml(output) {
+            return Ok(config);
+        }
+        let output = strip_ansi_codes(output);
+        let lines: Vec<&str> = output.lines().collect();
+        let start = lines
+            .iter()
+            .position(|line| {
+                ["defaultHook:", "defaultIsm:", "owner:", "requiredHook:"]
+                    .iter()
+                    .any(|key| line.starts_with(key))
+            })
+            .ok_or(ConfigError::NoConfigInOutput)?;
+        let yaml: Vec<&str> = lines[start..]
+            .iter()
+            .copied()
+            .take_while(|line| {
+                line.trim().is_empty()
+                    || line.starts_with([' ', '-'])
+                    || line.split_once(':').is_some_and(|(key, _)| {
+                        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric())
+                    })
+            })
+            .collect();
+        Self::from_yaml(&yaml.join("\n"))
+    }
+
+    pub fn owner(&self) -> Address {
+        self.owner
+    }
+
+    /// Whether this describes contracts that are already deployed, as `hyperlane core read`
+    /// reports them, rather than ones still to be deployed.
+    pub fn is_deployed(&self) -> bool {
+        [
+            self.default_hook.address,
+            self.default_ism.address,
+            self.required_hook.address,
+        ]
+        .iter()
+        .all(|address| !address.is_zero())
+    }
+
+    pub fn default_hook(&self) -> &DefaultHook {
+        &self.default_hook
+    }
+
+    pub fn default_ism(&self) -> &DefaultIsm {
+        &self.default_ism
+    }
+
+    pub fn required_hook(&self) -> &RequiredHook {
+        &self.required_hook
+    }
+
+    /// Checks that the fields the ISM and hook types depend on are set, that the protocol fees
+    /// are amounts and that a trusted relayer ISM has a relayer.
+    ///
+    /// A protocol fee hook must also have a beneficiary, fees paid to the zero address being
+    /// burned, and charge no more than its maximum fee, which the hook contract would reject.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let hook = &self.required_hook;
+        if hook.hook_type == HookType::ProtocolFee {
+            let fields = [
+                ("beneficiary", hook.beneficiary.is_some()),
+                ("maxProtocolFee", hook.max_protocol_fee.is_some()),
+                ("owner", hook.owner.is_some()),
+                ("protocolFee", hook.protocol_fee.is_some()),
+            ];
+            if let Some((field, _)) = fields.into_iter().find(|(_, is_set)| !is_set) {
+                return Err(ConfigError::MissingHookField { field });
+            }
+        }
+        let amounts = [
+            ("protocolFee", &hook.protocol_fee),
+            ("maxProtocolFee", &hook.max_protocol_fee),
+        ];
+        let mut parsed = Vec::new();
+        for (field, amount) in amounts {
+            let Some(amount) = amount else { continue };
+            match amount.parse::<U256>() {
+                Ok(amount) => parsed.push(amount),
+                Err(_) => {
+                    return Err(ConfigError::InvalidAmount {
+                        field,
+                        amount: amount.clone(),
+                    })
+                }
+            }
+        }
+        if hook.hook_type == HookType::ProtocolFee {
+            if hook.beneficiary.unwrap_or_default().is_zero() {
+                return Err(ConfigError::ZeroBeneficiary);
+            }
+            // Both are set, as checked above
+            if let [protocol_fee, max_protocol_fee] = parsed[..] {
+                if protocol_fee > max_protocol_fee {
+                    return Err(ConfigError::ProtocolFeeExceedsMax {
+                        protocol_fee,
+                        max_protocol_fee,
+                    });
+                }
+            }
+        }
+        if self.default_ism.ism_type == IsmType::TrustedRelayerIsm
+            && self.default_ism.relayer.unwrap_or_default().is_zero()
+        {
+            return Err(ConfigError::ZeroRelayer);
+        }
+        Ok(())
+    }
+
+    pub fn update_owner(&mut self, new_owner: Address) -> Result<(), ConfigError> {
+        self.owner = new_owner;
+        Ok(())
+    }
+
+    pub fn update_default_hook(&mut self, default_hook: DefaultHook) -> Result<(), ConfigError> {
+        self.update(|config| config.default_hook = default_hook)
+    }
+
+    pub fn update_default_ism(&mut self, default_ism: DefaultIsm) -> Result<(), ConfigError> {
+        self.update(|config| config.default_ism = default_ism)
+    }
+
+    pub fn update_required_hook(&mut self, required_hook: RequiredHook) -> Result<(), ConfigError> {
+        self.update(|config| config.required_hook = required_hook)
+    }
+
+    /// This config as it applies to a chain whose core config reads as `current`: the owner and
+    /// the hook and ISM settings come from this config, but the hook and ISM addresses, which
+    /// differ from chain to chain, from `current`.
+    pub fn for_chain(&self, current: &CoreConfig) -> CoreConfig {
+        let mut config = self.clone();
+        config.default_hook.address = current.default_hook.address;
+        config.default_ism.address = current.default_ism.address;
+        config.required_hook.address = current.required_hook.address;
+        config
+    }
+
+    /// Applies `edit`, unless the edited config fails [`CoreConfig::validate`], in which case
+    /// the config is left as it was.
+    fn update(&mut self, edit: impl FnOnce(&mut Self)) -> Result<(), ConfigError> {
+        let mut updated = self.clone();
+        edit(&mut updated);
+        updated.validate()?;
+        *self = updated;
+        Ok(())
+    }
+}
+
+/// Splits a `key: value` or `- value` line of YAML into everything up to the value and the
+/// value, if the value is a scalar on that line.
+fn split_scalar_line(line: &str) -> Option<(&str, &str)> {
+    let split = match line.trim_start().strip_prefix("- ") {
+        Some(item) if !item.contains(": ") => line.len() - item.len(),
+        _ => line.find(": ")? + 2,
+    };
+    let value = &line[split..];
+    let is_scalar = !value.is_empty()
+        && !value.starts_with(['|', '>', '&', '*', '[', '{', '#'])
+        && !value.ends_with(':');
+    is_scalar.then(|| line.split_at(split))
+}
+
+/// `value` without the quotes YAML may have put around it.
+fn unquote(value: &str) -> &str {
+    ['\'', '"']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+/// `text` without ANSI escape sequences such as colors.
+fn strip_ansi_codes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // `ESC [ <parameters> <final byte>`, the final byte being in `@`..=`~`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// The largest config payload accepted by default, see [`payload_str`].
+pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
+
+/// The YAML text of a config payload, checking that it is at most `limit` bytes before it is
+/// decoded, let alone parsed.
+pub fn payload_str(bytes: &[u8], limit: usize) -> Result<&str, ConfigError> {
+    if bytes.len() > limit {
+        return Err(ConfigError::PayloadTooLarge {
+            size: bytes.len(),
+            limit,
+        });
+    }
+    std::str::from_utf8(bytes).map_err(|_| ConfigError::InvalidUtf8)
+}
+
+impl CoreConfig {
+    /// Parses a YAML payload of at most `limit` bytes, see [`payload_str`].
+    pub fn from_payload(bytes: &[u8], limit: usize) -> Result<Self, ConfigError> {
+        Self::from_yaml(payload_str(bytes, limit)?)
+    }
+
+    /// Parses a YAML payload of at most `limit` bytes that maps chain names to the core config
+    /// of each chain, rejecting names that only differ in case or whitespace.
+    ///
+    /// The hooks and ISM of a core config are contracts of one chain, so every chain gets its
+    /// own config.
+    pub fn per_chain_from_payload(
+        bytes: &[u8],
+        limit: usize,
+    ) -> Result<BTreeMap<ChainName, Self>, ConfigError> {
+        let configs: BTreeMap<String, Self> = serde_yaml::from_str(payload_str(bytes, limit)?)?;
+        let mut per_chain = BTreeMap::new();
+        for (name, config) in configs {
+            let name = ChainName::new(name);
+            if per_chain.contains_key(&name) {
+                return Err(ConfigError::DuplicateChain {
+                    name: name.to_string(),
+                });
+            }
+            per_chain.insert(name, config);
+        }
+        Ok(per_chain)
+    }
+}
+
+impl WarpRouteConfig {
+    /// Parses a YAML payload of at most `limit` bytes, see [`payload_str`].
+    pub fn from_payload(bytes: &[u8], limit: usize) -> Result<Self, ConfigError> {
+        Self::from_yaml(payload_str(bytes, limit)?)
+    }
+}
+
+/// Parses a payload of at most [`DEFAULT_MAX_PAYLOAD_SIZE`] bytes.
+impl TryFrom<&[u8]> for CoreConfig {
+    type Error = ConfigError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_payload(bytes, DEFAULT_MAX_PAYLOAD_SIZE)
+    }
+}
+
+/// Parses a payload of at most [`DEFAULT_MAX_PAYLOAD_SIZE`] bytes.
+impl TryFrom<&[u8]> for WarpRouteConfig {
+    type Error = ConfigError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_payload(bytes, DEFAULT_MAX_PAYLOAD_SIZE)
+    }
+}
+
+/// The routers of `routers`, by chain name, of the chains other than `chain`, with their chain
+/// names normalized, see [`WarpRouteConfig::enrollment_problems`].
+fn remote_routers<'a>(
+    chain: &'a ChainName,
+    routers: &'a BTreeMap<String, Address>,
+) -> impl Iterator<Item = (ChainName, &'a Address)> + 'a {
+    routers
+        .iter()
+        .map(|(remote, router)| (ChainName::new(remote), router))
+        .filter(move |(remote, _)| remote != chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_ADDRESS: Address = Address::new([
+        0x74, 0x2d, 0x35, 0xcc, 0x66, 0x34, 0xc0, 0x53, 0x29, 0x25, 0xa3, 0xb8, 0x44, 0xbc, 0x45,
+        0x4e, 0x44, 0x38, 0xf4, 0x4e,
+    ]);
+
+    fn create_sample_warp_route_config() -> WarpRouteConfig {
+        WarpRouteConfig {
+            chains: {
+                let mut map = BTreeMap::new();
+                map.insert(
+                    "chain1".into(),
+                    ChainConfig {
+                        interchain_security_module: InterchainSecurityModule::trusted_relayer(
+                            VALID_ADDRESS,
+                        ),
+                        is_nft: false,
+                        mailbox: VALID_ADDRESS.into(),
+                        interchain_gas_paymaster: VALID_ADDRESS.into(),
+                        owner: VALID_ADDRESS.into(),
+                        token_type: TokenType::Synthetic,
+                        token: Some(VALID_ADDRESS.into()),
+                        vault: None,
+                        lockbox: None,
+                        xerc20: None,
+                        decimals: None,
+                        scale: None,
+                        remote_routers: None,
+                        destination_gas: None,
+                        extra: BTreeMap::new(),
+                    },
+                );
+                map
+            },
+        }
+    }
+
+    fn create_sample_core_config() -> CoreConfig {
+        CoreConfig {
+            default_hook: DefaultHook {
+                address: VALID_ADDRESS,
+                hook_type: HookType::MerkleTreeHook,
+            },
+            default_ism: DefaultIsm {
+                address: VALID_ADDRESS,
+                relayer: Some(VALID_ADDRESS),
+                ism_type: IsmType::TrustedRelayerIsm,
+            },
+            owner: VALID_ADDRESS,
+            required_hook: RequiredHook {
+                address: VALID_ADDRESS,
+                beneficiary: Some(VALID_ADDRESS),
+                max_protocol_fee: Some("100000000000000000".to_string()),
+                owner: Some(VALID_ADDRESS),
+                protocol_fee: Some("0".to_string()),
+                hook_type: HookType::ProtocolFee,
+            },
+        }
+    }
+
+    #[test]
+    fn test_warp_route_config_serialization() {
+        let config = create_sample_warp_route_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized: WarpRouteConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, deserialized);
+    }
+
+    #[test]
+    fn test_to_json_and_yaml_round_trip() {
+        let warp = create_sample_warp_route_config();
+        assert_eq!(
+            WarpRouteConfig::from_json(&warp.to_json().unwrap()).unwrap(),
+            warp
+        );
+        assert_eq!(
+            WarpRouteConfig::from_yaml(&warp.to_yaml().unwrap()).unwrap(),
+            warp
+        );
+        let pretty = warp.to_json_pretty().unwrap();
+        assert!(pretty.contains('\n'));
+        assert_eq!(WarpRouteConfig::from_json(&pretty).unwrap(), warp);
+
+        let core = create_sample_core_config();
+        assert_eq!(
+            CoreConfig::from_json(&core.to_json().unwrap()).unwrap(),
+            core
+        );
+        assert_eq!(
+            CoreConfig::from_yaml(&core.to_yaml().unwrap()).unwrap(),
+            core
+        );
+        let pretty = core.to_json_pretty().unwrap();
+        assert!(pretty.contains('\n'));
+        assert_eq!(CoreConfig::from_json(&pretty).unwrap(), core);
+    }
+
+    #[test]
+    fn test_core_config_serialization() {
+        let config = create_sample_core_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized: CoreConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, deserialized);
+    }
+
+    const COLLATERAL_VAULT_CONFIG: &str = r#"
+    chain1:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "collateralVault"
+      vault: "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+    "#;
+
+    #[test]
+    fn test_collateral_vault_config_round_trip() {
+        let config = WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            config.chains["chain1"].vault,
+            Some(ChainAddress::Evm(
+                "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+                    .parse()
+                    .unwrap()
+            ))
+        );
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""vault":"0x5fbdb2315678afecb367f032d93f642f64180aa3""#));
+        assert!(!json.contains("lockbox"));
+        assert_eq!(WarpRouteConfig::from_json(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_token_type_fields_validation() {
+        let mut config = WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap();
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.vault = None;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingTokenField { field: "vault", .. })
+        ));
+
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.token_type = TokenType::XErc20Lockbox;
+        chain.lockbox = Some(VALID_ADDRESS.into());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingTokenField {
+                field: "xERC20",
+                ..
+            })
+        ));
+
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.xerc20 = Some(XErc20Config::new(XErc20Limits::Velo {
+            mint: "100".to_string(),
+            burn: "100".to_string(),
+        }));
+        config.validate().unwrap();
+
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.vault = Some(VALID_ADDRESS.into());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::UnexpectedTokenField { chain, field: "vault" }) if chain == "chain1"
+        ));
+    }
+
+    const XERC20_LOCKBOX_CONFIG: &str = r#"
+    chain1:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "xERC20Lockbox"
+      lockbox: "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+      xERC20:
+        warpRouteLimits:
+          type: "standard"
+          bufferCap: "1000000000000000000000"
+          rateLimitPerSecond: "11574074074074074"
+        extraBridges:
+          - lockbox: "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+            limits:
+              type: "velo"
+              mint: "5000000000000000000"
+              burn: "5000000000000000000"
+    "#;
+
+    #[test]
+    fn test_xerc20_lockbox_config_round_trip() {
+        let config = WarpRouteConfig::from_yaml_strict(XERC20_LOCKBOX_CONFIG).unwrap();
+        config.validate().unwrap();
+        let chain = &config.chains["chain1"];
+        assert_eq!(chain.token_type, TokenType::XErc20Lockbox);
+        let xerc20 = chain.xerc20().unwrap();
+        assert_eq!(
+            xerc20.warp_route_limits(),
+            &XErc20Limits::Standard {
+                buffer_cap: "1000000000000000000000".to_string(),
+                rate_limit_per_second: "11574074074074074".to_string(),
+            }
+        );
+        assert_eq!(
+            xerc20.extra_bridges(),
+            [XErc20Bridge {
+                lockbox: "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+                    .parse()
+                    .unwrap(),
+                limits: XErc20Limits::Velo {
+                    mint: "5000000000000000000".to_string(),
+                    burn: "5000000000000000000".to_string(),
+                },
+            }]
+        );
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""type":"xERC20Lockbox""#));
+        assert!(json.contains(r#""warpRouteLimits":{"type":"standard","bufferCap":"#));
+        assert_eq!(WarpRouteConfig::from_json(&json).unwrap(), config);
+        assert_eq!(
+            WarpRouteConfig::from_yaml(&config.to_yaml().unwrap()).unwrap(),
+            config
+        );
+
+        // Configs written before the token types were spelled as hyperlane does still parse
+        let old_spelling = XERC20_LOCKBOX_CONFIG.replace("xERC20Lockbox", "xErc20Lockbox");
+        assert_eq!(WarpRouteConfig::from_yaml(&old_spelling).unwrap(), config);
+
+        let mut missing_limits = config;
+        missing_limits.chains.get_mut("chain1").unwrap().xerc20 = None;
+        assert!(matches!(
+            missing_limits.validate(),
+            Err(ConfigError::MissingTokenField { chain, field: "xERC20" }) if chain == "chain1"
+        ));
+        let incomplete = XERC20_LOCKBOX_CONFIG.replace("bufferCap", "bufferCapp");
+        assert!(WarpRouteConfig::from_yaml(&incomplete).is_err());
+    }
+
+    const NATIVE_SCALED_CONFIG: &str = r#"
+    chain1:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "nativeScaled"
+      decimals: 6
+      scale: 1000000000000
+    "#;
+
+    #[test]
+    fn test_native_scaled_config_round_trip() {
+        let config = WarpRouteConfig::from_yaml_strict(NATIVE_SCALED_CONFIG).unwrap();
+        config.validate().unwrap();
+        let chain = &config.chains["chain1"];
+        assert_eq!(chain.decimals(), Some(6));
+        assert_eq!(chain.scale(), Some(1_000_000_000_000));
+        assert!(chain.extra.is_empty());
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""decimals":6"#));
+        assert!(json.contains(r#""scale":1000000000000"#));
+        assert_eq!(WarpRouteConfig::from_json(&json).unwrap(), config);
+        assert_eq!(
+            WarpRouteConfig::from_yaml(&config.to_yaml().unwrap()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_scale_validation() {
+        let mut config = WarpRouteConfig::from_yaml(NATIVE_SCALED_CONFIG).unwrap();
+        config.chains.get_mut("chain1").unwrap().scale = None;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingTokenField { field: "scale", .. })
+        ));
+
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.scale = Some(1_000);
+        chain.decimals = None;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::MissingTokenField {
+                field: "decimals",
+                ..
+            })
+        ));
+
+        // Other token types may carry their token's decimals, but not scale amounts
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.token_type = TokenType::Native;
+        chain.decimals = Some(18);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::UnexpectedTokenField { chain, field: "scale" }) if chain == "chain1"
+        ));
+        config.chains.get_mut("chain1").unwrap().scale = Some(DEFAULT_SCALE);
+        config.validate().unwrap();
+        config.chains.get_mut("chain1").unwrap().scale = None;
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_evm_chain_config_addresses() {
+        let config = WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap();
+        let chain = &config.chains["chain1"];
+        assert_eq!(chain.mailbox, ChainAddress::Evm(VALID_ADDRESS));
+        assert_eq!(chain.owner, ChainAddress::Evm(VALID_ADDRESS));
+        assert_eq!(
+            chain.interchain_security_module.relayer(),
+            Some(&ChainAddress::Evm(VALID_ADDRESS))
+        );
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json.to_lowercase()
+                .matches(r#""0x742d35cc6634c0532925a3b844bc454e4438f44e""#)
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_cosmos_chain_config_addresses() {
+        let yaml = r#"
+        neutron:
+          interchainSecurityModule:
+            relayer: "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4"
+            type: "trustedRelayerIsm"
+          isNft: false
+          mailbox: "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4"
+          interchainGasPaymaster: "neutron12p8wntzra3vpfcqv05scdx5sa3ftaj6gjcmtm7ynkl0e6crtt4ns8cnrmx"
+          owner: "neutron1dwnrgwsf5c9vqjxsax04pdm0mx007yrre4yyvm"
+          type: "synthetic"
+        "#;
+        let config = WarpRouteConfig::from_yaml(yaml).unwrap();
+        config.validate().unwrap();
+        let chain = &config.chains["neutron"];
+        assert_eq!(
+            chain.owner,
+            ChainAddress::Bech32("neutron1dwnrgwsf5c9vqjxsax04pdm0mx007yrre4yyvm".to_string())
+        );
+        assert!(matches!(chain.mailbox, ChainAddress::Bech32(_)));
+        assert!(matches!(
+            chain.interchain_gas_paymaster,
+            ChainAddress::Bech32(_)
+        ));
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""owner":"neutron1dwnrgwsf5c9vqjxsax04pdm0mx007yrre4yyvm""#));
+        assert_eq!(WarpRouteConfig::from_json(&json).unwrap(), config);
+
+        let invalid = yaml.replace(
+            "neutron1dwnrgwsf5c9vqjxsax04pdm0mx007yrre4yyvm",
+            "not-an-address",
+        );
+        assert!(WarpRouteConfig::from_yaml(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_token_type_requirements() {
+        // (requires_token, requires_vault, requires_lockbox, requires_xerc20_limits,
+        // is_synthetic, is_nft), matched exhaustively so that new variants must be added here
+        let expected = |token_type: &TokenType| match token_type {
+            TokenType::Synthetic => (false, false, false, false, true, false),
+            TokenType::FastSynthetic => (false, false, false, false, true, false),
+            TokenType::SyntheticUri => (false, false, false, false, true, true),
+            TokenType::Collateral => (true, false, false, false, false, false),
+            TokenType::CollateralVault => (false, true, false, false, false, false),
+            TokenType::XErc20 => (true, false, false, true, false, false),
+            TokenType::XErc20Lockbox => (false, false, true, true, false, false),
+            TokenType::CollateralFiat => (true, false, false, false, false, false),
+            TokenType::FastCollateral => (true, false, false, false, false, false),
+            TokenType::CollateralUri => (true, false, false, false, false, true),
+            TokenType::Native => (false, false, false, false, false, false),
+            TokenType::NativeScaled => (false, false, false, false, false, false),
+        };
+        let all = [
+            TokenType::Synthetic,
+            TokenType::FastSynthetic,
+            TokenType::SyntheticUri,
+            TokenType::Collateral,
+            TokenType::CollateralVault,
+            TokenType::XErc20,
+            TokenType::XErc20Lockbox,
+            TokenType::CollateralFiat,
+            TokenType::FastCollateral,
+            TokenType::CollateralUri,
+            TokenType::Native,
+            TokenType::NativeScaled,
+        ];
+        for token_type in &all {
+            assert_eq!(
+                (
+                    token_type.requires_token(),
+                    token_type.requires_vault(),
+                    token_type.requires_lockbox(),
+                    token_type.requires_xerc20_limits(),
+                    token_type.is_synthetic(),
+                    token_type.is_nft()
+                ),
+                expected(token_type),
+                "{token_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nft_flag_must_match_token_type() {
+        let mut config = WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap();
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.token_type = TokenType::CollateralUri;
+        chain.vault = None;
+        chain.token = Some(VALID_ADDRESS.into());
+        chain.is_nft = true;
+        config.validate().unwrap();
+
+        config.chains.get_mut("chain1").unwrap().is_nft = false;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::NftFlagMismatch {
+                token_type: TokenType::CollateralUri,
+                is_nft: false,
+                ..
+            })
+        ));
+
+        let chain = config.chains.get_mut("chain1").unwrap();
+        chain.token_type = TokenType::Collateral;
+        chain.is_nft = true;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::NftFlagMismatch { is_nft: true, .. })
+        ));
+    }
+
+    /// A route with a chain of each of `token_types`, named after its index.
+    fn route_of(token_types: &[TokenType]) -> WarpRouteConfig {
+        let sample = &create_sample_warp_route_config().chains["chain1"];
+        let chains = token_types
+            .iter()
+            .enumerate()
+            .map(|(i, token_type)| {
+                let mut chain = sample.clone();
+                chain.token_type = token_type.clone();
+                chain.is_nft = token_type.is_nft();
+                (format!("chain{i}").into(), chain)
+            })
+            .collect();
+        WarpRouteConfig { chains }
+    }
+
+    #[test]
+    fn test_route_must_not_mix_nft_and_fungible_chains() {
+        route_of(&[TokenType::CollateralUri, TokenType::SyntheticUri])
+            .validate()
+            .unwrap();
+        route_of(&[TokenType::Collateral, TokenType::Synthetic])
+            .validate()
+            .unwrap();
+
+        let mixed = route_of(&[
+            TokenType::CollateralUri,
+            TokenType::Synthetic,
+            TokenType::SyntheticUri,
+        ]);
+        assert!(matches!(
+            mixed.validate(),
+            Err(ConfigError::MixedNftRoute { nft_chains, fungible_chains })
+                if nft_chains == ["chain0", "chain2"] && fungible_chains == ["chain1"]
+        ));
+    }
+
+    #[test]
+    fn test_route_needs_at_least_two_chains() {
+        assert!(matches!(
+            route_of(&[]).validate_chain_count(),
+            Err(ConfigError::InsufficientChains { count: 0 })
+        ));
+        assert!(matches!(
+            route_of(&[TokenType::Collateral]).validate_chain_count(),
+            Err(ConfigError::InsufficientChains { count: 1 })
+        ));
+        route_of(&[TokenType::Collateral, TokenType::Synthetic])
+            .validate_chain_count()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ism_type_deserialization() {
+        let known: IsmType = serde_json::from_str(r#""trustedRelayerIsm""#).unwrap();
+        assert_eq!(known, IsmType::TrustedRelayerIsm);
+        let multisig: IsmType = serde_yaml::from_str("messageIdMultisigIsm").unwrap();
+        assert_eq!(multisig, IsmType::MessageIdMultisigIsm);
+        assert!(multisig.is_multisig());
+
+        let unknown: IsmType = serde_json::from_str(r#""trustedRelayrIsm""#).unwrap();
+        assert_eq!(unknown, IsmType::Other("trustedRelayrIsm".to_string()));
+        assert_eq!(
+            serde_json::to_string(&unknown).unwrap(),
+            r#""trustedRelayrIsm""#
+        );
+        assert_eq!(
+            serde_json::to_string(&IsmType::DomainRoutingIsm).unwrap(),
+            r#""domainRoutingIsm""#
+        );
+    }
+
+    const AGGREGATION_ISM_CONFIG: &str = r#"
+        chain1:
+          interchainSecurityModule:
+            type: "aggregationIsm"
+            threshold: 2
+            modules:
+              - type: "trustedRelayerIsm"
+                relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              - type: "messageIdMultisigIsm"
+                threshold: 1
+                validators: ["0x1111111111111111111111111111111111111111"]
+          isNft: false
+          mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          type: "synthetic"
+        "#;
+
+    #[test]
+    fn test_aggregation_ism_round_trip() {
+        let config = WarpRouteConfig::from_yaml(AGGREGATION_ISM_CONFIG).unwrap();
+        let ism = &config.chains["chain1"].interchain_security_module;
+        assert_eq!(ism.ism_type(), IsmType::AggregationIsm);
+        let InterchainSecurityModule::AggregationIsm(aggregation) = ism else {
+            panic!("expected an aggregation ISM, got {ism:?}");
+        };
+        assert_eq!(aggregation.threshold, 2);
+        assert_eq!(
+            ism.modules(),
+            [
+                InterchainSecurityModule::trusted_relayer(VALID_ADDRESS),
+                InterchainSecurityModule::MessageIdMultisigIsm(MultisigIsmConfig {
+                    validators: vec![Address::repeat_byte(0x11)],
+                    threshold: 1,
+                    extra: BTreeMap::new(),
+                }),
+            ]
+        );
+        assert!(ism.modules()[1].is_multisig());
+        assert_eq!(ism.relayer(), None);
+
+        assert_eq!(
+            WarpRouteConfig::from_yaml(&config.to_yaml().unwrap()).unwrap(),
+            config
+        );
+        assert_eq!(
+            WarpRouteConfig::from_json(&config.to_json().unwrap()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_json_schema_accepts_the_sample_configs() {
+        use schema::tests::validate;
+        let schema = WarpRouteConfig::json_schema();
+        let samples = [
+            COLLATERAL_VAULT_CONFIG,
+            XERC20_LOCKBOX_CONFIG,
+            NATIVE_SCALED_CONFIG,
+            COMPREHENSIVE_WARP_ROUTE_CONFIG,
+            AGGREGATION_ISM_CONFIG,
+        ];
+        for sample in samples {
+            let value: serde_json::Value = serde_yaml::from_str(sample).unwrap();
+            validate(&schema, &schema, &value).unwrap();
+        }
+        let sample = serde_json::to_value(create_sample_warp_route_config()).unwrap();
+        validate(&schema, &schema, &sample).unwrap();
+
+        let bad_token_type = COLLATERAL_VAULT_CONFIG.replace("collateralVault", "colateralVault");
+        assert!(WarpRouteConfig::from_yaml(&bad_token_type).is_err());
+        let value: serde_json::Value = serde_yaml::from_str(&bad_token_type).unwrap();
+        assert_eq!(
+            validate(&schema, &schema, &value),
+            Err("$.chain1.type doesn't match `enum`".to_string())
+        );
+        let mut missing_owner = value;
+        missing_owner["chain1"]["type"] = "collateralVault".into();
+        missing_owner["chain1"]
+            .as_object_mut()
+            .unwrap()
+            .remove("owner");
+        assert_eq!(
+            validate(&schema, &schema, &missing_owner),
+            Err("$.chain1 doesn't match `required`".to_string())
+        );
+
+        let schema = CoreConfig::json_schema();
+        let sample = serde_json::to_value(create_sample_core_config()).unwrap();
+        validate(&schema, &schema, &sample).unwrap();
+        let mut bad_ism = sample;
+        bad_ism["defaultIsm"]["type"] = 1.into();
+        assert!(validate(&schema, &schema, &bad_ism).is_err());
+    }
+
+    #[test]
+    fn test_trusted_relayer_ism_serialization() {
+        let ism = InterchainSecurityModule::trusted_relayer(VALID_ADDRESS);
+        let json = serde_json::to_value(&ism).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "trustedRelayerIsm",
+                "relayer": VALID_ADDRESS,
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<InterchainSecurityModule>(json).unwrap(),
+            ism
+        );
+        assert!(serde_json::from_str::<InterchainSecurityModule>(
+            r#"{"type": "trustedRelayrIsm", "relayer": "0x742d35cc6634c0532925a3b844bc454e4438f44e"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hook_type_deserialization() {
+        let known: HookType = serde_json::from_str(r#""merkleTreeHook""#).unwrap();
+        assert_eq!(known, HookType::MerkleTreeHook);
+
+        let unknown: HookType = serde_yaml::from_str("ccipHook").unwrap();
+        assert_eq!(unknown, HookType::Other("ccipHook".to_string()));
+        assert_eq!(serde_yaml::to_string(&unknown).unwrap().trim(), "ccipHook");
+        assert_eq!(
+            serde_json::to_string(&HookType::InterchainGasPaymaster).unwrap(),
+            r#""interchainGasPaymaster""#
+        );
+    }
+
+    /// A warp route config as written by `hyperlane warp init` and extended by hand, using
+    /// fields this crate doesn't model.
+    const COMPREHENSIVE_WARP_ROUTE_CONFIG: &str = r#"
+    holesky:
+      type: collateral
+      token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      mailbox: "0x46f7c5d896bbec89be1b19e4485e59b4be49e9cc"
+      interchainGasPaymaster: "0x5cbf4e70448ed46c2616b04e9ebc72d29ff0cfa9"
+      isNft: false
+      name: Tangle
+      symbol: TNT
+      decimals: 18
+      hook: "0x0000000000000000000000000000000000000000"
+      interchainSecurityModule:
+        type: trustedRelayerIsm
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      proxyAdmin:
+        address: "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+        owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      remoteRouters:
+        "3799":
+          address: "0x000000000000000000000000e7f1725e7734ce288f8367e1bb143e90bb3f0512"
+      destinationGas:
+        "3799": "68000"
+      gas: 68000
+    tangletestnet:
+      type: synthetic
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      mailbox: "0x0fc0cdd6fa3ccad5d3e8b0a2ccc8b4a0c7d2a1d1"
+      interchainGasPaymaster: "0x9fe46736679d2d9a65f0992f2272de9f3c7fa6e0"
+      isNft: false
+      name: Tangle
+      symbol: TNT
+      decimals: 18
+      totalSupply: 0
+      interchainSecurityModule:
+        type: trustedRelayerIsm
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      remoteRouters:
+        "17000":
+          address: "0x0000000000000000000000005fbdb2315678afecb367f032d93f642f64180aa3"
+      destinationGas:
+        "17000": "68000"
+    "#;
+
+    /// Collects the path of every field of `input` that is missing from, or has a different value
+    /// in, `output`. Strings are compared case-insensitively, since addresses may come back
+    /// checksummed.
+    fn dropped_fields(
+        input: &serde_json::Value,
+        output: &serde_json::Value,
+        path: &str,
+        dropped: &mut Vec<String>,
+    ) {
+        use serde_json::Value;
+        match (input, output) {
+            (Value::Object(input), Value::Object(output)) => {
+                for (key, value) in input {
+                    let path = format!("{path}.{key}");
+                    match output.get(key) {
+                        Some(output) => dropped_fields(value, output, &path, dropped),
+                        None => dropped.push(path),
+                    }
+                }
+            }
+            (Value::Array(input), Value::Array(output)) if input.len() == output.len() => {
+                for (i, (input, output)) in input.iter().zip(output).enumerate() {
+                    dropped_fields(input, output, &format!("{path}[{i}]"), dropped);
+                }
+            }
+            (Value::String(input), Value::String(output)) if input.eq_ignore_ascii_case(output) => {
+            }
+            (input, output) if input == output => {}
+            _ => dropped.push(path.to_string()),
+        }
     }
 
-    pub fn update_chain_config(&mut self, chain_name: &str, new_config: ChainConfig) {
-        self.chains.insert(chain_name.to_string(), new_config);
-    }
-}
+    #[test]
+    fn test_warp_route_config_round_trip_keeps_every_field() {
+        let input: serde_json::Value =
+            serde_yaml::from_str(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        let config = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
 
-impl CoreConfig {
-    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
-        serde_json::from_str(json).map_err(ConfigError::from)
+        for output in [
+            serde_json::to_value(&config).unwrap(),
+            serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap(),
+        ] {
+            let mut dropped = Vec::new();
+            dropped_fields(&input, &output, "", &mut dropped);
+            assert!(
+                dropped.is_empty(),
+                "fields lost in the round trip: {}",
+                dropped.join(", ")
+            );
+        }
     }
 
-    pub fn from_yaml(yaml: &str) -> Result<Self, ConfigError> {
-        serde_yaml::from_str(yaml).map_err(ConfigError::from)
-    }
+    #[test]
+    fn test_relayer_allowlist() {
+        let config = create_sample_warp_route_config();
+        let allowed = vec![Address::repeat_byte(0x11), VALID_ADDRESS];
+        config.validate_relayers(&allowed).unwrap();
 
-    pub fn update_owner(&mut self, new_owner: Address) -> Result<(), ConfigError> {
-        self.owner = new_owner;
-        Ok(())
+        let disallowed = vec![Address::repeat_byte(0x11)];
+        assert!(matches!(
+            config.validate_relayers(&disallowed),
+            Err(ConfigError::UnauthorizedRelayer { relayer, .. })
+                if relayer == ChainAddress::Evm(VALID_ADDRESS)
+        ));
+
+        // Only trusted relayer ISMs have their relayer checked
+        let mut multisig = config;
+        for chain in multisig.chains.values_mut() {
+            chain.interchain_security_module =
+                InterchainSecurityModule::MessageIdMultisigIsm(MultisigIsmConfig {
+                    validators: vec![Address::repeat_byte(0x22)],
+                    threshold: 1,
+                    extra: BTreeMap::new(),
+                });
+        }
+        multisig.validate_relayers(&disallowed).unwrap();
     }
-}
 
-impl TryFrom<&[u8]> for CoreConfig {
-    type Error = ConfigError;
+    #[test]
+    fn test_chain_names_must_be_registry_names() {
+        let config = WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap();
+        config.validate().unwrap();
 
-    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        let s = std::str::from_utf8(bytes).map_err(|_| ConfigError::InvalidUtf8)?;
-        Self::from_yaml(s)
+        // Names are lowercased as they are parsed, so only other characters are left to reject
+        let chain1 = COLLATERAL_VAULT_CONFIG.lines().nth(1).unwrap();
+        for name in ["Holesky Testnet", "base-sepolia", "chain_1", "chain.1"] {
+            let yaml = COLLATERAL_VAULT_CONFIG.replace(chain1, &format!("    \"{name}\":"));
+            let config = WarpRouteConfig::from_yaml(&yaml).unwrap();
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::InvalidChainName { name: invalid })
+                    if invalid == name.to_lowercase()
+            ));
+        }
+        let yaml = COLLATERAL_VAULT_CONFIG.replace(chain1, "    Chain1:");
+        WarpRouteConfig::from_yaml(&yaml)
+            .unwrap()
+            .validate()
+            .unwrap();
     }
-}
 
-impl TryFrom<&[u8]> for WarpRouteConfig {
-    type Error = ConfigError;
+    #[test]
+    fn test_shared_mailbox_is_flagged() {
+        let mut config = create_sample_warp_route_config();
+        let chain1 = config.chain_config("chain1").unwrap().clone();
+        config.update_chain_config("chain2", chain1.clone());
+        config.update_chain_config("chain3", chain1);
+        assert!(matches!(
+            config.validate_distinct_mailboxes(),
+            Err(ConfigError::DuplicateMailboxAcrossChains { mailbox, chains })
+                if mailbox == ChainAddress::Evm(VALID_ADDRESS)
+                    && chains == ["chain1", "chain2", "chain3"]
+        ));
 
-    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        let s = std::str::from_utf8(bytes).map_err(|_| ConfigError::InvalidUtf8)?;
-        Self::from_yaml(s)
+        for (chain, byte) in [("chain2", 0x22), ("chain3", 0x33)] {
+            config.chain_config_mut(chain).unwrap().mailbox = Address::repeat_byte(byte).into();
+        }
+        config.validate_distinct_mailboxes().unwrap();
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_strict_parsing_rejects_unknown_fields() {
+        let misspelled = r#"
+        chain1:
+          interchainSecurityModule:
+            relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+            type: "trustedRelayerIsm"
+          isNft: false
+          mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          type: "collateral"
+          tokne: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        "#;
+        let lenient = WarpRouteConfig::from_yaml(misspelled).unwrap();
+        assert!(lenient.chains["chain1"].extra.contains_key("tokne"));
 
-    use crate::runner::run_and_focus_multiple;
-    use gadget_sdk::executor::process::manager::GadgetProcessManager;
+        assert!(matches!(
+            WarpRouteConfig::from_yaml_strict(misspelled),
+            Err(ConfigError::UnknownField { chain, field }) if chain == "chain1" && field == "tokne"
+        ));
+        assert!(matches!(
+            WarpRouteConfig::from_json_strict(&lenient.to_json().unwrap()),
+            Err(ConfigError::UnknownField { field, .. }) if field == "tokne"
+        ));
 
-    const VALID_ADDRESS: Address = Address::new([
-        0x74, 0x2d, 0x35, 0xCc, 0x66, 0x34, 0xC0, 0x53, 0x29, 0x25, 0xa3, 0xb8, 0x44, 0xBc, 0x45,
-        0x4e, 0x44, 0x38, 0xf4, 0x4e,
-    ]);
+        // Fields hyperlane knows about are accepted even if they aren't modelled
+        WarpRouteConfig::from_yaml_strict(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+    }
 
-    fn create_sample_warp_route_config() -> WarpRouteConfig {
-        WarpRouteConfig {
-            chains: {
-                let mut map = HashMap::new();
-                map.insert(
-                    "chain1".to_string(),
-                    ChainConfig {
-                        interchain_security_module: InterchainSecurityModule {
-                            relayer: VALID_ADDRESS,
-                            ism_type: "trustedRelayerIsm".to_string(),
-                        },
-                        is_nft: false,
-                        mailbox: VALID_ADDRESS,
-                        interchain_gas_paymaster: VALID_ADDRESS,
-                        owner: VALID_ADDRESS,
-                        token_type: TokenType::Synthetic,
-                        token: Some(VALID_ADDRESS),
-                    },
-                );
-                map
-            },
+    #[test]
+    fn test_yaml_with_anchors_round_trips() {
+        for config in [
+            create_sample_warp_route_config(),
+            WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap(),
+        ] {
+            let yaml = config.to_yaml_with_anchors().unwrap();
+            assert_eq!(WarpRouteConfig::from_yaml(&yaml).unwrap(), config);
         }
     }
 
-    fn create_sample_core_config() -> CoreConfig {
-        CoreConfig {
-            default_hook: DefaultHook {
-                address: VALID_ADDRESS,
-                hook_type: "merkleTreeHook".to_string(),
-            },
-            default_ism: DefaultIsm {
-                address: VALID_ADDRESS,
-                relayer: VALID_ADDRESS,
-                ism_type: "trustedRelayerIsm".to_string(),
-            },
-            owner: VALID_ADDRESS,
-            required_hook: RequiredHook {
-                address: VALID_ADDRESS,
-                beneficiary: VALID_ADDRESS,
-                max_protocol_fee: "100000000000000000".to_string(),
-                owner: VALID_ADDRESS,
-                protocol_fee: "0".to_string(),
-                hook_type: "protocolFee".to_string(),
-            },
-        }
+    #[test]
+    fn test_repeated_addresses_share_an_anchor() {
+        let config = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        let yaml = config.to_yaml_with_anchors().unwrap();
+
+        // The owner, relayer and token of the route are all the same address
+        let owner = "742d35cc6634c0532925a3b844bc454e4438f44e";
+        let definitions: Vec<&str> = yaml
+            .lines()
+            .filter(|line| line.to_lowercase().contains(owner))
+            .collect();
+        assert_eq!(definitions.len(), 1, "{yaml}");
+        let anchor = definitions[0]
+            .split_once('&')
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .unwrap();
+        assert!(yaml.matches(&format!("*{anchor}")).count() >= 5, "{yaml}");
+
+        // Addresses that appear once are left as they are
+        assert!(yaml
+            .to_lowercase()
+            .contains("0x46f7c5d896bbec89be1b19e4485e59b4be49e9cc"));
     }
 
     #[test]
-    fn test_warp_route_config_serialization() {
-        let config = create_sample_warp_route_config();
-        let json = serde_json::to_string(&config).unwrap();
-        let deserialized: WarpRouteConfig = serde_json::from_str(&json).unwrap();
-        assert_eq!(config, deserialized);
+    fn test_enrollment_problems() {
+        let config = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        let routers = BTreeMap::from([
+            (
+                "holesky".to_string(),
+                "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+                    .parse()
+                    .unwrap(),
+            ),
+            (
+                "tangletestnet".to_string(),
+                "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+                    .parse()
+                    .unwrap(),
+            ),
+        ]);
+        assert!(config.enrollment_problems("holesky", &routers).is_empty());
+        assert!(config
+            .enrollment_problems("tangletestnet", &routers)
+            .is_empty());
+
+        let mut wrong = routers.clone();
+        wrong.insert("tangletestnet".to_string(), Address::repeat_byte(0x11));
+        assert_eq!(config.enrollment_problems("holesky", &wrong).len(), 1);
+
+        let mut extended = routers.clone();
+        extended.insert("sepolia".to_string(), Address::repeat_byte(0x11));
+        assert_eq!(
+            config.enrollment_problems("holesky", &extended),
+            ["`holesky` has no router enrolled for `sepolia`"]
+        );
+        assert_eq!(
+            config.enrollment_problems("sepolia", &extended),
+            ["`sepolia` has no warp route config"]
+        );
+
+        let mut enrolled = config.clone();
+        enrolled.enroll_routers("holesky", &extended).unwrap();
+        assert!(enrolled
+            .enrollment_problems("holesky", &extended)
+            .is_empty());
+        assert_eq!(
+            enrolled.chains["holesky"].remote_routers().unwrap().len(),
+            config.chains["holesky"].remote_routers().unwrap().len() + 1
+        );
+        assert!(matches!(
+            enrolled.enroll_routers("sepolia", &extended),
+            Err(ConfigError::MissingChain { chain }) if chain == "sepolia"
+        ));
+
+        // Chain names are normalized, so a chain's own router is never its remote router
+        let unnormalized = BTreeMap::from([
+            ("Holesky ".to_string(), routers["holesky"]),
+            ("TangleTestnet".to_string(), routers["tangletestnet"]),
+        ]);
+        assert!(config
+            .enrollment_problems(" HOLESKY", &unnormalized)
+            .is_empty());
+        let mut reenrolled = config.clone();
+        reenrolled.enroll_routers("Holesky", &unnormalized).unwrap();
+        assert_eq!(
+            reenrolled.chains["holesky"].remote_routers().unwrap().len(),
+            config.chains["holesky"].remote_routers().unwrap().len()
+        );
+        extended.insert("mychain".to_string(), Address::repeat_byte(0x22));
+        assert!(matches!(
+            enrolled.enroll_routers("holesky", &extended),
+            Err(ConfigError::UnknownDomain { chain }) if chain == "mychain"
+        ));
     }
 
     #[test]
-    fn test_core_config_serialization() {
-        let config = create_sample_core_config();
+    fn test_remote_routers_and_destination_gas() {
+        let config = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        let holesky = &config.chains["holesky"];
+        assert_eq!(
+            holesky.remote_routers().unwrap()["3799"].address(),
+            "0x000000000000000000000000e7f1725e7734ce288f8367e1bb143e90bb3f0512"
+        );
+        assert_eq!(
+            holesky.destination_gas(),
+            Some(&BTreeMap::from([("3799".to_string(), "68000".to_string())]))
+        );
+        assert_eq!(
+            holesky.remote_router_for_chain("tangletestnet"),
+            Some(&holesky.remote_routers().unwrap()["3799"])
+        );
+        assert_eq!(holesky.remote_router_for_chain("sepolia"), None);
+        assert!(!holesky.extra.contains_key("remoteRouters"));
+        assert!(!holesky.extra.contains_key("destinationGas"));
+
+        let round_tripped = WarpRouteConfig::from_yaml(&config.to_yaml().unwrap()).unwrap();
+        assert_eq!(round_tripped, config);
+    }
+
+    #[test]
+    fn test_null_token_deserializes_to_none() {
+        let yaml = r#"
+        chain1:
+          interchainSecurityModule:
+            relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+            type: "trustedRelayerIsm"
+          isNft: false
+          mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+          type: "synthetic"
+          token: null
+        "#;
+        let config = WarpRouteConfig::from_yaml(yaml).unwrap();
+        assert_eq!(config.chains["chain1"].token, None);
+        assert!(!config.chains["chain1"].extra.contains_key("token"));
+
         let json = serde_json::to_string(&config).unwrap();
-        let deserialized: CoreConfig = serde_json::from_str(&json).unwrap();
-        assert_eq!(config, deserialized);
+        assert!(!json.contains("token"));
+        let json_with_null = json.replace(r#""chain1":{"#, r#""chain1":{"token":null,"#);
+        assert_ne!(json_with_null, json);
+        assert_eq!(WarpRouteConfig::from_json(&json_with_null).unwrap(), config);
     }
 
     #[test]
     fn test_warp_route_config_update() {
         let mut config = create_sample_warp_route_config();
         let new_chain_config = ChainConfig {
-            interchain_security_module: InterchainSecurityModule {
-                relayer: VALID_ADDRESS,
-                ism_type: "newIsm".to_string(),
-            },
+            interchain_security_module: InterchainSecurityModule::TestIsm(BTreeMap::new()),
             is_nft: true,
-            mailbox: VALID_ADDRESS,
-            interchain_gas_paymaster: VALID_ADDRESS,
-            owner: VALID_ADDRESS,
+            mailbox: VALID_ADDRESS.into(),
+            interchain_gas_paymaster: VALID_ADDRESS.into(),
+            owner: VALID_ADDRESS.into(),
             token_type: TokenType::Collateral,
             token: None,
+            vault: None,
+            lockbox: None,
+            xerc20: None,
+            decimals: None,
+            scale: None,
+            remote_routers: None,
+            destination_gas: None,
+            extra: BTreeMap::new(),
         };
         config.update_chain_config("chain2", new_chain_config.clone());
         assert_eq!(config.chains.get("chain2"), Some(&new_chain_config));
@@ -247,6 +2709,328 @@ mod tests {
         assert_eq!(config.owner, new_owner);
     }
 
+    /// The stdout of `hyperlane core read --chain holesky`.
+    const CORE_READ_STDOUT: &str = "Hyperlane CLI
+\u{1b}[90mReading core config on holesky...\u{1b}[39m
+defaultHook:
+  address: \"0x98aae089cad930c64a76dd2247a2ac5773a4b8ce\"
+  type: merkleTreeHook
+defaultIsm:
+  address: \"0x0bc10b0a4d3ab9c6f06a8ec5d5a2be8d1cff7a8b\"
+  relayer: \"0x742d35cc6634c0532925a3b844bc454e4438f44e\"
+  type: trustedRelayerIsm
+owner: \"0x742d35cc6634c0532925a3b844bc454e4438f44e\"
+requiredHook:
+  address: \"0x5cbf4e70448ed46c2616b04e9ebc72d29ff0cfa9\"
+  beneficiary: \"0x742d35cc6634c0532925a3b844bc454e4438f44e\"
+  maxProtocolFee: \"100000000000000000\"
+  owner: \"0x742d35cc6634c0532925a3b844bc454e4438f44e\"
+  protocolFee: \"0\"
+  type: protocolFee
+\u{1b}[32m✅ Core config written to configs/core-config.yaml\u{1b}[39m
+";
+
+    #[test]
+    fn test_parse_core_read_output() {
+        let config = CoreConfig::from_read_output(CORE_READ_STDOUT).unwrap();
+        config.validate().unwrap();
+        assert!(config.is_deployed());
+        assert_eq!(
+            config.owner(),
+            "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(config.default_ism().ism_type, IsmType::TrustedRelayerIsm);
+
+        // Plain YAML parses as well
+        let yaml = config.to_yaml().unwrap();
+        assert_eq!(CoreConfig::from_read_output(&yaml).unwrap(), config);
+
+        assert!(matches!(
+            CoreConfig::from_read_output("Error: chain not found"),
+            Err(ConfigError::NoConfigInOutput)
+        ));
+    }
+
+    #[test]
+    fn test_core_config_update_components() {
+        let mut config = create_sample_core_config();
+        let address = Address::repeat_byte(0x22);
+
+        let hook = DefaultHook::new(address, HookType::InterchainGasPaymaster);
+        config.update_default_hook(hook.clone()).unwrap();
+        assert_eq!(config.default_hook(), &hook);
+
+        let ism = DefaultIsm::new(address, address, IsmType::MessageIdMultisigIsm);
+        config.update_default_ism(ism.clone()).unwrap();
+        assert_eq!(config.default_ism(), &ism);
+
+        let required = RequiredHook::new(
+            address,
+            HookType::ProtocolFee,
+            address,
+            address,
+            "1000",
+            "100000000000000000",
+        );
+        config.update_required_hook(required.clone()).unwrap();
+        assert_eq!(config.required_hook(), &required);
+        config.validate().unwrap();
+
+        let round_tripped = CoreConfig::from_yaml(&config.to_yaml().unwrap()).unwrap();
+        assert_eq!(round_tripped, config);
+    }
+
+    #[test]
+    fn test_type_dependent_core_fields_are_optional() {
+        let yaml = r#"
+defaultHook:
+  address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  type: merkleTreeHook
+defaultIsm:
+  address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  type: messageIdMultisigIsm
+owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+requiredHook:
+  address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  type: merkleTreeHook
+"#;
+        let config = CoreConfig::from_yaml(yaml).unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.default_ism().relayer, None);
+        let round_tripped = config.to_yaml().unwrap();
+        assert!(!round_tripped.contains("relayer"));
+        assert!(!round_tripped.contains("protocolFee"));
+
+        let trusted_relayer = yaml.replace("messageIdMultisigIsm", "trustedRelayerIsm");
+        assert!(matches!(
+            CoreConfig::from_yaml(&trusted_relayer).unwrap().validate(),
+            Err(ConfigError::ZeroRelayer)
+        ));
+
+        // The required hook's type is the last field of the document
+        let (head, tail) = yaml.rsplit_once("merkleTreeHook").unwrap();
+        let protocol_fee = format!("{head}protocolFee{tail}");
+        assert!(matches!(
+            CoreConfig::from_yaml(&protocol_fee).unwrap().validate(),
+            Err(ConfigError::MissingHookField {
+                field: "beneficiary"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_core_config_updates_are_rejected() {
+        let mut config = create_sample_core_config();
+        let original = config.clone();
+
+        let no_relayer = DefaultIsm::new(VALID_ADDRESS, Address::ZERO, IsmType::TrustedRelayerIsm);
+        assert!(matches!(
+            config.update_default_ism(no_relayer),
+            Err(ConfigError::ZeroRelayer)
+        ));
+
+        let bad_fee = RequiredHook::new(
+            VALID_ADDRESS,
+            HookType::ProtocolFee,
+            VALID_ADDRESS,
+            VALID_ADDRESS,
+            "0.1 ETH",
+            "100000000000000000",
+        );
+        assert!(matches!(
+            config.update_required_hook(bad_fee),
+            Err(ConfigError::InvalidAmount {
+                field: "protocolFee",
+                ..
+            })
+        ));
+        assert_eq!(config, original);
+    }
+
+    #[test]
+    fn test_protocol_fee_hook_validation() {
+        let mut config = create_sample_core_config();
+        let hook = |beneficiary, protocol_fee| {
+            RequiredHook::new(
+                VALID_ADDRESS,
+                HookType::ProtocolFee,
+                VALID_ADDRESS,
+                beneficiary,
+                protocol_fee,
+                "100000000000000000",
+            )
+        };
+
+        config
+            .update_required_hook(hook(VALID_ADDRESS, "100000000000000000"))
+            .unwrap();
+        assert!(matches!(
+            config.update_required_hook(hook(Address::ZERO, "1000")),
+            Err(ConfigError::ZeroBeneficiary)
+        ));
+        assert!(matches!(
+            config.update_required_hook(hook(VALID_ADDRESS, "100000000000000001")),
+            Err(ConfigError::ProtocolFeeExceedsMax { protocol_fee, max_protocol_fee })
+                if protocol_fee == U256::from(100000000000000001u64)
+                    && max_protocol_fee == U256::from(100000000000000000u64)
+        ));
+
+        // Other hooks have no beneficiary to check
+        let mut merkle_tree = hook(Address::ZERO, "0");
+        merkle_tree.hook_type = HookType::MerkleTreeHook;
+        config.update_required_hook(merkle_tree).unwrap();
+    }
+
+    #[test]
+    fn test_warp_route_config_update_owner() {
+        let mut config = create_sample_warp_route_config();
+        let new_owner = Address::repeat_byte(0x11);
+        config.update_owner(new_owner);
+        assert!(config
+            .chains
+            .values()
+            .all(|chain| chain.owner == ChainAddress::Evm(new_owner)));
+    }
+
+    #[test]
+    fn test_warp_route_config_merge() {
+        let mut config = create_sample_warp_route_config();
+        let mut other = create_sample_warp_route_config();
+        let chain_config = other.chains.remove("chain1").unwrap();
+        other.update_chain_config("chain2", chain_config);
+
+        config.merge(other).unwrap();
+        assert_eq!(config.chain_names(), vec!["chain1", "chain2"]);
+    }
+
+    #[test]
+    fn test_warp_route_config_merge_duplicate_chain() {
+        let mut config = create_sample_warp_route_config();
+        let result = config.merge(create_sample_warp_route_config());
+        assert!(matches!(
+            result,
+            Err(ConfigError::DuplicateChain { name }) if name == "chain1"
+        ));
+        assert_eq!(config, create_sample_warp_route_config());
+    }
+
+    #[test]
+    fn test_chain_config_accessors() {
+        let mut config = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        assert_eq!(
+            config.chain_config("holesky").unwrap().token_type(),
+            &TokenType::Collateral
+        );
+        assert_eq!(
+            config.chain_config(" Holesky"),
+            config.chain_config("holesky")
+        );
+        assert_eq!(config.chain_config("sepolia"), None);
+
+        config.chain_config_mut("TangleTestnet").unwrap().owner = Address::repeat_byte(0x11).into();
+        assert_eq!(
+            config.chain_config("tangletestnet").unwrap().owner(),
+            &ChainAddress::Evm(Address::repeat_byte(0x11))
+        );
+        assert!(config.chain_config_mut("sepolia").is_none());
+
+        let chains: Vec<(&str, &TokenType)> = config
+            .iter_chains()
+            .map(|(name, chain)| (name.as_str(), chain.token_type()))
+            .collect();
+        assert_eq!(
+            chains,
+            [
+                ("holesky", &TokenType::Collateral),
+                ("tangletestnet", &TokenType::Synthetic)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let chain_config = create_sample_warp_route_config().chains["chain1"].clone();
+        let mut forward = WarpRouteConfig::from_fragments(vec![]).unwrap();
+        forward.update_chain_config("chain1", chain_config.clone());
+        forward.update_chain_config("chain2", chain_config.clone());
+        let mut backward = WarpRouteConfig::from_fragments(vec![]).unwrap();
+        backward.update_chain_config("chain2", chain_config.clone());
+        backward.update_chain_config("chain1", chain_config);
+        assert_eq!(
+            forward.content_hash().unwrap(),
+            backward.content_hash().unwrap()
+        );
+        assert_ne!(
+            forward.content_hash().unwrap(),
+            create_sample_warp_route_config().content_hash().unwrap()
+        );
+
+        backward.update_owner(Address::repeat_byte(0x11));
+        assert_ne!(
+            forward.content_hash().unwrap(),
+            backward.content_hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_chain_names_are_normalized() {
+        let yaml = COLLATERAL_VAULT_CONFIG.replacen("chain1:", "\"Chain1 \":", 1);
+        let config = WarpRouteConfig::from_yaml(&yaml).unwrap();
+        assert_eq!(config.chain_names(), ["chain1"]);
+        assert_eq!(
+            config,
+            WarpRouteConfig::from_yaml(COLLATERAL_VAULT_CONFIG).unwrap()
+        );
+
+        let round_trip = WarpRouteConfig::from_yaml(&config.to_yaml().unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+
+        let mut updated = create_sample_warp_route_config();
+        let chain_config = updated.chains["chain1"].clone();
+        updated.update_chain_config("Chain1 ", chain_config);
+        assert_eq!(updated, create_sample_warp_route_config());
+
+        let duplicate = format!(
+            "{yaml}\n{}",
+            COLLATERAL_VAULT_CONFIG.trim_start_matches('\n')
+        );
+        // serde reports the `DuplicateChain` of `try_from` as a deserialization error
+        let err = WarpRouteConfig::from_yaml(&duplicate).unwrap_err();
+        assert!(matches!(err, ConfigError::YamlDeserializationError { .. }));
+        assert!(
+            err.to_string()
+                .contains("Chain `chain1` is configured more than once"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_warp_route_config_from_fragments() {
+        let fragments = ["chain1", "chain2", "chain3"]
+            .into_iter()
+            .map(|name| {
+                let mut fragment = create_sample_warp_route_config();
+                let chain_config = fragment.chains.remove("chain1").unwrap();
+                fragment.update_chain_config(name, chain_config);
+                fragment
+            })
+            .collect();
+
+        let config = WarpRouteConfig::from_fragments(fragments).unwrap();
+        assert_eq!(config.chain_names(), vec!["chain1", "chain2", "chain3"]);
+
+        let duplicated = vec![
+            create_sample_warp_route_config(),
+            create_sample_warp_route_config(),
+        ];
+        assert!(matches!(
+            WarpRouteConfig::from_fragments(duplicated),
+            Err(ConfigError::DuplicateChain { .. })
+        ));
+    }
+
     #[test]
     fn test_warp_route_config_from_json() {
         let json = r#"
@@ -290,7 +3074,7 @@ mod tests {
         "#;
         let config = CoreConfig::from_yaml(yaml).unwrap();
         assert_eq!(config.owner, VALID_ADDRESS);
-        assert_eq!(config.default_hook.hook_type, "merkleTreeHook");
+        assert_eq!(config.default_hook.hook_type, HookType::MerkleTreeHook);
     }
 
     #[test]
@@ -333,7 +3117,119 @@ mod tests {
         "#;
         let config = CoreConfig::try_from(yaml.as_bytes()).unwrap();
         assert_eq!(config.owner, VALID_ADDRESS);
-        assert_eq!(config.default_hook.hook_type, "merkleTreeHook");
+        assert_eq!(config.default_hook.hook_type, HookType::MerkleTreeHook);
+    }
+
+    #[test]
+    fn test_warp_route_config_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = create_sample_warp_route_config();
+
+        let json_path = dir.path().join("warp.json");
+        std::fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+        assert_eq!(WarpRouteConfig::from_path(&json_path).unwrap(), config);
+
+        let yaml_path = dir.path().join("warp.yaml");
+        std::fs::write(&yaml_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(WarpRouteConfig::from_path(&yaml_path).unwrap(), config);
+    }
+
+    #[tokio::test]
+    async fn test_warp_route_config_from_reader() {
+        let expected = WarpRouteConfig::from_yaml(COMPREHENSIVE_WARP_ROUTE_CONFIG).unwrap();
+        let yaml = COMPREHENSIVE_WARP_ROUTE_CONFIG.as_bytes();
+        assert_eq!(WarpRouteConfig::from_reader(yaml).unwrap(), expected);
+        assert_eq!(
+            WarpRouteConfig::from_async_reader(yaml).await.unwrap(),
+            expected
+        );
+
+        let json = expected.to_json().unwrap();
+        let reader = std::io::Cursor::new(json.as_bytes());
+        assert_eq!(WarpRouteConfig::from_json_reader(reader).unwrap(), expected);
+        // JSON is YAML as well
+        assert_eq!(
+            WarpRouteConfig::from_reader(json.as_bytes()).unwrap(),
+            expected
+        );
+
+        assert!(matches!(
+            WarpRouteConfig::from_reader("chain1:\n  type: [unterminated\n".as_bytes()),
+            Err(ConfigError::YamlDeserializationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_core_config_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = create_sample_core_config();
+
+        let json_path = dir.path().join("core.json");
+        std::fs::write(&json_path, serde_json::to_string(&config).unwrap()).unwrap();
+        assert_eq!(CoreConfig::from_path(&json_path).unwrap(), config);
+
+        let yml_path = dir.path().join("core.yml");
+        std::fs::write(&yml_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(CoreConfig::from_path(&yml_path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_from_path_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            WarpRouteConfig::from_path(&dir.path().join("missing.yaml")),
+            Err(ConfigError::InFile { source, .. }) if matches!(*source, ConfigError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_path_error_names_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("warp.yaml");
+        std::fs::write(&path, "chain1:\n  type: [unterminated\n").unwrap();
+
+        let err = WarpRouteConfig::from_path(&path).unwrap_err();
+        assert!(matches!(
+            &err,
+            ConfigError::InFile { source, .. }
+                if matches!(**source, ConfigError::YamlDeserializationError { .. })
+        ));
+        let message = err.to_string();
+        assert!(
+            message.starts_with(&format!("error in '{}': ", path.display())),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_names_chain() {
+        let yaml = COMPREHENSIVE_WARP_ROUTE_CONFIG.replace("type: synthetic", "type: synthetik");
+        let err = WarpRouteConfig::from_yaml(&yaml).unwrap_err();
+        assert!(matches!(
+            &err,
+            ConfigError::YamlDeserializationError { context: Some(context), .. }
+                if context == "chain `tangletestnet`"
+        ));
+        let message = err.to_string();
+        assert!(
+            message
+                .starts_with("failed parsing chain `tangletestnet`: YAML deserialization error: "),
+            "{message}"
+        );
+
+        let json = r#"{"holesky": {"type": "collateral"}}"#;
+        assert!(WarpRouteConfig::from_json(json)
+            .unwrap_err()
+            .to_string()
+            .starts_with("failed parsing chain `holesky`: JSON deserialization error: "));
+
+        // Without a chain to blame, e.g. when the YAML itself is malformed, there is no label
+        let err = WarpRouteConfig::from_yaml("holesky: [unterminated").unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::YamlDeserializationError { context: None, .. }
+        ));
+        assert!(err.to_string().starts_with("YAML deserialization error: "));
     }
 
     #[test]
@@ -348,4 +3244,32 @@ mod tests {
             Err(ConfigError::InvalidUtf8)
         ));
     }
+
+    #[test]
+    fn test_oversized_payload_is_rejected_before_parsing() {
+        // Neither valid UTF-8 nor YAML, so only the size check can have rejected it
+        let oversized = vec![0xff; 101];
+        assert!(matches!(
+            WarpRouteConfig::from_payload(&oversized, 100),
+            Err(ConfigError::PayloadTooLarge {
+                size: 101,
+                limit: 100
+            })
+        ));
+        assert!(matches!(
+            CoreConfig::from_payload(&oversized, 100),
+            Err(ConfigError::PayloadTooLarge { .. })
+        ));
+        assert!(matches!(
+            WarpRouteConfig::try_from(vec![b' '; DEFAULT_MAX_PAYLOAD_SIZE + 1].as_slice()),
+            Err(ConfigError::PayloadTooLarge { .. })
+        ));
+
+        let config = COLLATERAL_VAULT_CONFIG.as_bytes();
+        assert!(WarpRouteConfig::from_payload(config, config.len()).is_ok());
+        assert!(matches!(
+            WarpRouteConfig::from_payload(config, config.len() - 1),
+            Err(ConfigError::PayloadTooLarge { .. })
+        ));
+    }
 }
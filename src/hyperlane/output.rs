@@ -0,0 +1,518 @@
+use alloy_primitives::{keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Addresses of the contracts deployed for a warp route on one chain.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DeployedAddresses {
+    /// The warp route's router, i.e. the `HypERC20`/`HypERC20Collateral`/... contract.
+    pub router: Address,
+    /// The token standard the router implements, e.g. `EvmHypCollateral`.
+    pub standard: String,
+    /// The token locked by the router, for collateral routes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collateral: Option<Address>,
+    /// The token's metadata, as `hyperlane warp deploy` reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+}
+
+/// The contracts deployed for a warp route, keyed by chain name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DeployedRoute {
+    pub chains: BTreeMap<String, DeployedAddresses>,
+}
+
+/// A [`DeployedRoute`] along with its [`DeployedRoute::route_id`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteSummary {
+    pub route_id: B256,
+    pub chains: BTreeMap<String, DeployedAddresses>,
+}
+
+/// The outcome of a `hyperlane warp deploy` by chain, see
+/// [`PartialDeployment::from_deploy_output`].
+///
+/// When only some chains deployed, a retry only needs to target [`PartialDeployment::failed`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct PartialDeployment {
+    /// The contracts deployed on the chains that succeeded.
+    pub deployed: DeployedRoute,
+    /// Why each of the other chains failed, keyed by chain name.
+    pub failed: BTreeMap<String, String>,
+}
+
+#[derive(Error, Debug)]
+pub enum OutputError {
+    #[error("No `tokens:` deployment summary found in the command output")]
+    MissingSummary,
+    #[error("YAML deserialization error: {0}")]
+    YamlDeserializationError(#[from] serde_yaml::Error),
+    #[error("Deployment summary for `{chain}` is missing `{key}`")]
+    MissingKey { chain: String, key: &'static str },
+    #[error("Invalid address `{0}`")]
+    InvalidAddress(String),
+    #[error("The deployed route has no chains")]
+    EmptyRoute,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Deserialize)]
+struct DeploySummary {
+    tokens: Vec<DeployedToken>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeployedToken {
+    chain_name: Option<String>,
+    standard: Option<String>,
+    address_or_denom: Option<String>,
+    collateral_address_or_denom: Option<String>,
+    symbol: Option<String>,
+    name: Option<String>,
+    decimals: Option<u8>,
+}
+
+/// A warp route's entry in the Hyperlane registry, see
+/// [`DeployedRoute::registry_config_path`]. Addresses are checksummed, as in the registry.
+#[derive(Serialize)]
+struct RegistryWarpRoute {
+    tokens: Vec<RegistryToken>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistryToken {
+    chain_name: String,
+    standard: String,
+    address_or_denom: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collateral_address_or_denom: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decimals: Option<u8>,
+    connections: Vec<RegistryConnection>,
+}
+
+#[derive(Serialize)]
+struct RegistryConnection {
+    /// `<protocol>|<chain>|<router>`
+    token: String,
+}
+
+impl DeployedRoute {
+    /// Extracts the deployed addresses from the output of `hyperlane warp deploy`.
+    ///
+    /// The CLI finishes by printing the route's warp core config as YAML: a `tokens:` list with
+    /// one entry per chain. Everything around it is progress logging and is ignored.
+    pub fn from_deploy_output(output: &str) -> Result<Self, OutputError> {
+        let summary: DeploySummary = serde_yaml::from_str(&extract_yaml_block(output, "tokens:")?)?;
+
+        let mut chains = BTreeMap::new();
+        for (i, token) in summary.tokens.into_iter().enumerate() {
+            let chain = token.chain_name.ok_or_else(|| OutputError::MissingKey {
+                chain: format!("token #{i}"),
+                key: "chainName",
+            })?;
+            let router = token
+                .address_or_denom
+                .ok_or_else(|| OutputError::MissingKey {
+                    chain: chain.clone(),
+                    key: "addressOrDenom",
+                })?;
+            let standard = token.standard.ok_or_else(|| OutputError::MissingKey {
+                chain: chain.clone(),
+                key: "standard",
+            })?;
+
+            let addresses = DeployedAddresses {
+                router: parse_address(&router)?,
+                standard,
+                collateral: token
+                    .collateral_address_or_denom
+                    .as_deref()
+                    .map(parse_address)
+                    .transpose()?,
+                symbol: token.symbol,
+                name: token.name,
+                decimals: token.decimals,
+            };
+            chains.insert(chain, addresses);
+        }
+        Ok(Self { chains })
+    }
+
+    /// A deterministic identifier for the route.
+    ///
+    /// This is the keccak256 hash of, for each chain in name order, the chain name followed by a
+    /// zero byte, the router address, and the collateral address if there is one. Redeploying
+    /// the same route yields new routers, and so a new id.
+    pub fn route_id(&self) -> B256 {
+        let mut preimage = Vec::new();
+        for (chain, addresses) in &self.chains {
+            preimage.extend_from_slice(chain.as_bytes());
+            preimage.push(0);
+            preimage.extend_from_slice(addresses.router.as_slice());
+            if let Some(collateral) = addresses.collateral {
+                preimage.extend_from_slice(collateral.as_slice());
+            }
+        }
+        keccak256(preimage)
+    }
+
+    /// Where the route's registry entry goes, relative to the registry's root:
+    /// `deployments/warp_routes/<SYMBOL>/<chain>-<chain>...-config.yaml`, with the chains in
+    /// name order and the symbol of the first one that reports it.
+    pub fn registry_config_path(&self) -> Result<PathBuf, OutputError> {
+        let first = self.chains.keys().next().ok_or(OutputError::EmptyRoute)?;
+        let symbol = self
+            .chains
+            .values()
+            .find_map(|addresses| addresses.symbol.as_deref())
+            .ok_or_else(|| OutputError::MissingKey {
+                chain: first.clone(),
+                key: "symbol",
+            })?;
+        let chains: Vec<&str> = self.chains.keys().map(String::as_str).collect();
+        Ok(Path::new("deployments/warp_routes")
+            .join(symbol)
+            .join(format!("{}-config.yaml", chains.join("-"))))
+    }
+
+    /// Writes the route's registry entry, its warp core config, under `out_dir` as it is laid
+    /// out in the registry (see [`DeployedRoute::registry_config_path`]), ready to be committed
+    /// to it. Every router is listed as connected to the routers on all the other chains.
+    pub fn write_registry_artifacts(&self, out_dir: &Path) -> Result<(), OutputError> {
+        let path = out_dir.join(self.registry_config_path()?);
+        let tokens = self
+            .chains
+            .iter()
+            .map(|(chain, addresses)| RegistryToken {
+                chain_name: chain.clone(),
+                standard: addresses.standard.clone(),
+                address_or_denom: addresses.router.to_checksum(None),
+                collateral_address_or_denom: addresses
+                    .collateral
+                    .map(|collateral| collateral.to_checksum(None)),
+                symbol: addresses.symbol.clone(),
+                name: addresses.name.clone(),
+                decimals: addresses.decimals,
+                connections: self
+                    .chains
+                    .iter()
+                    .filter(|(remote, _)| *remote != chain)
+                    .map(|(remote, addresses)| RegistryConnection {
+                        token: format!("ethereum|{remote}|{}", addresses.router.to_checksum(None)),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_yaml::to_string(&RegistryWarpRoute { tokens })?)?;
+        Ok(())
+    }
+
+    pub fn summary(&self) -> RouteSummary {
+        RouteSummary {
+            route_id: self.route_id(),
+            chains: self.chains.clone(),
+        }
+    }
+}
+
+impl PartialDeployment {
+    /// Sorts the `chains` of a route into those the output of `hyperlane warp deploy` reports
+    /// as deployed, in its `tokens:` summary, and those it doesn't.
+    ///
+    /// A deploy that failed part way may print no summary at all, in which case no chain is
+    /// deployed. The reason given for a failed chain is the last line of `output` reporting an
+    /// error on it.
+    pub fn from_deploy_output(output: &str, chains: &[&str]) -> Result<Self, OutputError> {
+        let deployed = match DeployedRoute::from_deploy_output(output) {
+            Ok(route) => route,
+            Err(OutputError::MissingSummary) => DeployedRoute::default(),
+            Err(e) => return Err(e),
+        };
+        let failed = chains
+            .iter()
+            .filter(|chain| !deployed.chains.contains_key(**chain))
+            .map(|chain| (chain.to_string(), failure_reason(output, chain)))
+            .collect();
+        Ok(Self { deployed, failed })
+    }
+
+    /// Whether every chain of the route was deployed.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    pub fn failed_chains(&self) -> Vec<&str> {
+        self.failed.keys().map(String::as_str).collect()
+    }
+}
+
+impl fmt::Display for PartialDeployment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let deployed: Vec<&str> = self.deployed.chains.keys().map(String::as_str).collect();
+        let failed: Vec<String> = self
+            .failed
+            .iter()
+            .map(|(chain, reason)| format!("{chain} ({reason})"))
+            .collect();
+        write!(
+            f,
+            "deployed on {}, failed on {}",
+            deployed.join(", "),
+            failed.join(", ")
+        )
+    }
+}
+
+/// The last line of `output` reporting an error on `chain`.
+fn failure_reason(output: &str, chain: &str) -> String {
+    output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| {
+            let lower = line.to_lowercase();
+            line.contains(chain) && (lower.contains("error") || lower.contains("fail"))
+        })
+        .map_or_else(
+            || "not in the deployment summary".to_string(),
+            str::to_string,
+        )
+}
+
+fn parse_address(address: &str) -> Result<Address, OutputError> {
+    address
+        .parse()
+        .map_err(|_| OutputError::InvalidAddress(address.to_string()))
+}
+
+/// Cuts the YAML mapping starting at the line `key` out of `output`, along with every line
+/// nested under it, and strips the indentation the CLI adds when printing it.
+fn extract_yaml_block(output: &str, key: &str) -> Result<String, OutputError> {
+    let mut lines = output.lines().skip_while(|line| line.trim() != key);
+    let first = lines.next().ok_or(OutputError::MissingSummary)?;
+    let indent = first.len() - first.trim_start().len();
+
+    let mut block = vec![&first[indent..]];
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let line_indent = line.len() - trimmed.len();
+        let nested = line_indent > indent || (line_indent == indent && trimmed.starts_with("- "));
+        if !nested {
+            break;
+        }
+        block.push(&line[indent..]);
+    }
+    Ok(block.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tail of a `hyperlane warp deploy` run against holesky and tangletestnet.
+    const WARP_DEPLOY_OUTPUT: &str = r#"
+Deploying contracts to holesky...
+Deploying contracts to tangletestnet...
+✅ Warp contract deployment complete
+Writing deployment artifacts...
+    tokens:
+      - chainName: holesky
+        standard: EvmHypCollateral
+        decimals: 18
+        symbol: TNT
+        name: Tangle
+        addressOrDenom: "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+        collateralAddressOrDenom: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        connections:
+          - token: ethereum|tangletestnet|0xe7f1725e7734ce288f8367e1bb143e90bb3f0512
+      - chainName: tangletestnet
+        standard: EvmHypSynthetic
+        decimals: 18
+        symbol: TNT
+        name: Tangle
+        addressOrDenom: "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+        connections:
+          - token: ethereum|holesky|0x5fbdb2315678afecb367f032d93f642f64180aa3
+⛽️ Gas Usage Statistics
+"#;
+
+    #[test]
+    fn test_deployed_route_from_deploy_output() {
+        let route = DeployedRoute::from_deploy_output(WARP_DEPLOY_OUTPUT).unwrap();
+        assert_eq!(route.chains.len(), 2);
+
+        let holesky = &route.chains["holesky"];
+        assert_eq!(holesky.standard, "EvmHypCollateral");
+        assert_eq!(
+            holesky.router,
+            "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(
+            holesky.collateral,
+            Some(
+                "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+                    .parse()
+                    .unwrap()
+            )
+        );
+
+        let tangle = &route.chains["tangletestnet"];
+        assert_eq!(tangle.standard, "EvmHypSynthetic");
+        assert_eq!(tangle.collateral, None);
+    }
+
+    #[test]
+    fn test_route_id() {
+        let route = DeployedRoute::from_deploy_output(WARP_DEPLOY_OUTPUT).unwrap();
+        assert_eq!(route.route_id(), route.clone().route_id());
+
+        let mut redeployed = route.clone();
+        redeployed.chains.get_mut("tangletestnet").unwrap().router =
+            "0x9fe46736679d2d9a65f0992f2272de9f3c7fa6e0"
+                .parse()
+                .unwrap();
+        assert_ne!(route.route_id(), redeployed.route_id());
+
+        let mut fewer_chains = route.clone();
+        fewer_chains.chains.remove("tangletestnet");
+        assert_ne!(route.route_id(), fewer_chains.route_id());
+    }
+
+    #[test]
+    fn test_write_registry_artifacts() {
+        let route = DeployedRoute::from_deploy_output(WARP_DEPLOY_OUTPUT).unwrap();
+        assert_eq!(route.chains["holesky"].symbol.as_deref(), Some("TNT"));
+        assert_eq!(route.chains["holesky"].decimals, Some(18));
+
+        let out_dir = tempfile::tempdir().unwrap();
+        route.write_registry_artifacts(out_dir.path()).unwrap();
+
+        let path = out_dir
+            .path()
+            .join("deployments/warp_routes/TNT/holesky-tangletestnet-config.yaml");
+        assert_eq!(
+            route.registry_config_path().unwrap(),
+            Path::new("deployments/warp_routes/TNT/holesky-tangletestnet-config.yaml")
+        );
+        let written: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let expected: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+            tokens:
+              - chainName: holesky
+                standard: EvmHypCollateral
+                addressOrDenom: "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+                collateralAddressOrDenom: "0x742d35Cc6634C0532925a3b844Bc454e4438f44e"
+                symbol: TNT
+                name: Tangle
+                decimals: 18
+                connections:
+                  - token: ethereum|tangletestnet|0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512
+              - chainName: tangletestnet
+                standard: EvmHypSynthetic
+                addressOrDenom: "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512"
+                symbol: TNT
+                name: Tangle
+                decimals: 18
+                connections:
+                  - token: ethereum|holesky|0x5FbDB2315678afecb367f032d93F642f64180aa3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(written, expected);
+
+        let mut unnamed = route.clone();
+        for addresses in unnamed.chains.values_mut() {
+            addresses.symbol = None;
+        }
+        assert!(matches!(
+            unnamed.write_registry_artifacts(out_dir.path()),
+            Err(OutputError::MissingKey { key: "symbol", .. })
+        ));
+        assert!(matches!(
+            DeployedRoute::default().registry_config_path(),
+            Err(OutputError::EmptyRoute)
+        ));
+    }
+
+    #[test]
+    fn test_partial_deployment() {
+        let output = format!(
+            "Deploying contracts to sepolia...\n\
+             Error: failed to deploy to sepolia: insufficient funds for gas\n{WARP_DEPLOY_OUTPUT}"
+        );
+        let chains = ["holesky", "sepolia", "tangletestnet"];
+        let deployment = PartialDeployment::from_deploy_output(&output, &chains).unwrap();
+
+        assert!(!deployment.is_complete());
+        assert_eq!(
+            deployment.deployed.chains.keys().collect::<Vec<_>>(),
+            ["holesky", "tangletestnet"]
+        );
+        assert_eq!(deployment.failed_chains(), ["sepolia"]);
+        assert_eq!(
+            deployment.failed["sepolia"],
+            "Error: failed to deploy to sepolia: insufficient funds for gas"
+        );
+        assert_eq!(
+            deployment.to_string(),
+            "deployed on holesky, tangletestnet, failed on sepolia \
+             (Error: failed to deploy to sepolia: insufficient funds for gas)"
+        );
+
+        let complete =
+            PartialDeployment::from_deploy_output(WARP_DEPLOY_OUTPUT, &chains[..1]).unwrap();
+        assert!(complete.is_complete());
+
+        let nothing = PartialDeployment::from_deploy_output("Error: boom", &chains).unwrap();
+        assert!(nothing.deployed.chains.is_empty());
+        assert_eq!(nothing.failed["holesky"], "not in the deployment summary");
+    }
+
+    #[test]
+    fn test_deploy_output_without_summary() {
+        assert!(matches!(
+            DeployedRoute::from_deploy_output("Error: insufficient funds"),
+            Err(OutputError::MissingSummary)
+        ));
+    }
+
+    #[test]
+    fn test_deploy_output_missing_address() {
+        let output = r#"
+    tokens:
+      - chainName: holesky
+        standard: EvmHypCollateral
+"#;
+        assert!(matches!(
+            DeployedRoute::from_deploy_output(output),
+            Err(OutputError::MissingKey { chain, key: "addressOrDenom" }) if chain == "holesky"
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+use super::ConfigError;
+use crate::runner::CommandRunner;
+use alloy_primitives::Address;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// The schemes an RPC URL may have, see [`Registry::validate_rpc_overrides`].
+const RPC_URL_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// The Hyperlane registry the `hyperlane` CLI reads chain metadata and deployed addresses from.
+#[derive(Debug)]
+pub struct Registry;
+
+impl Registry {
+    /// Whether `hyperlane registry init` has already set up a registry in `dir`, going by the
+    /// chain metadata it writes.
+    pub fn is_initialized(dir: &Path) -> bool {
+        std::fs::read_dir(dir.join("chains"))
+            .map(|mut chains| chains.next().is_some())
+            .unwrap_or(false)
+    }
+
+    /// Checks that every RPC URL of `overrides`, by chain name, is an HTTP URL, the only kind of
+    /// RPC URL the chain metadata has room for, see [`Registry::write_rpc_overrides`].
+    pub fn validate_rpc_overrides(overrides: &HashMap<String, String>) -> Result<(), ConfigError> {
+        for (chain, url) in overrides {
+            let valid = reqwest::Url::parse(url).is_ok_and(|parsed| {
+                RPC_URL_SCHEMES.contains(&parsed.scheme()) && parsed.host().is_some()
+            });
+            if !valid {
+                return Err(ConfigError::InvalidRpcUrl {
+                    chain: chain.clone(),
+                    url: url.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the `hyperlane` CLI use the RPC URL of `overrides` for each chain of it, by chain
+    /// name, instead of those of the registry it reads from.
+    ///
+    /// The CLI layers the chain metadata of the local registry in `dir` over that of the
+    /// registry, so the URL is written as the `http` URL of the `rpcUrls` of the chain's
+    /// `metadata.yaml` there, keeping the rest of the metadata. `overrides` must have passed
+    /// [`Registry::validate_rpc_overrides`]. Chains without an override are left alone.
+    pub fn write_rpc_overrides(
+        dir: &Path,
+        overrides: &HashMap<String, String>,
+    ) -> Result<(), ConfigError> {
+        for (chain, url) in overrides {
+            let chain_dir = dir.join("chains").join(chain);
+            let path = chain_dir.join("metadata.yaml");
+            let mut metadata = match std::fs::read_to_string(&path) {
+                Ok(metadata) => serde_yaml::from_str(&metadata)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_yaml::Mapping::new(),
+                Err(e) => return Err(e.into()),
+            };
+            let mut rpc_url = serde_yaml::Mapping::new();
+            rpc_url.insert("http".into(), url.as_str().into());
+            metadata.insert("rpcUrls".into(), vec![rpc_url].into());
+            std::fs::create_dir_all(&chain_dir)?;
+            std::fs::write(&path, serde_yaml::to_string(&metadata)?)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `hyperlane registry addresses` and returns the address of every contract, by chain
+    /// and contract name (e.g. `"holesky"` → `"mailbox"` → address).
+    pub async fn all_addresses(
+        runner: &mut CommandRunner,
+    ) -> Result<HashMap<String, HashMap<String, Address>>, ConfigError> {
+        let name = "run registry addresses";
+        let command = runner.cli().registry_addresses().to_string();
+        let output = runner
+            .run_and_focus_multiple(vec![(name, command.as_str())])
+            .await?
+            .remove(name)
+            .unwrap_or_default();
+        Self::parse_addresses(&output)
+    }
+
+    /// Runs `hyperlane registry list` and returns the names of the chains the registry knows.
+    pub async fn known_chains(runner: &mut CommandRunner) -> Result<HashSet<String>, ConfigError> {
+        let name = "run registry list";
+        let command = runner.cli().registry_list().to_string();
+        let output = runner
+            .run_and_focus_multiple(vec![(name, command.as_str())])
+            .await?
+            .remove(name)
+            .unwrap_or_default();
+        Ok(Self::parse_chain_list(&output))
+    }
+
+    /// Parses the output of `hyperlane registry list`, a table with a row per chain whose first
+    /// column is the chain name. Everything around the table is ignored.
+    pub fn parse_chain_list(output: &str) -> HashSet<String> {
+        output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('│')?.split('│').next())
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "(index)")
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Parses the output of `hyperlane registry addresses`, which is YAML (or JSON, which is
+    /// also valid YAML).
+    ///
+    /// Addresses that aren't EVM addresses, as registered for Cosmos and Sealevel chains, are
+    /// left out.
+    pub fn parse_addresses(
+        output: &str,
+    ) -> Result<HashMap<String, HashMap<String, Address>>, ConfigError> {
+        let chains: HashMap<String, HashMap<String, String>> = serde_yaml::from_str(output)?;
+        Ok(chains
+            .into_iter()
+            .map(|(chain, contracts)| {
+                let contracts = contracts
+                    .into_iter()
+                    .filter_map(|(contract, address)| Some((contract, address.parse().ok()?)))
+                    .collect();
+                (chain, contracts)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY_ADDRESSES_OUTPUT: &str = r#"
+holesky:
+  domainRoutingIsmFactory: "0x0Bc10B0A4d3aB9C6f06A8Ec5D5a2bE8d1cFf7A8B"
+  interchainGasPaymaster: "0x5CBf4e70448Ed46c2616b04e9ebc72D29FF0cfA9"
+  mailbox: "0x46f7C5D896bbeC89bE1B19e4485e59b4Be49e9Cc"
+  merkleTreeHook: "0x98AAE089CaD930C64a76dD2247a2aC5773a4B8cE"
+neutron:
+  mailbox: "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4"
+"#;
+
+    const REGISTRY_LIST_OUTPUT: &str = r#"
+Hyperlane chains:
+------------------------------
+┌───────────────┬────────────────┬──────────┬──────────┬──────────┐
+│ (index)       │ Display Name   │ Chain Id │ Domain Id│ Protocol │
+├───────────────┼────────────────┼──────────┼──────────┼──────────┤
+│ holesky       │ 'Holesky'      │ 17000    │ 17000    │ 'ethereum' │
+│ sepolia       │ 'Sepolia'      │ 11155111 │ 11155111 │ 'ethereum' │
+│ tangletestnet │ 'Tangle Testnet' │ 3799   │ 3799     │ 'ethereum' │
+└───────────────┴────────────────┴──────────┴──────────┴──────────┘
+"#;
+
+    #[tokio::test]
+    async fn test_known_chains_runs_registry_list() {
+        use gadget_sdk::executor::process::manager::GadgetProcessManager;
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+            .with_dry_run_output("run registry list", REGISTRY_LIST_OUTPUT);
+        let chains = Registry::known_chains(&mut runner).await.unwrap();
+        assert_eq!(
+            chains,
+            HashSet::from([
+                "holesky".to_string(),
+                "sepolia".to_string(),
+                "tangletestnet".to_string()
+            ])
+        );
+        assert_eq!(runner.history()[0].1, "hyperlane registry list");
+
+        assert!(Registry::parse_chain_list("no table here").is_empty());
+    }
+
+    #[test]
+    fn test_rpc_overrides() {
+        let registry_dir = tempfile::tempdir().unwrap();
+        let sepolia = registry_dir.path().join("chains").join("sepolia");
+        std::fs::create_dir_all(&sepolia).unwrap();
+        let sepolia_metadata = "chainId: 11155111\nrpcUrls:\n- http: https://rpc.sepolia.org\n";
+        std::fs::write(sepolia.join("metadata.yaml"), sepolia_metadata).unwrap();
+        let holesky = registry_dir.path().join("chains").join("holesky");
+        std::fs::create_dir_all(&holesky).unwrap();
+        std::fs::write(holesky.join("metadata.yaml"), "chainId: 17000\n").unwrap();
+
+        let overrides = HashMap::from([(
+            "holesky".to_string(),
+            "https://holesky.example.com/v1/key".to_string(),
+        )]);
+        Registry::validate_rpc_overrides(&overrides).unwrap();
+        Registry::write_rpc_overrides(registry_dir.path(), &overrides).unwrap();
+        let metadata: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(holesky.join("metadata.yaml")).unwrap())
+                .unwrap();
+        assert_eq!(metadata["chainId"], 17000);
+        assert_eq!(
+            metadata["rpcUrls"][0]["http"],
+            "https://holesky.example.com/v1/key"
+        );
+        // Chains without an override keep the registry's URLs
+        assert_eq!(
+            std::fs::read_to_string(sepolia.join("metadata.yaml")).unwrap(),
+            sepolia_metadata
+        );
+
+        let overrides = HashMap::from([(
+            "tangletestnet".to_string(),
+            "http://tangle.example.com:9944".to_string(),
+        )]);
+        Registry::validate_rpc_overrides(&overrides).unwrap();
+        Registry::write_rpc_overrides(registry_dir.path(), &overrides).unwrap();
+        let metadata = std::fs::read_to_string(
+            registry_dir
+                .path()
+                .join("chains/tangletestnet/metadata.yaml"),
+        )
+        .unwrap();
+        assert_eq!(
+            metadata,
+            "rpcUrls:\n- http: http://tangle.example.com:9944\n"
+        );
+
+        // The metadata has no room for WebSocket URLs
+        for url in [
+            "wss://tangle.example.com",
+            "ws://localhost:9944",
+            "ftp://rpc.example.com",
+            "holesky.example.com",
+            "http://",
+        ] {
+            let overrides = HashMap::from([("holesky".to_string(), url.to_string())]);
+            assert!(matches!(
+                Registry::validate_rpc_overrides(&overrides),
+                Err(ConfigError::InvalidRpcUrl { chain, url: invalid })
+                    if chain == "holesky" && invalid == url
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_registry_addresses() {
+        let addresses = Registry::parse_addresses(REGISTRY_ADDRESSES_OUTPUT).unwrap();
+        assert_eq!(addresses["holesky"].len(), 4);
+        assert_eq!(
+            addresses["holesky"]["mailbox"],
+            "0x46f7c5d896bbec89be1b19e4485e59b4be49e9cc"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert!(addresses["neutron"].is_empty());
+
+        let json = serde_json::to_string(&addresses).unwrap();
+        assert_eq!(Registry::parse_addresses(&json).unwrap(), addresses);
+
+        assert!(Registry::parse_addresses("not a registry").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_all_addresses_runs_registry_addresses() {
+        use gadget_sdk::executor::process::manager::GadgetProcessManager;
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+            .with_dry_run_output("run registry addresses", REGISTRY_ADDRESSES_OUTPUT);
+        let addresses = Registry::all_addresses(&mut runner).await.unwrap();
+        assert_eq!(addresses["holesky"].len(), 4);
+        assert_eq!(
+            runner.history(),
+            [(
+                "run registry addresses".to_string(),
+                "hyperlane registry addresses".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use super::{HookType, IsmType, TokenType, UNMODELLED_CHAIN_FIELDS};
+use serde_json::{json, Map, Value};
+
+/// The JSON Schema dialect of the schemas.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The hook types [`HookType`] knows. Others are accepted too, as written.
+const HOOK_TYPES: [HookType; 8] = [
+    HookType::MerkleTreeHook,
+    HookType::ProtocolFee,
+    HookType::InterchainGasPaymaster,
+    HookType::AggregationHook,
+    HookType::PausableHook,
+    HookType::DomainRoutingHook,
+    HookType::FallbackRoutingHook,
+    HookType::OpStackHook,
+];
+
+/// The schema of a [`WarpRouteConfig`](super::WarpRouteConfig), see
+/// [`WarpRouteConfig::json_schema`](super::WarpRouteConfig::json_schema).
+pub fn warp_route_config() -> Value {
+    json!({
+        "$schema": DIALECT,
+        "title": "Warp route config",
+        "description": "The config of each chain of a Hyperlane warp route, by chain name.",
+        "type": "object",
+        "additionalProperties": { "$ref": "#/$defs/chainConfig" },
+        "$defs": defs(),
+    })
+}
+
+/// The schema of a [`CoreConfig`](super::CoreConfig), see
+/// [`CoreConfig::json_schema`](super::CoreConfig::json_schema).
+pub fn core_config() -> Value {
+    json!({
+        "$schema": DIALECT,
+        "title": "Core config",
+        "description": "The Hyperlane core contracts of a chain.",
+        "type": "object",
+        "required": ["defaultHook", "defaultIsm", "owner", "requiredHook"],
+        "properties": {
+            "defaultHook": { "$ref": "#/$defs/defaultHook" },
+            "defaultIsm": { "$ref": "#/$defs/defaultIsm" },
+            "owner": { "$ref": "#/$defs/address" },
+            "requiredHook": { "$ref": "#/$defs/requiredHook" },
+        },
+        "$defs": defs(),
+    })
+}
+
+/// The definitions both schemas refer to.
+fn defs() -> Value {
+    let isms = isms();
+    let ism_types: Vec<Value> = isms
+        .iter()
+        .map(|ism| ism["properties"]["type"]["const"].clone())
+        .collect();
+    json!({
+        "address": {
+            "description": "A 20-byte EVM address.",
+            "type": "string",
+            "pattern": "^0x[0-9a-fA-F]{40}$",
+        },
+        "chainAddress": {
+            "description": "An EVM, bech32 (Cosmos) or base58 (Sealevel) address.",
+            "type": "string",
+            "anyOf": [
+                { "$ref": "#/$defs/address" },
+                { "pattern": "^[!-~]+1([02-9ac-hj-np-z]{6,}|[02-9AC-HJ-NP-Z]{6,})$" },
+                { "pattern": "^[1-9A-HJ-NP-Za-km-z]{32,44}$" },
+            ],
+        },
+        "uint": {
+            "description": "A non-negative integer, as a decimal string.",
+            "type": "string",
+            "pattern": "^[0-9]+$",
+        },
+        "tokenType": { "enum": TokenType::ALL },
+        "hookType": { "anyOf": [{ "enum": HOOK_TYPES }, { "type": "string" }] },
+        "ismType": { "anyOf": [{ "enum": ism_types }, { "type": "string" }] },
+        "interchainSecurityModule": { "oneOf": isms },
+        "chainConfig": chain_config(),
+        "xerc20Limits": {
+            "oneOf": [
+                {
+                    "type": "object",
+                    "required": ["type", "bufferCap", "rateLimitPerSecond"],
+                    "properties": {
+                        "type": { "const": "standard" },
+                        "bufferCap": { "$ref": "#/$defs/uint" },
+                        "rateLimitPerSecond": { "$ref": "#/$defs/uint" },
+                    },
+                },
+                {
+                    "type": "object",
+                    "required": ["type", "mint", "burn"],
+                    "properties": {
+                        "type": { "const": "velo" },
+                        "mint": { "$ref": "#/$defs/uint" },
+                        "burn": { "$ref": "#/$defs/uint" },
+                    },
+                },
+            ],
+        },
+        "xerc20Config": {
+            "type": "object",
+            "required": ["warpRouteLimits"],
+            "properties": {
+                "warpRouteLimits": { "$ref": "#/$defs/xerc20Limits" },
+                "extraBridges": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["lockbox", "limits"],
+                        "properties": {
+                            "lockbox": { "$ref": "#/$defs/chainAddress" },
+                            "limits": { "$ref": "#/$defs/xerc20Limits" },
+                        },
+                    },
+                },
+            },
+        },
+        "remoteRouter": {
+            "type": "object",
+            "required": ["address"],
+            "properties": {
+                "address": {
+                    "description": "The router as a 32-byte hex string.",
+                    "type": "string",
+                    "pattern": "^0x[0-9a-fA-F]{64}$",
+                },
+            },
+        },
+        "defaultHook": {
+            "type": "object",
+            "required": ["address", "type"],
+            "properties": {
+                "address": { "$ref": "#/$defs/address" },
+                "type": { "$ref": "#/$defs/hookType" },
+            },
+        },
+        "defaultIsm": {
+            "type": "object",
+            "required": ["address", "type"],
+            "properties": {
+                "address": { "$ref": "#/$defs/address" },
+                "relayer": nullable(json!({ "$ref": "#/$defs/address" })),
+                "type": { "$ref": "#/$defs/ismType" },
+            },
+        },
+        "requiredHook": {
+            "type": "object",
+            "required": ["address", "type"],
+            "properties": {
+                "address": { "$ref": "#/$defs/address" },
+                "beneficiary": nullable(json!({ "$ref": "#/$defs/address" })),
+                "maxProtocolFee": nullable(json!({ "$ref": "#/$defs/uint" })),
+                "owner": nullable(json!({ "$ref": "#/$defs/address" })),
+                "protocolFee": nullable(json!({ "$ref": "#/$defs/uint" })),
+                "type": { "$ref": "#/$defs/hookType" },
+            },
+        },
+    })
+}
+
+fn chain_config() -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "required": [
+            "interchainSecurityModule",
+            "isNft",
+            "mailbox",
+            "interchainGasPaymaster",
+            "owner",
+            "type",
+        ],
+        "properties": {
+            "interchainSecurityModule": { "$ref": "#/$defs/interchainSecurityModule" },
+            "isNft": {
+                "description": "Whether the route carries NFTs, as its `type` must.",
+                "type": "boolean",
+            },
+            "mailbox": { "$ref": "#/$defs/chainAddress" },
+            "interchainGasPaymaster": { "$ref": "#/$defs/chainAddress" },
+            "owner": { "$ref": "#/$defs/chainAddress" },
+            "type": { "$ref": "#/$defs/tokenType" },
+            "token": nullable(json!({ "$ref": "#/$defs/chainAddress" })),
+            "vault": nullable(json!({ "$ref": "#/$defs/chainAddress" })),
+            "lockbox": nullable(json!({ "$ref": "#/$defs/chainAddress" })),
+            "xERC20": nullable(json!({ "$ref": "#/$defs/xerc20Config" })),
+            "decimals": { "type": ["integer", "null"], "minimum": 0, "maximum": 255 },
+            "scale": { "type": ["integer", "null"], "minimum": 0 },
+            "remoteRouters": {
+                "description": "The routers of the route on the other chains, by domain id.",
+                "type": ["object", "null"],
+                "propertyNames": { "pattern": "^[0-9]+$" },
+                "additionalProperties": { "$ref": "#/$defs/remoteRouter" },
+            },
+            "destinationGas": {
+                "description": "The gas paid for handling a transfer on each domain, by domain id.",
+                "type": ["object", "null"],
+                "propertyNames": { "pattern": "^[0-9]+$" },
+                "additionalProperties": { "$ref": "#/$defs/uint" },
+            },
+        },
+    });
+    // Listed so that editors complete them, they are kept as written
+    for field in UNMODELLED_CHAIN_FIELDS {
+        schema["properties"][*field] = json!({});
+    }
+    schema
+}
+
+/// One schema per [`InterchainSecurityModule`](super::InterchainSecurityModule) variant, told
+/// apart by their `type`.
+fn isms() -> Vec<Value> {
+    let multisig = || {
+        (
+            json!({
+                "validators": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/address" },
+                },
+                "threshold": { "type": "integer", "minimum": 0, "maximum": 255 },
+            }),
+            &["threshold"][..],
+        )
+    };
+    let aggregation = || {
+        (
+            json!({
+                "modules": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/interchainSecurityModule" },
+                },
+                "threshold": { "type": "integer", "minimum": 0, "maximum": 255 },
+            }),
+            &["modules", "threshold"][..],
+        )
+    };
+    let routing = || {
+        (
+            json!({
+                "owner": { "$ref": "#/$defs/chainAddress" },
+                "domains": {
+                    "description": "The ISM verifying the messages from each origin chain, by \
+                                    chain name.",
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/$defs/interchainSecurityModule" },
+                },
+            }),
+            &["owner", "domains"][..],
+        )
+    };
+    let no_config = || (json!({}), &[] as &[&str]);
+    [
+        (
+            IsmType::TrustedRelayerIsm,
+            (
+                json!({ "relayer": { "$ref": "#/$defs/chainAddress" } }),
+                &["relayer"][..],
+            ),
+        ),
+        (IsmType::MerkleRootMultisigIsm, multisig()),
+        (IsmType::MessageIdMultisigIsm, multisig()),
+        (IsmType::StorageMerkleRootMultisigIsm, multisig()),
+        (IsmType::StorageMessageIdMultisigIsm, multisig()),
+        (IsmType::AggregationIsm, aggregation()),
+        (IsmType::StorageAggregationIsm, aggregation()),
+        (IsmType::RoutingIsm, routing()),
+        (IsmType::DomainRoutingIsm, routing()),
+        (IsmType::FallbackRoutingIsm, routing()),
+        (IsmType::DefaultFallbackRoutingIsm, routing()),
+        (IsmType::PausableIsm, no_config()),
+        (IsmType::OpStackIsm, no_config()),
+        (IsmType::TestIsm, no_config()),
+    ]
+    .into_iter()
+    .map(|(ism_type, (mut properties, required))| {
+        properties["type"] = json!({ "const": ism_type });
+        let mut schema = Map::new();
+        schema.insert("type".to_string(), json!("object"));
+        schema.insert(
+            "required".to_string(),
+            json!([&["type"][..], required].concat()),
+        );
+        schema.insert("properties".to_string(), properties);
+        Value::Object(schema)
+    })
+    .collect()
+}
+
+/// `schema`, or `null`, which the parsers read as an unset field.
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+/// A validator for the keywords the schemas above use, enough to check them against sample
+/// configs. `pattern` and `propertyNames` aren't checked.
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::Value;
+
+    /// Checks `value` against `schema`, a subschema of `root`, returning the path of the first
+    /// value that doesn't match.
+    pub(crate) fn validate(root: &Value, schema: &Value, value: &Value) -> Result<(), String> {
+        validate_at(root, schema, value, "$")
+    }
+
+    fn validate_at(root: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+        let fail = |keyword: &str| Err(format!("{path} doesn't match `{keyword}`"));
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.strip_prefix("#/$defs/").unwrap();
+            validate_at(root, &root["$defs"][name], value, path)?;
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => vec![types.as_str().unwrap()],
+            };
+            if !types.iter().any(|ty| has_type(value, ty)) {
+                return fail("type");
+            }
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            if !values.contains(value) {
+                return fail("enum");
+            }
+        }
+        if schema
+            .get("const")
+            .is_some_and(|constant| constant != value)
+        {
+            return fail("const");
+        }
+        if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64()) {
+            if number < minimum {
+                return fail("minimum");
+            }
+        }
+        if let (Some(maximum), Some(number)) = (schema["maximum"].as_f64(), value.as_f64()) {
+            if number > maximum {
+                return fail("maximum");
+            }
+        }
+        if let Some(schemas) = schema["anyOf"].as_array() {
+            if !schemas
+                .iter()
+                .any(|schema| validate_at(root, schema, value, path).is_ok())
+            {
+                return fail("anyOf");
+            }
+        }
+        if let Some(schemas) = schema["oneOf"].as_array() {
+            let matching = schemas
+                .iter()
+                .filter(|schema| validate_at(root, schema, value, path).is_ok())
+                .count();
+            if matching != 1 {
+                return fail("oneOf");
+            }
+        }
+        if let Value::Object(object) = value {
+            for field in schema["required"].as_array().into_iter().flatten() {
+                if !object.contains_key(field.as_str().unwrap()) {
+                    return fail("required");
+                }
+            }
+            for (key, value) in object {
+                let path = format!("{path}.{key}");
+                match schema["properties"].get(key) {
+                    Some(property) => validate_at(root, property, value, &path)?,
+                    None => {
+                        if let Some(additional) = schema.get("additionalProperties") {
+                            validate_at(root, additional, value, &path)?;
+                        }
+                    }
+                }
+            }
+        }
+        if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+            for (i, item) in items.iter().enumerate() {
+                validate_at(root, item_schema, item, &format!("{path}[{i}]"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn has_type(value: &Value, ty: &str) -> bool {
+        match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => panic!("unknown type `{ty}`"),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use super::{ChainName, ConfigError, WarpRouteConfig};
+use crate::preflight::{PreflightError, RpcEndpoints};
+use alloy_primitives::{keccak256, Address, Bytes, U256};
+use alloy_provider::Provider;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// A source of validator announcements, e.g. the `ValidatorAnnounce` contract of each chain.
+#[async_trait]
+pub trait ValidatorRegistry: Debug + Send + Sync {
+    /// The validators that have announced a signature storage location on `chain`.
+    async fn announced_validators(&self, chain: &str) -> Result<Vec<Address>, ConfigError>;
+}
+
+/// Reads the announced validators from the `ValidatorAnnounce` contract of each chain over
+/// JSON-RPC (`eth_call` of `getAnnouncedValidators()`).
+#[derive(Clone, Debug)]
+pub struct RpcValidatorRegistry {
+    endpoints: RpcEndpoints,
+    validator_announce: HashMap<String, Address>,
+}
+
+impl RpcValidatorRegistry {
+    /// Queries the `ValidatorAnnounce` contract at `validator_announce[chain]`, by chain name,
+    /// through the endpoint of each chain in `endpoints`.
+    pub fn new(endpoints: RpcEndpoints, validator_announce: HashMap<String, Address>) -> Self {
+        Self {
+            endpoints,
+            validator_announce,
+        }
+    }
+
+    /// Reads the `ValidatorAnnounce` contract of each chain from `VALIDATOR_ANNOUNCE_ADDRESSES`
+    /// (`chain=address,...`), with the chain names normalized as [`ChainName`]s, and queries them
+    /// through `endpoints`.
+    ///
+    /// Returns `None` when `VALIDATOR_ANNOUNCE_ADDRESSES` is unset.
+    pub fn from_env(endpoints: Option<&RpcEndpoints>) -> Result<Option<Self>, PreflightError> {
+        let Ok(addresses) = std::env::var("VALIDATOR_ANNOUNCE_ADDRESSES") else {
+            return Ok(None);
+        };
+        let endpoints = endpoints.cloned().ok_or(PreflightError::InvalidSetting {
+            name: "RPC_URLS",
+            value: "(unset), required by VALIDATOR_ANNOUNCE_ADDRESSES".to_string(),
+        })?;
+        let validator_announce = addresses
+            .split(',')
+            .map(|entry| {
+                entry
+                    .split_once('=')
+                    .and_then(|(chain, address)| {
+                        let address = address.trim().parse().ok()?;
+                        Some((ChainName::new(chain).to_string(), address))
+                    })
+                    .ok_or_else(|| PreflightError::InvalidSetting {
+                        name: "VALIDATOR_ANNOUNCE_ADDRESSES",
+                        value: entry.to_string(),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self::new(endpoints, validator_announce)))
+    }
+}
+
+#[async_trait]
+impl ValidatorRegistry for RpcValidatorRegistry {
+    async fn announced_validators(&self, chain: &str) -> Result<Vec<Address>, ConfigError> {
+        let lookup_error = |message: String| ConfigError::ValidatorLookup {
+            chain: chain.to_string(),
+            message,
+        };
+        let validator_announce = self
+            .validator_announce
+            .get(chain)
+            .ok_or_else(|| lookup_error("no ValidatorAnnounce contract configured".to_string()))?;
+        let provider = self
+            .endpoints
+            .provider(chain)
+            .map_err(|e| lookup_error(e.to_string()))?;
+        let call = serde_json::json!({
+            "to": validator_announce,
+            "data": Bytes::copy_from_slice(&keccak256("getAnnouncedValidators()")[..4]),
+        });
+        let output: Bytes = provider
+            .client()
+            .request("eth_call", (call, "latest"))
+            .await
+            .map_err(|e| lookup_error(e.to_string()))?;
+        decode_address_array(&output)
+            .ok_or_else(|| lookup_error(format!("unexpected call output {output}")))
+    }
+}
+
+/// Decodes the ABI encoding of an `address[]` returned by a call, or `None` if `output` isn't
+/// one.
+fn decode_address_array(output: &[u8]) -> Option<Vec<Address>> {
+    if output.len() % 32 != 0 {
+        return None;
+    }
+    let words: Vec<&[u8]> = output.chunks(32).collect();
+    let index = |word: &[u8]| -> Option<usize> { U256::from_be_slice(word).try_into().ok() };
+    let offset = index(words.first()?)?;
+    if offset % 32 != 0 {
+        return None;
+    }
+    let start = offset / 32;
+    let len = index(words.get(start)?)?;
+    let elements = words.get(start + 1..)?;
+    if elements.len() < len {
+        return None;
+    }
+    elements[..len]
+        .iter()
+        .map(|word| {
+            // Addresses are right-aligned in their word, padded with zeros
+            word[..12]
+                .iter()
+                .all(|&b| b == 0)
+                .then(|| Address::from_slice(&word[12..]))
+        })
+        .collect()
+}
+
+/// Checks that every one of `validators` is announced on `chain` in `registry`.
+pub async fn validate_announced_validators(
+    chain: &str,
+    validators: &[Address],
+    registry: &dyn ValidatorRegistry,
+) -> Result<(), ConfigError> {
+    let announced = registry.announced_validators(chain).await?;
+    match validators
+        .iter()
+        .find(|validator| !announced.contains(validator))
+    {
+        Some(&validator) => Err(ConfigError::ValidatorNotAnnounced {
+            chain: chain.to_string(),
+            validator,
+        }),
+        None => Ok(()),
+    }
+}
+
+impl WarpRouteConfig {
+    /// Runs [`validate_announced_validators`] for the validators of every multisig ISM in the
+    /// route.
+    pub async fn validate_announced_validators(
+        &self,
+        registry: &dyn ValidatorRegistry,
+    ) -> Result<(), ConfigError> {
+        for chain in self.chain_names() {
+            let ism = &self.chains[chain].interchain_security_module;
+            if ism.is_multisig() {
+                validate_announced_validators(chain, ism.validators(), registry).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const ANNOUNCED: Address = Address::new([0x11; 20]);
+    const UNANNOUNCED: Address = Address::new([0x22; 20]);
+
+    #[derive(Debug, Default)]
+    struct MockRegistry {
+        announcements: HashMap<String, Vec<Address>>,
+    }
+
+    #[async_trait]
+    impl ValidatorRegistry for MockRegistry {
+        async fn announced_validators(&self, chain: &str) -> Result<Vec<Address>, ConfigError> {
+            Ok(self.announcements.get(chain).cloned().unwrap_or_default())
+        }
+    }
+
+    fn registry() -> MockRegistry {
+        MockRegistry {
+            announcements: HashMap::from([("holesky".to_string(), vec![ANNOUNCED])]),
+        }
+    }
+
+    fn multisig_route(validators: &[Address]) -> WarpRouteConfig {
+        let validators: Vec<String> = validators.iter().map(|v| format!("\"{v}\"")).collect();
+        WarpRouteConfig::from_yaml(&format!(
+            r#"
+            holesky:
+              interchainSecurityModule:
+                type: "messageIdMultisigIsm"
+                validators: [{}]
+                threshold: 1
+              isNft: false
+              mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              type: "synthetic"
+            "#,
+            validators.join(", ")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode_announced_validators() {
+        let mut output = [[0u8; 32]; 4];
+        output[0][31] = 0x20;
+        output[1][31] = 2;
+        output[2][12..].copy_from_slice(ANNOUNCED.as_slice());
+        output[3][12..].copy_from_slice(UNANNOUNCED.as_slice());
+        let output = output.concat();
+        assert_eq!(
+            decode_address_array(&output),
+            Some(vec![ANNOUNCED, UNANNOUNCED])
+        );
+        let mut empty = [[0u8; 32]; 2];
+        empty[0][31] = 0x20;
+        assert_eq!(decode_address_array(&empty.concat()), Some(Vec::new()));
+
+        // Truncated, or not an address array
+        assert_eq!(decode_address_array(&output[..96]), None);
+        assert_eq!(decode_address_array(&output[..70]), None);
+        assert_eq!(decode_address_array(&[0xff; 128]), None);
+        assert_eq!(decode_address_array(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_announced_validators_pass() {
+        validate_announced_validators("holesky", &[ANNOUNCED], &registry())
+            .await
+            .unwrap();
+        multisig_route(&[ANNOUNCED])
+            .validate_announced_validators(&registry())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unannounced_validator_is_rejected() {
+        assert!(matches!(
+            validate_announced_validators("holesky", &[ANNOUNCED, UNANNOUNCED], &registry()).await,
+            Err(ConfigError::ValidatorNotAnnounced { validator, .. }) if validator == UNANNOUNCED
+        ));
+        assert!(matches!(
+            multisig_route(&[UNANNOUNCED])
+                .validate_announced_validators(&registry())
+                .await,
+            Err(ConfigError::ValidatorNotAnnounced { chain, .. }) if chain == "holesky"
+        ));
+    }
+}
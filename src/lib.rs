@@ -1,174 +1,4194 @@
+use alloy_primitives::{Address, B256, U256};
 use gadget_sdk as sdk;
 use sdk::config::StdGadgetConfiguration;
 use sdk::ctx::{ServicesContext, TangleClientContext};
 use sdk::event_listener::tangle::jobs::{services_post_processor, services_pre_processor};
-use sdk::event_listener::tangle::TangleEventListener;
+use sdk::event_listener::tangle::{TangleEvent, TangleEventListener};
 use sdk::executor::process::manager::GadgetProcessManager;
+use sdk::clients::tangle::runtime::TangleClient;
+use sdk::tangle_subxt::subxt::utils::AccountId32;
+use sdk::tangle_subxt::tangle_testnet_runtime::api::runtime_types::tangle_primitives::services::field::Field;
 use sdk::tangle_subxt::tangle_testnet_runtime::api::services::events::JobCalled;
-use std::collections::HashMap;
-use std::convert::Infallible;
-use std::sync::{Arc, LazyLock};
+use sdk::{error, info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+pub mod capture;
+use capture::{CaptureError, CapturedOutput, DEFAULT_COMPRESSION_THRESHOLD};
+
+pub mod cli;
+use cli::{HyperlaneCli, Version, MIN_CLI_VERSION};
+
+pub mod confirmation;
+use confirmation::ConfirmationPolling;
+
+pub mod counters;
 
 pub mod hyperlane;
-use crate::hyperlane::{CoreConfig, WarpRouteConfig};
+use crate::hyperlane::diff::{diff_serialized, FieldChange};
+use crate::hyperlane::output::{DeployedRoute, OutputError, PartialDeployment};
+use crate::hyperlane::validators::ValidatorRegistry;
+use crate::hyperlane::{
+    network_for_chain, ChainAddress, ChainName, ConfigError, CoreConfig, Network, Registry,
+    WarpRouteConfig,
+};
+
+pub mod preflight;
+use preflight::{BalanceCheck, CostCap, PreflightError};
+
+pub mod report;
+use report::{BatchReport, DeploymentReport, HookReport, PhaseStatus, RouteResult};
 
 pub mod runner;
-use runner::run_and_focus_multiple;
+use runner::{
+    CommandLog, CommandRunner, GasOverrides, KeyFile, ProcessExecutor, RunnerError, TempConfigFile,
+};
 
-static HYPERLANE_KEY: LazyLock<String> =
-    LazyLock::new(|| std::env::var("HYP_KEY").expect("HYP_KEY environment variable not set"));
+pub mod state;
+use state::{
+    validate_phase_order, CallKey, CallStore, DeployPhase, DeploymentLocks, DeploymentState,
+    FileStateStore, PendingCalls, PhaseOrderError, StateError, StateStore,
+};
 
 #[derive(TangleClientContext, ServicesContext)]
 pub struct HyperlaneContext {
     #[config]
     pub env: StdGadgetConfiguration,
+    /// Log the `hyperlane` commands the job would run instead of running them.
+    pub dry_run: bool,
+    /// The chains this service instance was requested for, see
+    /// [`HyperlaneContext::load_service_chains`]. Configs referencing any other chain are
+    /// rejected; `None` allows every chain.
+    pub service_chains: Option<HashSet<ChainName>>,
+    /// Log one-line summaries of the job's configs rather than dumping them in full.
+    pub quiet: bool,
+    /// If set, deployments are refused unless the deployer is funded on every chain.
+    pub balance_check: Option<BalanceCheck>,
+    /// If set, deployments projected to cost more than the cap are refused.
+    pub cost_cap: Option<CostCap>,
+    /// If set, trusted relayer ISMs may only use one of these relayers.
+    pub relayer_allowlist: Option<Vec<Address>>,
+    /// If set, the validators of multisig ISMs must be announced in this registry, see
+    /// [`RpcValidatorRegistry`](hyperlane::validators::RpcValidatorRegistry).
+    pub validator_registry: Option<Arc<dyn ValidatorRegistry>>,
+    /// Refuse routes where chains share a mailbox rather than only warning about them, see
+    /// [`WarpRouteConfig::validate_distinct_mailboxes`].
+    pub reject_shared_mailboxes: bool,
+    /// Read the deployed route back and check it against its config, see [`check_readback`].
+    pub check_readback: bool,
+    /// Refuse routes owned by another account than the core contracts on some chain, see
+    /// [`validate_owner_consistency`].
+    pub check_owner_consistency: bool,
+    /// Commands run before the phases of a deployment, e.g. to top up the deployer, as
+    /// `(name, command)` pairs. A failing command aborts the deployment.
+    pub pre_deploy_commands: Vec<(String, String)>,
+    /// Commands run once every phase of a deployment has completed, as `(name, command)` pairs.
+    pub post_deploy_commands: Vec<(String, String)>,
+    /// If set, deployments wait for the core contracts to be deployed on every chain before
+    /// reading them back.
+    pub confirmation: Option<ConfirmationPolling>,
+    /// How many times a chain's config is re-read and re-applied after its apply failed, see
+    /// [`DEFAULT_RECONCILE_RETRIES`].
+    pub reconcile_retries: u32,
+    /// The largest config payload a job accepts, in bytes, see
+    /// [`DEFAULT_MAX_PAYLOAD_SIZE`](hyperlane::DEFAULT_MAX_PAYLOAD_SIZE).
+    pub max_payload_size: usize,
+    /// The network this service deploys on. Configs referencing well-known chains of the other
+    /// network are rejected.
+    pub network: Network,
+    /// If set, the registry the `hyperlane` commands use instead of the CLI's default, see
+    /// [`Network::registry_url_var`].
+    pub registry_url: Option<String>,
+    /// The `hyperlane` CLI to run, see [`runner::DEFAULT_BINARY`].
+    pub hyperlane_bin: String,
+    /// The RPC URL of each chain, by chain name, the `hyperlane` commands use instead of the
+    /// registry's, see [`Registry::write_rpc_overrides`].
+    pub rpc_overrides: HashMap<String, String>,
+    /// The file holding the key commands sending transactions are signed with. `None` leaves it
+    /// to the CLI, which reads `HYP_KEY`.
+    pub signing_key_file: Option<KeyFile>,
+    /// Fee caps for the transactions the `hyperlane` commands send.
+    pub gas_overrides: GasOverrides,
+    /// How long a command may print nothing before it is killed as waiting on a prompt, see
+    /// [`runner::DEFAULT_PROMPT_TIMEOUT`].
+    pub prompt_timeout: Duration,
+    /// How many bytes of the stdout, and of the stderr, of a command are kept, see
+    /// [`runner::DEFAULT_CAPTURE_LIMIT`].
+    pub capture_limit: usize,
+    /// The directory holding the work directories and the registry of every service, see
+    /// [`HyperlaneContext::service_work_dir`] and [`HyperlaneContext::service_registry_dir`].
+    pub base_dir: PathBuf,
+    /// If set, deployed routes are written here as registry entries, see
+    /// [`DeployedRoute::write_registry_artifacts`].
+    pub registry_artifacts_dir: Option<PathBuf>,
+    /// If set, a JSON line per command run is appended to this file, see [`CommandLog`].
+    pub command_log: Option<PathBuf>,
+    /// Held by each job for its service while it runs, so that duplicate or retried job calls
+    /// can't deploy concurrently.
+    pub deployment_locks: DeploymentLocks,
+    /// The results of the job calls that completed, so that calls delivered again aren't run
+    /// again, see [`run_once`].
+    pub call_store: Arc<dyn CallStore>,
+    /// The calls let through by [`pre_process_call`], see [`HyperlaneContext::run_call`].
+    pub pending_calls: PendingCalls,
+    /// Cancelled when the blueprint shuts down: running jobs then kill the command they are
+    /// running and fail with [`WarpRouteJobError::Cancelled`].
+    pub cancellation: CancellationToken,
+}
+
+#[derive(Error, Debug)]
+pub enum WarpRouteJobError {
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("Runner error: {0}")]
+    Runner(RunnerError),
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Unexpected prompt: {source}")]
+    UnexpectedPrompt {
+        command: String,
+        source: RunnerError,
+    },
+    #[error("YAML serialization error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Output error: {0}")]
+    Output(#[from] OutputError),
+    #[error("Capture error: {0}")]
+    Capture(#[from] CaptureError),
+    #[error("The warp route was only partially deployed: {0}")]
+    PartialDeployment(PartialDeployment),
+    #[error("State error: {0}")]
+    State(#[from] StateError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid phase order: {0}")]
+    PhaseOrder(#[from] PhaseOrderError),
+    #[error("Invalid address `{0}`")]
+    InvalidAddress(String),
+    #[error("Invalid deployment label `{0}`")]
+    InvalidLabel(String),
+    #[error("Chain `{chain}` is not part of this service")]
+    ChainNotInService { chain: String },
+    #[error("Failed to read the service from Tangle: {0}")]
+    ServiceLookup(String),
+    #[error("Invalid service request arguments: {0}")]
+    InvalidServiceArgs(String),
+    #[error("Chain `{chain}` is not part of the warp route")]
+    ChainNotInRoute { chain: String },
+    #[error("Chain `{chain}` is already part of the warp route")]
+    ChainAlreadyInRoute { chain: String },
+    #[error("Warp route {route_id} is not the route deployed by this service")]
+    RouteNotFound { route_id: B256 },
+    #[error("Chain `{name}` is not in the Hyperlane registry")]
+    UnknownChain { name: String },
+    #[error("No chains given")]
+    NoChains,
+    #[error("Chain `{chain}` is not a {network} chain")]
+    NetworkMismatch { chain: String, network: Network },
+    #[error("Preflight error: {0}")]
+    Preflight(#[from] PreflightError),
+    #[error("Projected deployment cost of {projected} wei exceeds the cap of {cap} wei")]
+    CostExceedsCap { projected: U256, cap: U256 },
+    #[error("Deployer {address} only holds {balance} wei on `{chain}`")]
+    InsufficientBalance {
+        chain: String,
+        address: Address,
+        balance: U256,
+    },
+    #[error("Route verification failed: {}", problems.join("; "))]
+    VerificationFailed { problems: Vec<String> },
+    #[error("The deployed route differs from its config: {}", mismatches.join("; "))]
+    PostDeployMismatch { mismatches: Vec<String> },
+    #[error(
+        "The warp route and core contracts have different owners: {}",
+        mismatches.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    InconsistentOwners { mismatches: Vec<OwnerMismatch> },
+    #[error("Contract {address} on `{chain}` was not deployed in time")]
+    ConfirmationTimeout { chain: String, address: Address },
+    #[error("The hyperlane CLI `{binary}` is not installed")]
+    CliNotFound { binary: String },
+    #[error("hyperlane CLI {version} is not supported, {minimum} or newer is required")]
+    CliVersionUnsupported { version: Version, minimum: Version },
+    #[error("No version in the output of `hyperlane --version`: {output}")]
+    UnknownCliVersion { output: String },
+}
+
+impl From<RunnerError> for WarpRouteJobError {
+    fn from(e: RunnerError) -> Self {
+        match e {
+            RunnerError::Cancelled => WarpRouteJobError::Cancelled,
+            RunnerError::UnexpectedPrompt { ref name, .. } => WarpRouteJobError::UnexpectedPrompt {
+                command: name.clone(),
+                source: e,
+            },
+            e => WarpRouteJobError::Runner(e),
+        }
+    }
+}
+
+/// Settings for [`deploy_warp_route`].
+#[derive(Clone, Debug)]
+pub struct DeployOptions {
+    /// Where the [`DeploymentState`] is persisted between runs.
+    pub state_store: Arc<dyn StateStore>,
+    /// The service whose [`DeploymentState`] to use.
+    pub service_id: u64,
+    /// The phases to run, in order (see [`validate_phase_order`]).
+    pub phase_order: Vec<DeployPhase>,
+    /// See [`HyperlaneContext::service_chains`].
+    pub service_chains: Option<HashSet<ChainName>>,
+    /// If set, the core and warp configs are only read and applied on these chains of the
+    /// route, leaving the others to other operators.
+    pub chain_filter: Option<HashSet<ChainName>>,
+    /// If set, every chain of the route must be one of these, see
+    /// [`HyperlaneContext::known_chains`].
+    pub known_chains: Option<HashSet<String>>,
+    /// If set, every well-known chain of the route must belong to this network, see
+    /// [`validate_network`].
+    pub network: Option<Network>,
+    /// See [`HyperlaneContext::validator_registry`].
+    pub validator_registry: Option<Arc<dyn ValidatorRegistry>>,
+    /// See [`HyperlaneContext::quiet`].
+    pub quiet: bool,
+    /// See [`HyperlaneContext::balance_check`].
+    pub balance_check: Option<BalanceCheck>,
+    /// See [`HyperlaneContext::cost_cap`].
+    pub cost_cap: Option<CostCap>,
+    /// See [`HyperlaneContext::relayer_allowlist`].
+    pub relayer_allowlist: Option<Vec<Address>>,
+    /// See [`HyperlaneContext::reject_shared_mailboxes`].
+    pub reject_shared_mailboxes: bool,
+    /// See [`HyperlaneContext::check_readback`].
+    pub check_readback: bool,
+    /// See [`HyperlaneContext::check_owner_consistency`]. Routes are only checked against an
+    /// existing core config, whose owner is compared before any handover to
+    /// [`DeployOptions::final_owner`].
+    pub check_owner_consistency: bool,
+    /// See [`HyperlaneContext::pre_deploy_commands`].
+    pub pre_deploy_commands: Vec<(String, String)>,
+    /// See [`HyperlaneContext::post_deploy_commands`].
+    pub post_deploy_commands: Vec<(String, String)>,
+    /// See [`HyperlaneContext::confirmation`].
+    pub confirmation: Option<ConfirmationPolling>,
+    /// See [`HyperlaneContext::reconcile_retries`].
+    pub reconcile_retries: u32,
+    /// See [`HyperlaneContext::max_payload_size`].
+    pub max_payload_size: usize,
+    /// Whether to check that the deployed route is connected once every phase has run, see
+    /// [`verify_route`].
+    pub verify_route: bool,
+    /// If set, [`DeployPhase::CoreApply`] hands the core contracts over to this owner, e.g. a
+    /// multisig, instead of leaving them with the deployer.
+    pub final_owner: Option<Address>,
+    /// The registry `hyperlane` uses. `registry init` is skipped if it is already initialized;
+    /// `None` always runs it.
+    pub registry_dir: Option<PathBuf>,
+    /// See [`HyperlaneContext::registry_artifacts_dir`].
+    pub registry_artifacts_dir: Option<PathBuf>,
+}
+
+impl HyperlaneContext {
+    /// The directory of the deployment labelled `label` of this service, see
+    /// [`service_work_dir`].
+    pub fn service_work_dir(&self, label: Option<&str>) -> Result<PathBuf, WarpRouteJobError> {
+        service_work_dir(
+            &self.base_dir,
+            self.env.service_id().unwrap_or_default(),
+            label,
+        )
+    }
+
+    /// Reads the chains this service instance was requested for from its instance on Tangle,
+    /// see [`service_chains_from_args`].
+    pub async fn load_service_chains(
+        &self,
+        client: &TangleClient,
+    ) -> Result<HashSet<ChainName>, WarpRouteJobError> {
+        let service = self
+            .service_instance(client)
+            .await
+            .map_err(|e| WarpRouteJobError::ServiceLookup(e.to_string()))?;
+        service_chains_from_args(&service.args.0)
+    }
+
+    /// The local registry of this service, see [`service_registry_dir`].
+    pub fn service_registry_dir(&self) -> PathBuf {
+        service_registry_dir(&self.base_dir, self.env.service_id().unwrap_or_default())
+    }
+
+    /// A runner for the `hyperlane` commands of a job, set up as configured, that runs them in
+    /// `work_dir`.
+    pub fn runner(&self, work_dir: &Path) -> CommandRunner {
+        let executor = ProcessExecutor::new(GadgetProcessManager::new())
+            .with_prompt_timeout(self.prompt_timeout)
+            .with_capture_limit(self.capture_limit);
+        let cli = HyperlaneCli::default()
+            .with_binary(&self.hyperlane_bin)
+            .with_local_registry(self.service_registry_dir())
+            .with_gas_overrides(self.gas_overrides.clone());
+        let cli = match &self.registry_url {
+            Some(registry_url) => cli.with_registry(registry_url),
+            None => cli,
+        };
+        let cli = match &self.signing_key_file {
+            Some(key_file) => cli.with_key_file(key_file.path()),
+            None => cli,
+        };
+        let runner = CommandRunner::with_executor(Box::new(executor), self.dry_run)
+            .with_cancellation(self.cancellation.clone())
+            .with_cli(cli)
+            .with_work_dir(work_dir);
+        match self.command_log.as_deref().map(CommandLog::append_to) {
+            Some(Ok(log)) => runner.with_command_log(log),
+            Some(Err(e)) => {
+                warn!(error = %e, "Failed to open the command log, running without it");
+                runner
+            }
+            None => runner,
+        }
+    }
+
+    /// The chains the Hyperlane registry knows, see [`Registry::known_chains`].
+    pub async fn known_chains(&self) -> Result<HashSet<String>, WarpRouteJobError> {
+        let mut runner = self.runner(&self.base_dir);
+        Ok(Registry::known_chains(&mut runner).await?)
+    }
+
+    /// Checks that the registry knows every one of `chains`, see
+    /// [`HyperlaneContext::known_chains`]. A dry run's placeholder listing has no chains to check
+    /// against, so dry runs skip the check.
+    pub async fn validate_known_chains(
+        &self,
+        chains: &BTreeSet<ChainName>,
+    ) -> Result<(), WarpRouteJobError> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let known_chains = self.known_chains().await?;
+        match chains
+            .iter()
+            .find(|chain| !known_chains.contains(chain.as_ref()))
+        {
+            Some(name) => Err(WarpRouteJobError::UnknownChain {
+                name: name.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Readies the `hyperlane` CLI for a job: checks that it is supported, see [`check_cli`],
+    /// and writes the RPC overrides into the service's registry. Dry runs run no command and
+    /// leave the registry alone, so they skip both.
+    ///
+    /// Jobs call this holding the deployment lock of the service, as it writes to the registry
+    /// the service's commands read.
+    pub async fn prepare_job(&self) -> Result<(), WarpRouteJobError> {
+        if self.dry_run {
+            return Ok(());
+        }
+        self.check_cli().await?;
+        Ok(Registry::write_rpc_overrides(
+            &self.service_registry_dir(),
+            &self.rpc_overrides,
+        )?)
+    }
+
+    /// Checks that the configured `hyperlane` CLI is installed and supported, see [`check_cli`].
+    pub async fn check_cli(&self) -> Result<Version, WarpRouteJobError> {
+        let mut runner = self.runner(&self.base_dir);
+        check_cli(&mut runner).await
+    }
+
+    /// Runs `job`, the body of job `job_id`, through [`run_once`] for the call
+    /// [`pre_process_call`] queued for it, so that a call delivered twice returns its recorded
+    /// result the second time. Dry runs, which record nothing, and calls without a queued id
+    /// just run `job`.
+    pub async fn run_call<Fut>(&self, job_id: u8, job: Fut) -> Result<Vec<u8>, WarpRouteJobError>
+    where
+        Fut: Future<Output = Result<Vec<u8>, WarpRouteJobError>>,
+    {
+        match self.pending_calls.take(job_id) {
+            Some(call_id) if !self.dry_run => {
+                let key = CallKey {
+                    service_id: self.env.service_id().unwrap_or_default(),
+                    call_id,
+                };
+                run_once(self.call_store.as_ref(), key, job).await
+            }
+            _ => job.await,
+        }
+    }
+
+    /// Initializes the service's registry unless it already is, see [`ensure_registry`].
+    pub async fn ensure_registry(&self, runner: &mut CommandRunner) -> Result<(), RunnerError> {
+        ensure_registry(runner, Some(&self.service_registry_dir())).await
+    }
+}
+
+/// Runs `hyperlane registry init`, unless the registry in `registry_dir` is already
+/// initialized.
+pub async fn ensure_registry(
+    runner: &mut CommandRunner,
+    registry_dir: Option<&Path>,
+) -> Result<(), RunnerError> {
+    if let Some(registry_dir) = registry_dir.filter(|dir| Registry::is_initialized(dir)) {
+        info!(
+            "Registry at {} is already initialized, skipping registry init",
+            registry_dir.display()
+        );
+        return Ok(());
+    }
+    let registry_init = runner.cli().registry_init().to_string();
+    let commands = vec![("run registry init", registry_init.as_str())];
+    runner.run_and_focus_multiple(commands).await?;
+    Ok(())
+}
+
+/// Runs `hyperlane --version` and checks that the CLI is at least [`MIN_CLI_VERSION`], so that
+/// a missing or outdated CLI fails a job before it runs anything else. Returns the version.
+pub async fn check_cli(runner: &mut CommandRunner) -> Result<Version, WarpRouteJobError> {
+    let name = "run version";
+    let command = runner.cli().version().to_string();
+    let output = match runner
+        .run_and_focus_multiple(vec![(name, command.as_str())])
+        .await
+    {
+        Ok(mut outputs) => outputs.remove(name).unwrap_or_default(),
+        // What `sh` exits with when the command doesn't exist or can't be executed
+        Err(RunnerError::CommandFailed {
+            code: Some(126 | 127),
+            ..
+        }) => {
+            return Err(WarpRouteJobError::CliNotFound {
+                binary: runner.cli().binary().to_string(),
+            })
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let version =
+        Version::from_output(&output).ok_or(WarpRouteJobError::UnknownCliVersion { output })?;
+    if version < MIN_CLI_VERSION {
+        return Err(WarpRouteJobError::CliVersionUnsupported {
+            version,
+            minimum: MIN_CLI_VERSION,
+        });
+    }
+    info!(%version, "Using the hyperlane CLI");
+    Ok(version)
 }
 
+/// The label of deployments that weren't given one.
+pub const DEFAULT_LABEL: &str = "default";
+
+/// Returns `<base_dir>/<service_id>/.registry`, the local registry the `hyperlane` commands of
+/// the service read chain metadata from and write deployed addresses to, see
+/// [`HyperlaneCli::with_local_registry`]. Labels can't start with a `.`, so no deployment's
+/// directory is ever the registry.
+pub fn service_registry_dir(base_dir: &Path, service_id: u64) -> PathBuf {
+    base_dir.join(service_id.to_string()).join(".registry")
+}
+
+/// Returns `<base_dir>/<service_id>/<label>`, creating it if needed.
+///
+/// The configs and the [`DeploymentState`] of a deployment live in this directory, so that
+/// services, and deployments of one service under different labels, never share them. The
+/// `hyperlane` registry is shared by the deployments of a service and lives next to them, see
+/// [`service_registry_dir`]. Labels are restricted to ASCII alphanumerics, `-`, `_` and `.`, and
+/// can't start with a `.`, so that they can't point outside the service's directory.
+pub fn service_work_dir(
+    base_dir: &Path,
+    service_id: u64,
+    label: Option<&str>,
+) -> Result<PathBuf, WarpRouteJobError> {
+    let label = label.unwrap_or(DEFAULT_LABEL);
+    let is_valid = !label.is_empty()
+        && !label.starts_with('.')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !is_valid {
+        return Err(WarpRouteJobError::InvalidLabel(label.to_string()));
+    }
+
+    let dir = base_dir.join(service_id.to_string()).join(label);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+impl DeployOptions {
+    pub fn new(state_store: Arc<dyn StateStore>, service_id: u64) -> Self {
+        Self {
+            state_store,
+            service_id,
+            phase_order: DeployPhase::default_order(),
+            service_chains: None,
+            chain_filter: None,
+            known_chains: None,
+            network: None,
+            validator_registry: None,
+            quiet: true,
+            balance_check: None,
+            cost_cap: None,
+            relayer_allowlist: None,
+            reject_shared_mailboxes: false,
+            check_readback: false,
+            check_owner_consistency: false,
+            pre_deploy_commands: Vec::new(),
+            post_deploy_commands: Vec::new(),
+            confirmation: None,
+            reconcile_retries: DEFAULT_RECONCILE_RETRIES,
+            max_payload_size: hyperlane::DEFAULT_MAX_PAYLOAD_SIZE,
+            verify_route: false,
+            final_owner: None,
+            registry_dir: None,
+            registry_artifacts_dir: None,
+        }
+    }
+}
+
+/// Deploys a warp route, see [`deploy_warp_route`].
+///
+/// Returns the JSON encoded [`DeploymentReport`] of the run. Its `route` is the
+/// [`RouteSummary`](hyperlane::output::RouteSummary) of the deployed route, which includes its
+/// [`DeployedRoute::route_id`], or `null` if the warp route has not been deployed by this
+/// operator.
+///
+/// With `verify_route` set, the job fails unless every router of the deployed route is enrolled
+/// on every other chain, see [`verify_route`]. With `final_owner` set, the core contracts are
+/// handed over to that address, see [`DeployOptions::final_owner`].
 #[sdk::job(
     id = 0,
-    params(config, advanced, existing_core_config),
+    params(
+        config,
+        advanced,
+        existing_core_config,
+        deploy_phase_order,
+        label,
+        chain_filter,
+        verify_route,
+        final_owner
+    ),
     result(_),
     event_listener(
         listener = TangleEventListener<JobCalled, Arc<HyperlaneContext>>,
-        pre_processor = services_pre_processor,
+        pre_processor = pre_process_call,
         post_processor = services_post_processor,
     ),
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn operate_a_warp_route(
     ctx: Arc<HyperlaneContext>,
     config: Vec<u8>,
     advanced: bool,
     existing_core_config: Option<Vec<u8>>,
-) -> Result<u64, Infallible> {
-    // 1. Deploy or use an existing set of Hyperlane contracts
-    //     `hyperlane registry init`
-    //     `hyperlane core init --advanced [config]` for non-trusted relayer setup
-    //     `hyperlane core init` just gives you a trusted relayer setup (relayer address is deployer)
-    //     `hyperlane core deploy`
-    let mut manager = GadgetProcessManager::new();
-    match existing_core_config {
-        Some(existing_core_config) => {
-            // Deserialize the existing core config
-            let core_config = CoreConfig::try_from(&existing_core_config[..]).unwrap_or_else(|e| {
-                eprintln!("Failed to deserialize existing core config: {}", e);
-                std::process::exit(1);
-            });
+    deploy_phase_order: Option<Vec<String>>,
+    label: Option<String>,
+    chain_filter: Option<Vec<String>>,
+    verify_route: Option<bool>,
+    final_owner: Option<String>,
+) -> Result<Vec<u8>, WarpRouteJobError> {
+    let service_id = ctx.env.service_id().unwrap_or_default();
+    let span = tracing::info_span!(
+        "operate_a_warp_route",
+        service_id,
+        label = label.as_deref().unwrap_or(DEFAULT_LABEL)
+    );
+    let job = async {
+        let final_owner = final_owner
+            .map(|owner| {
+                owner
+                    .parse()
+                    .map_err(|_| WarpRouteJobError::InvalidAddress(owner))
+            })
+            .transpose()?;
+        let report = operate_route(
+            &ctx,
+            &config,
+            existing_core_config.as_deref(),
+            deploy_phase_order.as_deref(),
+            label.as_deref(),
+            chain_filter.as_deref(),
+            verify_route.unwrap_or(false),
+            final_owner,
+        )
+        .await?;
+        Ok::<_, WarpRouteJobError>(serde_json::to_vec(&report)?)
+    };
+    ctx.run_call(0, job.instrument(span)).await
+}
+
+/// Deploys several warp routes, one after the other, as [`operate_a_warp_route`] would.
+///
+/// The route at index `i` of `configs` is deployed under the label `<label>-<i>`. A route that
+/// fails doesn't stop the others; the JSON encoded [`BatchReport`] returned lists the result of
+/// each.
+#[sdk::job(
+    id = 2,
+    params(configs, label),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<JobCalled, Arc<HyperlaneContext>>,
+        pre_processor = pre_process_call,
+        post_processor = services_post_processor,
+    ),
+)]
+pub async fn operate_warp_routes(
+    ctx: Arc<HyperlaneContext>,
+    configs: Vec<Vec<u8>>,
+    label: Option<String>,
+) -> Result<Vec<u8>, WarpRouteJobError> {
+    let service_id = ctx.env.service_id().unwrap_or_default();
+    let label = label.unwrap_or_else(|| DEFAULT_LABEL.to_string());
+    let span = tracing::info_span!("operate_warp_routes", service_id, label = %label);
+    let job = async {
+        let routes = configs
+            .into_iter()
+            .enumerate()
+            .map(|(i, config)| (format!("{label}-{i}"), config))
+            .collect();
+        let report = deploy_batch(routes, |label, config| {
+            let ctx = Arc::clone(&ctx);
+            let span = tracing::info_span!("route", label = %label);
+            async move {
+                operate_route(&ctx, &config, None, None, Some(&label), None, false, None)
+                    .instrument(span)
+                    .await
+            }
+        })
+        .await;
+        Ok::<_, WarpRouteJobError>(serde_json::to_vec(&report)?)
+    };
+    ctx.run_call(2, job.instrument(span)).await
+}
 
-            // Log the deserialized core config for debugging
-            println!("Deserialized existing core config: {:?}", core_config);
+/// Deploys the route in `config` under `label` with the settings of `ctx`, holding the
+/// deployment lock of the service throughout.
+#[allow(clippy::too_many_arguments)]
+async fn operate_route(
+    ctx: &HyperlaneContext,
+    config: &[u8],
+    existing_core_config: Option<&[u8]>,
+    deploy_phase_order: Option<&[String]>,
+    label: Option<&str>,
+    chain_filter: Option<&[String]>,
+    verify_route: bool,
+    final_owner: Option<Address>,
+) -> Result<DeploymentReport, WarpRouteJobError> {
+    let service_id = ctx.env.service_id().unwrap_or_default();
+    let _lock = ctx.deployment_locks.lock(service_id).await;
+    ctx.prepare_job().await?;
+    let work_dir = ctx.service_work_dir(label)?;
+    let mut options = DeployOptions::new(Arc::new(FileStateStore::new(&work_dir)), service_id);
+    options.service_chains = ctx.service_chains.clone();
+    options.chain_filter = chain_filter.map(|chains| chains.iter().map(ChainName::new).collect());
+    // A dry run's placeholder listing has no chains to check against
+    if !ctx.dry_run {
+        options.known_chains = Some(ctx.known_chains().await?);
+    }
+    options.network = Some(ctx.network);
+    options.quiet = ctx.quiet;
+    options.balance_check = ctx.balance_check.clone();
+    options.cost_cap = ctx.cost_cap.clone();
+    options.relayer_allowlist = ctx.relayer_allowlist.clone();
+    options.validator_registry = ctx.validator_registry.clone();
+    options.reject_shared_mailboxes = ctx.reject_shared_mailboxes;
+    options.check_readback = ctx.check_readback;
+    options.check_owner_consistency = ctx.check_owner_consistency;
+    options.pre_deploy_commands = ctx.pre_deploy_commands.clone();
+    options.post_deploy_commands = ctx.post_deploy_commands.clone();
+    options.confirmation = ctx.confirmation.clone();
+    options.reconcile_retries = ctx.reconcile_retries;
+    options.max_payload_size = ctx.max_payload_size;
+    options.verify_route = verify_route;
+    options.final_owner = final_owner;
+    options.registry_dir = Some(ctx.service_registry_dir());
+    options.registry_artifacts_dir = ctx.registry_artifacts_dir.clone();
+    if let Some(phases) = deploy_phase_order {
+        options.phase_order = phases
+            .iter()
+            .map(|phase| phase.parse())
+            .collect::<Result<_, _>>()?;
+    }
 
-            // Use the existing core config in subsequent operations
-            let commands = vec![
-                ("run registry init", "hyperlane registry init"),
-                ("run core init --advanced", "hyperlane core init --advanced"),
-                ("run core deploy", "hyperlane core deploy"),
-            ];
-            let outputs = run_and_focus_multiple(&mut manager, commands)
-                .await
-                .unwrap();
+    let mut runner = ctx.runner(&work_dir);
+    let mut report = DeploymentReport::default();
+    deploy_warp_route_with_report(
+        &mut runner,
+        config,
+        existing_core_config,
+        &options,
+        &mut report,
+    )
+    .await?;
+    Ok(report)
+}
+
+/// The pre-processor of every job: [`services_pre_processor`], also queuing the id of each call
+/// it lets through in [`HyperlaneContext::pending_calls`], as the job itself only receives the
+/// call's arguments.
+pub async fn pre_process_call(
+    event: TangleEvent<JobCalled, Arc<HyperlaneContext>>,
+) -> Result<Option<TangleEvent<JobCalled, Arc<HyperlaneContext>>>, sdk::Error> {
+    let Some(event) = services_pre_processor(event).await? else {
+        return Ok(None);
+    };
+    event
+        .context
+        .pending_calls
+        .push(event.evt.job, event.evt.call_id);
+    Ok(Some(event))
+}
+
+/// Runs `job`, the job call identified by `key`, unless it already completed: a call delivered
+/// again, e.g. after the event listener reconnected, returns the result recorded in `store`
+/// without running anything.
+///
+/// Only results are recorded, so a call that failed runs again when it is delivered again.
+pub async fn run_once<Fut>(
+    store: &dyn CallStore,
+    key: CallKey,
+    job: Fut,
+) -> Result<Vec<u8>, WarpRouteJobError>
+where
+    Fut: Future<Output = Result<Vec<u8>, WarpRouteJobError>>,
+{
+    if let Some(result) = store.completed(key).await? {
+        info!(
+            service_id = key.service_id,
+            call_id = key.call_id,
+            "Call already completed, returning its result"
+        );
+        return Ok(result);
+    }
+    let result = job.await?;
+    store.record(key, &result).await?;
+    Ok(result)
+}
+
+/// Deploys each of `routes`, given as `(label, config)` pairs, in turn with `deploy`.
+///
+/// A route that fails is recorded as such and the batch moves on to the next, unless the
+/// failure is a cancellation, which ends the batch.
+pub async fn deploy_batch<F, Fut>(routes: Vec<(String, Vec<u8>)>, mut deploy: F) -> BatchReport
+where
+    F: FnMut(String, Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<DeploymentReport, WarpRouteJobError>>,
+{
+    let mut batch = BatchReport::default();
+    for (label, config) in routes {
+        let result = deploy(label.clone(), config).await;
+        let cancelled = matches!(result, Err(WarpRouteJobError::Cancelled));
+        match result {
+            Ok(report) => {
+                info!(label, "Route deployed");
+                batch.routes.push(RouteResult::succeeded(label, report));
+            }
+            Err(e) => {
+                error!(label, error = %e, "Route failed");
+                batch.routes.push(RouteResult::failed(label, e.to_string()));
+            }
         }
-        None => {
-            let commands = vec![
-                ("run registry init", "hyperlane registry init"),
-                (
-                    "run core init advanced",
-                    "hyperlane core init --advanced [config]",
-                ),
-                ("run core deploy", "hyperlane core deploy"),
-            ];
-            let outputs = run_and_focus_multiple(&mut manager, commands)
-                .await
-                .unwrap();
+        if cancelled {
+            break;
         }
     }
+    batch
+}
+
+/// Runs the phases of [`operate_a_warp_route`] through `runner`, in the order given by
+/// [`DeployOptions::phase_order`].
+///
+/// Progress is recorded in the [`DeploymentState`] in [`DeployOptions::state_store`] after
+/// each phase, and phases already recorded there are skipped. Dry runs read the state but never
+/// write it.
+///
+/// Returns the contracts deployed for the route, unless [`DeployPhase::WarpDeploy`] has never
+/// run for it.
+pub async fn deploy_warp_route(
+    runner: &mut CommandRunner,
+    config: &[u8],
+    existing_core_config: Option<&[u8]>,
+    options: &DeployOptions,
+) -> Result<Option<DeployedRoute>, WarpRouteJobError> {
+    let mut report = DeploymentReport::default();
+    deploy_warp_route_with_report(runner, config, existing_core_config, options, &mut report).await
+}
+
+/// Same as [`deploy_warp_route`], recording what the run did in `report` as it goes, so that
+/// a failed run still reports how far it got.
+pub async fn deploy_warp_route_with_report(
+    runner: &mut CommandRunner,
+    config: &[u8],
+    existing_core_config: Option<&[u8]>,
+    options: &DeployOptions,
+    report: &mut DeploymentReport,
+) -> Result<Option<DeployedRoute>, WarpRouteJobError> {
+    let first_command = runner.command_durations().len();
+    let result = run_deployment(runner, config, existing_core_config, options, report).await;
+    if !runner.is_dry_run() {
+        let token_type = WarpRouteConfig::from_payload(config, options.max_payload_size)
+            .map(|config| counters::route_token_types(&config))
+            .unwrap_or_else(|_| counters::UNKNOWN_TOKEN_TYPE.to_string());
+        counters::record_deployment(&token_type, result.is_ok());
+    }
+    for (name, duration) in &runner.command_durations()[first_command..] {
+        report.record_command(name, *duration);
+    }
+    let route = result?;
+    report.route = route.as_ref().map(DeployedRoute::summary);
+    Ok(route)
+}
+
+async fn run_deployment(
+    runner: &mut CommandRunner,
+    config: &[u8],
+    existing_core_config: Option<&[u8]>,
+    options: &DeployOptions,
+    report: &mut DeploymentReport,
+) -> Result<Option<DeployedRoute>, WarpRouteJobError> {
+    validate_phase_order(&options.phase_order)?;
 
-    // 2. `hyperlane warp init` - Initialize the Hyperlane warp route
     // Deserialize the config into the WarpRouteConfig struct
-    let warp_route_config = WarpRouteConfig::try_from(&config[..]).unwrap_or_else(|e| {
-        eprintln!("Failed to deserialize config: {}", e);
-        std::process::exit(1);
-    });
-
-    // Log the deserialized config for debugging
-    println!("Deserialized WarpRouteConfig: {:?}", warp_route_config);
-
-    // 3. `hyperlane warp deploy` - Deploy the Hyperlane warp route
-    let should_i_deploy = true; // Decide if this operator should deploy the warp route
-    if should_i_deploy {
-        let commands = vec![("run warp deploy", "hyperlane warp deploy")];
-        let outputs = run_and_focus_multiple(&mut manager, commands)
-            .await
-            .unwrap();
-    }
-
-    // 4. Update the core config of Hyperlane contracts on those chains
-    // i.e. on Holesky we do
-    //      `hyperlane core read --chain holesky`
-    //      `hyperlane core apply --chain holesky`
-    // i.e. on Tangle we do:
-    //     `hyperlane core read --chain tangle`
-    //     `hyperlane core apply --chain tangle`
-    //
-    // Note: Core apply can only be run by the person who deployed hyperlane core contracts
-    let mut outputs = HashMap::new();
-
-    // Read Holesky core config
-    let holesky_read_command = (
-        "run core read --chain holesky",
-        "hyperlane core read --chain holesky",
-    );
-    outputs.insert(
-        holesky_read_command.0.to_string(),
-        run_and_focus_multiple(&mut manager, vec![holesky_read_command])
-            .await
-            .unwrap()
-            .remove(holesky_read_command.0)
-            .unwrap(),
-    );
+    let warp_route_config = WarpRouteConfig::from_payload(config, options.max_payload_size)?;
+    warp_route_config.validate_chain_count()?;
+    warp_route_config.validate()?;
+    if let Some(allowlist) = &options.relayer_allowlist {
+        warp_route_config.validate_relayers(allowlist)?;
+    }
+    match warp_route_config.validate_distinct_mailboxes() {
+        Err(e) if !options.reject_shared_mailboxes => warn!("{e}"),
+        result => result?,
+    }
+    if let Some(registry) = &options.validator_registry {
+        warp_route_config
+            .validate_announced_validators(registry.as_ref())
+            .await?;
+    }
+    if let Some(service_chains) = &options.service_chains {
+        validate_service_chains(&warp_route_config, service_chains)?;
+    }
+    if let Some(chain_filter) = &options.chain_filter {
+        validate_chain_filter(&warp_route_config, chain_filter)?;
+    }
+    if let Some(known_chains) = &options.known_chains {
+        validate_known_chains(&warp_route_config, known_chains)?;
+    }
+    if let Some(network) = options.network {
+        validate_network(&warp_route_config, network)?;
+    }
+    let chains = filtered_chains(&warp_route_config, options);
+    report.chains = chains.iter().map(|chain| chain.to_string()).collect();
+    report.skipped_chains = warp_route_config
+        .chain_names()
+        .into_iter()
+        .filter(|chain| !chains.contains(chain))
+        .map(str::to_string)
+        .collect();
+    if let Some(balance_check) = &options.balance_check {
+        preflight::check_balances(&warp_route_config, balance_check).await?;
+    }
+    if let Some(cost_cap) = &options.cost_cap {
+        preflight::check_cost(&warp_route_config, cost_cap).await?;
+    }
 
-    // Apply Holesky core config
-    let holesky_apply_command = (
-        "run core apply --chain holesky",
-        format!(
-            "hyperlane core apply --chain holesky --input '{}'",
-            outputs["run core read --chain holesky"]
-        ),
-    );
-    run_and_focus_multiple(
-        &mut manager,
-        vec![(holesky_apply_command.0, &holesky_apply_command.1)],
-    )
-    .await
-    .unwrap();
+    let existing_core_config = existing_core_config
+        .map(|config| CoreConfig::from_payload(config, options.max_payload_size))
+        .transpose()?;
+    if options.check_owner_consistency {
+        if let Some(core_config) = &existing_core_config {
+            let unchecked = non_evm_owned_chains(&warp_route_config);
+            if !unchecked.is_empty() {
+                warn!(
+                    ?unchecked,
+                    "Not checking the owners of chains with non-EVM owners against the core owner"
+                );
+            }
+            let mismatches = validate_owner_consistency(&warp_route_config, core_config);
+            if !mismatches.is_empty() {
+                return Err(WarpRouteJobError::InconsistentOwners { mismatches });
+            }
+        }
+    }
+    for line in describe_configs(
+        &warp_route_config,
+        existing_core_config.as_ref(),
+        options.quiet,
+    ) {
+        info!("{line}");
+    }
+    report.pre_deploy_hooks = run_hooks(runner, &options.pre_deploy_commands).await?;
+
+    let store = options.state_store.as_ref();
+    let mut state = store.load(options.service_id).await?;
+    let config_hash = warp_route_config.content_hash()?;
+    for &phase in &options.phase_order {
+        // A route deployed from another config is deployed again
+        let redeploy = phase == DeployPhase::WarpDeploy
+            && state
+                .deployed_config_hash()
+                .is_some_and(|hash| hash != config_hash);
+        if redeploy {
+            info!(
+                service_id = options.service_id,
+                %config_hash,
+                "The warp route config changed since it was deployed, deploying it again"
+            );
+            // What was applied to the old route has to be applied to the new one. The core
+            // contracts don't depend on the route, so their deployment stands.
+            for later in options
+                .phase_order
+                .iter()
+                .skip_while(|&&later| later != DeployPhase::WarpDeploy)
+                .filter(|&&later| matches!(later, DeployPhase::WarpApply | DeployPhase::CoreApply))
+            {
+                state.reset_phase(*later);
+            }
+        } else if state.is_phase_complete(phase) {
+            if phase == DeployPhase::WarpDeploy && state.deployed_config_hash().is_some() {
+                info!(
+                    service_id = options.service_id,
+                    %config_hash,
+                    "An identical warp route config is already deployed, skipping warp deploy"
+                );
+            }
+            info!(
+                service_id = options.service_id,
+                ?phase,
+                "Phase already complete, skipping"
+            );
+            report.record_phase(phase, PhaseStatus::Skipped, Default::default());
+            continue;
+        }
+
+        info!(service_id = options.service_id, ?phase, "Starting phase");
+        let started = Instant::now();
+        let result = run_phase(
+            runner,
+            phase,
+            options,
+            &warp_route_config,
+            existing_core_config.as_ref(),
+            &mut state,
+        )
+        .await;
+        if let Err(e) = &result {
+            error!(service_id = options.service_id, ?phase, error = %e, "Phase failed");
+            report.record_phase(phase, PhaseStatus::Failed, started.elapsed());
+        }
+        result?;
+        complete_phase(runner, store, options.service_id, &mut state, phase).await?;
+        report.record_phase(phase, PhaseStatus::Completed, started.elapsed());
+        info!(service_id = options.service_id, ?phase, "Phase complete");
+    }
+
+    let route = state.deployed_route().cloned();
+    if options.verify_route {
+        match &route {
+            // A dry run's placeholder reads have no routers to check
+            Some(_) if runner.is_dry_run() => info!("[dry-run] Would verify the deployed route"),
+            Some(route) => verify_route(runner, route).await?,
+            None => {
+                info!("The warp route was not deployed by this operator, skipping verification")
+            }
+        }
+    }
+    if options.check_readback {
+        match &route {
+            Some(_) if runner.is_dry_run() => info!("[dry-run] Would read the deployed route back"),
+            Some(route) => check_readback(runner, route, &warp_route_config).await?,
+            None => info!("The warp route was not deployed by this operator, skipping readback"),
+        }
+    }
+    report.post_deploy_hooks = run_hooks(runner, &options.post_deploy_commands).await?;
+    Ok(route)
+}
+
+/// Runs `commands`, given as `(name, command)` pairs, in order, stopping at the first that
+/// fails. Returns the output of each, compressed if long, see [`CapturedOutput`].
+async fn run_hooks(
+    runner: &mut CommandRunner,
+    commands: &[(String, String)],
+) -> Result<Vec<HookReport>, WarpRouteJobError> {
+    if commands.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut outputs = runner
+        .run_and_focus_multiple(
+            commands
+                .iter()
+                .map(|(name, command)| (name.as_str(), command.as_str()))
+                .collect(),
+        )
+        .await?;
+    commands
+        .iter()
+        .map(|(name, _)| {
+            let output = outputs.remove(name).unwrap_or_default();
+            Ok(HookReport {
+                name: name.clone(),
+                output: CapturedOutput::new(&output, Some(DEFAULT_COMPRESSION_THRESHOLD))?,
+            })
+        })
+        .collect()
+}
+
+/// Checks that the deployed `route` is what `config` asked for: runs `hyperlane warp read` on
+/// each of its chains and compares the config read back with the chain's config in `config`,
+/// see [`ChainConfig::readback_mismatches`](hyperlane::ChainConfig::readback_mismatches).
+///
+/// Fails with [`WarpRouteJobError::PostDeployMismatch`] listing every mismatch found.
+pub async fn check_readback(
+    runner: &mut CommandRunner,
+    route: &DeployedRoute,
+    config: &WarpRouteConfig,
+) -> Result<(), WarpRouteJobError> {
+    let mut mismatches = Vec::new();
+    for chain in route.chains.keys() {
+        // Chains added by `extend_route` were deployed from another config
+        let Some(submitted) = config.chain_config(chain) else {
+            continue;
+        };
+        let read_name = format!("run warp read --chain {chain}");
+        let read_command = runner.cli().warp_read(chain).to_string();
+        let read_output = runner
+            .run_and_focus_multiple(vec![(&read_name, &read_command)])
+            .await?
+            .remove(&read_name)
+            .unwrap_or_default();
+        match WarpRouteConfig::from_yaml(&read_output)?.chain_config(chain) {
+            Some(deployed) => mismatches.extend(
+                submitted
+                    .readback_mismatches(deployed)
+                    .into_iter()
+                    .map(|change| format!("`{chain}`: {change}")),
+            ),
+            None => mismatches.push(format!("`{chain}`: missing from the config read back")),
+        }
+    }
 
-    // Read Tangle core config
-    let tangle_read_command = (
-        "run core read --chain tangletestnet",
-        "hyperlane core read --chain tangletestnet",
+    if !mismatches.is_empty() {
+        return Err(WarpRouteJobError::PostDeployMismatch { mismatches });
+    }
+    info!(
+        chains = route.chains.len(),
+        "The deployed route matches its config"
     );
-    outputs.insert(
-        tangle_read_command.0.to_string(),
-        run_and_focus_multiple(&mut manager, vec![tangle_read_command])
-            .await
-            .unwrap()
-            .remove(tangle_read_command.0)
-            .unwrap(),
+    Ok(())
+}
+
+/// Checks that `route` is connected: runs `hyperlane warp read` on each of its chains and checks
+/// that the routers of all the other chains are enrolled there, see
+/// [`WarpRouteConfig::enrollment_problems`].
+///
+/// Fails with [`WarpRouteJobError::VerificationFailed`] listing every problem found.
+pub async fn verify_route(
+    runner: &mut CommandRunner,
+    route: &DeployedRoute,
+) -> Result<(), WarpRouteJobError> {
+    let routers: BTreeMap<String, Address> = route
+        .chains
+        .iter()
+        .map(|(chain, addresses)| (chain.clone(), addresses.router))
+        .collect();
+    let mut problems = Vec::new();
+    for chain in routers.keys() {
+        let read_name = format!("run warp read --chain {chain}");
+        let read_command = runner.cli().warp_read(chain).to_string();
+        let read_output = runner
+            .run_and_focus_multiple(vec![(&read_name, &read_command)])
+            .await?
+            .remove(&read_name)
+            .unwrap_or_default();
+        let config = WarpRouteConfig::from_yaml(&read_output)?;
+        problems.extend(config.enrollment_problems(chain, &routers));
+    }
+
+    if !problems.is_empty() {
+        return Err(WarpRouteJobError::VerificationFailed { problems });
+    }
+    info!(chains = routers.len(), "Verified the warp route");
+    Ok(())
+}
+
+/// Adds the chains of `new_chains` to the warp route deployed by the service of `options`,
+/// without redeploying the rest of it. `route_id` must be the [`DeployedRoute::route_id`] of
+/// that route.
+///
+/// The new routers are deployed by `hyperlane warp deploy` from a config holding only the new
+/// chains, and recorded in the [`DeploymentState`] right away. Then every router of the extended
+/// route is enrolled with all the others through `hyperlane warp apply`, retried as
+/// [`reconcile_chain`] does. Returns the extended route, whose route id differs from `route_id`.
+pub async fn extend_route(
+    runner: &mut CommandRunner,
+    route_id: B256,
+    new_chains: &WarpRouteConfig,
+    options: &DeployOptions,
+) -> Result<DeployedRoute, WarpRouteJobError> {
+    new_chains.validate()?;
+    let mut state = options.state_store.load(options.service_id).await?;
+    let mut route = state
+        .deployed_route()
+        .filter(|route| route.route_id() == route_id)
+        .cloned()
+        .ok_or(WarpRouteJobError::RouteNotFound { route_id })?;
+    let chains = new_chains.chain_names();
+    if let Some(chain) = chains
+        .iter()
+        .find(|chain| route.chains.contains_key(**chain))
+    {
+        return Err(WarpRouteJobError::ChainAlreadyInRoute {
+            chain: chain.to_string(),
+        });
+    }
+
+    let config_file = write_temp_config(runner, "warp-route-extension", &new_chains.to_yaml()?)?;
+    let warp_deploy = runner.cli().warp_deploy(config_file.path()).to_string();
+    let output = runner
+        .run_and_focus_multiple(vec![("run warp deploy", warp_deploy.as_str())])
+        .await?
+        .remove("run warp deploy")
+        .unwrap_or_default();
+    if runner.is_dry_run() {
+        info!(
+            ?chains,
+            "[dry-run] Would enroll the new routers with the route"
+        );
+        return Ok(route);
+    }
+    let deployment = PartialDeployment::from_deploy_output(&output, &chains)?;
+    if !deployment.is_complete() {
+        return Err(WarpRouteJobError::PartialDeployment(deployment));
+    }
+    route.chains.extend(deployment.deployed.chains);
+    state.set_deployed_route(route.clone());
+    options.state_store.save(options.service_id, &state).await?;
+
+    let routers: BTreeMap<String, Address> = route
+        .chains
+        .iter()
+        .map(|(chain, addresses)| (chain.clone(), addresses.router))
+        .collect();
+    for chain in routers.keys() {
+        let enroll = |read_output: String| -> Result<String, WarpRouteJobError> {
+            let mut config = WarpRouteConfig::from_yaml(&read_output)?;
+            config.enroll_routers(chain, &routers)?;
+            Ok(config.to_yaml()?)
+        };
+        reconcile_chain(runner, "warp", chain, enroll, options.reconcile_retries).await?;
+    }
+    info!(?chains, route_id = %route.route_id(), "Extended the warp route");
+    Ok(route)
+}
+
+/// Runs the commands of a single `phase` of [`deploy_warp_route`].
+async fn run_phase(
+    runner: &mut CommandRunner,
+    phase: DeployPhase,
+    options: &DeployOptions,
+    warp_route_config: &WarpRouteConfig,
+    existing_core_config: Option<&CoreConfig>,
+    state: &mut DeploymentState,
+) -> Result<(), WarpRouteJobError> {
+    match phase {
+        DeployPhase::RegistryInit => {
+            ensure_registry(runner, options.registry_dir.as_deref()).await?;
+        }
+        // Deploy or use an existing set of Hyperlane contracts
+        //     `hyperlane core init --advanced --config <file>` for non-trusted relayer setup
+        //     `hyperlane core init` just gives you a trusted relayer setup (relayer address is deployer)
+        //     `hyperlane core deploy --config <file>`
+        DeployPhase::CoreDeploy => {
+            // Kept until the commands reading it have run
+            let mut existing_config_file = None;
+            let (core_init, core_deploy) = match existing_core_config {
+                // Use the existing core config in subsequent operations
+                Some(core_config) => {
+                    let config = existing_config_file
+                        .insert(write_temp_config(
+                            runner,
+                            "existing-core-config",
+                            &core_config.to_yaml()?,
+                        )?)
+                        .path();
+                    let core_init = runner.cli().core_init(config).to_string();
+                    // Contracts that are already deployed only need to be registered
+                    let core_deploy = (!core_config.is_deployed())
+                        .then(|| runner.cli().core_deploy(config).to_string());
+                    (core_init, core_deploy)
+                }
+                // `core init` writes the config that `core deploy` then deploys
+                None => {
+                    let config = Path::new(CORE_CONFIG_PATH);
+                    (
+                        runner.cli().core_init(config).to_string(),
+                        Some(runner.cli().core_deploy(config).to_string()),
+                    )
+                }
+            };
+            let mut commands = vec![("run core init advanced", core_init.as_str())];
+            match &core_deploy {
+                Some(core_deploy) => commands.push(("run core deploy", core_deploy.as_str())),
+                None => {
+                    info!("Existing core contracts are already deployed, skipping core deploy")
+                }
+            }
+            runner.run_and_focus_multiple(commands).await?;
+            if let (Some(polling), Some(_)) = (&options.confirmation, &core_deploy) {
+                confirm_core_deployment(runner, polling, warp_route_config).await?;
+            }
+        }
+        // Deploy the Hyperlane warp route from the job's config
+        //     `hyperlane warp deploy --config <file>` with the config written to <file>
+        DeployPhase::WarpDeploy => {
+            let config_file = write_temp_config(
+                runner,
+                "warp-route-deployment",
+                &warp_route_config.to_yaml()?,
+            )?;
+            let warp_deploy = runner.cli().warp_deploy(config_file.path()).to_string();
+            let commands = vec![("run warp deploy", warp_deploy.as_str())];
+            let (output, failure) = match runner.run_and_focus_multiple(commands).await {
+                Ok(mut outputs) => (outputs.remove("run warp deploy").unwrap_or_default(), None),
+                // Some chains may have been deployed before the command failed
+                Err(RunnerError::CommandFailed {
+                    name,
+                    code,
+                    stdout,
+                    stderr,
+                }) if name == "run warp deploy" => (
+                    format!("{stdout}\n{stderr}"),
+                    Some(RunnerError::CommandFailed {
+                        name,
+                        code,
+                        stdout,
+                        stderr,
+                    }),
+                ),
+                Err(e) => return Err(e.into()),
+            };
+            let chains = warp_route_config.chain_names();
+            match (
+                PartialDeployment::from_deploy_output(&output, &chains),
+                failure,
+            ) {
+                (Ok(deployment), None) if deployment.is_complete() => {
+                    let route = deployment.deployed;
+                    match &options.registry_artifacts_dir {
+                        Some(out_dir) if runner.is_dry_run() => info!(
+                            "[dry-run] Would write registry artifacts to {}",
+                            out_dir.display()
+                        ),
+                        // The route is deployed either way, so this doesn't fail the phase
+                        Some(out_dir) => match route.write_registry_artifacts(out_dir) {
+                            Ok(()) => {
+                                info!("Wrote registry artifacts to {}", out_dir.display())
+                            }
+                            Err(e) => error!("Failed to write registry artifacts: {e}"),
+                        },
+                        None => {}
+                    }
+                    state.set_deployed_route(route)
+                }
+                // Reported by chain, so that a retry can target the chains that failed
+                (Ok(deployment), _)
+                    if !deployment.is_complete() && !deployment.deployed.chains.is_empty() =>
+                {
+                    return Err(WarpRouteJobError::PartialDeployment(deployment))
+                }
+                (_, Some(failure)) => return Err(failure.into()),
+                // A dry run's placeholder output has no deployment summary to parse
+                (_, None) if runner.is_dry_run() => {
+                    info!("[dry-run] No deployed addresses to record")
+                }
+                (Ok(_), None) => return Err(OutputError::MissingSummary.into()),
+                (Err(e), None) => return Err(e.into()),
+            }
+            state.set_deployed_config_hash(warp_route_config.content_hash()?);
+        }
+        // Update the core config of Hyperlane contracts on those chains
+        // i.e. on Holesky we do
+        //      `hyperlane core read --chain holesky`
+        //      `hyperlane core apply --chain holesky`
+        // i.e. on Tangle we do:
+        //     `hyperlane core read --chain tangletestnet`
+        //     `hyperlane core apply --chain tangletestnet`
+        //
+        // Note: Core apply can only be run by the person who deployed hyperlane core contracts
+        DeployPhase::CoreApply => {
+            let dry_run = runner.is_dry_run();
+            for chain in filtered_chains(warp_route_config, options) {
+                let modify = |read_output: String| -> Result<String, WarpRouteJobError> {
+                    let current = match CoreConfig::from_read_output(&read_output) {
+                        Ok(core_config) => core_config,
+                        // A dry run's placeholder output has no config to parse
+                        Err(e) if dry_run => {
+                            info!("[dry-run] Applying the read output as is: {e}");
+                            return Ok(read_output);
+                        }
+                        Err(e) => return Err(e.into()),
+                    };
+                    // The existing config's hook and ISM addresses are those of a single chain
+                    let mut core_config = match existing_core_config {
+                        Some(desired) => desired.for_chain(&current),
+                        None => current,
+                    };
+                    if let Some(final_owner) = options.final_owner {
+                        core_config.update_owner(final_owner)?;
+                    }
+                    core_config.validate()?;
+                    Ok(core_config.to_yaml()?)
+                };
+                reconcile_chain(runner, "core", chain, modify, options.reconcile_retries).await?;
+            }
+        }
+        // Bring the deployed routers back to the job's config, e.g. after their owner or ISM was
+        // changed on chain
+        //      `hyperlane warp read --chain holesky`
+        //      `hyperlane warp apply --chain holesky`
+        DeployPhase::WarpApply => {
+            let dry_run = runner.is_dry_run();
+            for chain in filtered_chains(warp_route_config, options) {
+                let modify = |read_output: String| -> Result<String, WarpRouteJobError> {
+                    let mut config = match WarpRouteConfig::from_yaml(&read_output) {
+                        Ok(config) => config,
+                        // A dry run's placeholder output has no config to parse
+                        Err(e) if dry_run => {
+                            info!("[dry-run] Applying the read output as is: {e}");
+                            return Ok(read_output);
+                        }
+                        Err(e) => return Err(e.into()),
+                    };
+                    let desired = warp_route_config.chain_config(chain).ok_or_else(|| {
+                        ConfigError::MissingChain {
+                            chain: chain.to_string(),
+                        }
+                    })?;
+                    config.apply_chain_config(chain, desired)?;
+                    Ok(config.to_yaml()?)
+                };
+                reconcile_chain(runner, "warp", chain, modify, options.reconcile_retries).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Waits until the mailbox the registry lists for each chain of the route is deployed, see
+/// [`confirmation::wait_for_code`]. Chains without a registered mailbox aren't waited for.
+async fn confirm_core_deployment(
+    runner: &mut CommandRunner,
+    polling: &ConfirmationPolling,
+    warp_route_config: &WarpRouteConfig,
+) -> Result<(), WarpRouteJobError> {
+    // A dry run deploys nothing to wait for
+    if runner.is_dry_run() {
+        info!("[dry-run] Would wait for the core contracts to be deployed");
+        return Ok(());
+    }
+    let addresses = Registry::all_addresses(runner).await?;
+    for chain in warp_route_config.chain_names() {
+        match addresses
+            .get(chain)
+            .and_then(|contracts| contracts.get("mailbox"))
+        {
+            Some(&mailbox) => confirmation::wait_for_code(polling, chain, mailbox).await?,
+            None => info!("No mailbox registered on `{chain}`, not waiting for it"),
+        }
+    }
+    Ok(())
+}
+
+/// Where `hyperlane core init` writes the core config for `hyperlane core deploy`, relative to
+/// the runner's working directory.
+pub const CORE_CONFIG_PATH: &str = "configs/core-config.yaml";
+
+/// Writes `contents` to a [`TempConfigFile`] named after `stem`, in the `configs` directory of
+/// the runner's working directory or, without one, the system's temp directory. Configs are
+/// written in dry runs too, so the commands logged point at real files.
+fn write_temp_config(
+    runner: &CommandRunner,
+    stem: &str,
+    contents: &str,
+) -> Result<TempConfigFile, WarpRouteJobError> {
+    let dir = runner
+        .work_dir()
+        .map_or_else(std::env::temp_dir, |work_dir| work_dir.join("configs"));
+    Ok(TempConfigFile::new(
+        &dir,
+        stem,
+        "yaml",
+        contents.as_bytes(),
+    )?)
+}
+
+/// The log lines describing the configs of a deployment. Unless `quiet`, the configs are dumped
+/// in full for debugging.
+fn describe_configs(
+    warp_route_config: &WarpRouteConfig,
+    existing_core_config: Option<&CoreConfig>,
+    quiet: bool,
+) -> Vec<String> {
+    if quiet {
+        return vec![format!(
+            "Warp route config: chains={:?} existing_core_config={}",
+            warp_route_config.chain_names(),
+            existing_core_config.is_some()
+        )];
+    }
+
+    let mut lines = vec![format!(
+        "Deserialized WarpRouteConfig: {:?}",
+        warp_route_config
+    )];
+    if let Some(core_config) = existing_core_config {
+        lines.push(format!(
+            "Deserialized existing core config: {:?}",
+            core_config
+        ));
+    }
+    lines
+}
+
+/// The chains a service was requested for: its first request argument, a list of chain names.
+pub fn service_chains_from_args(
+    args: &[Field<AccountId32>],
+) -> Result<HashSet<ChainName>, WarpRouteJobError> {
+    let invalid = |message: &str| WarpRouteJobError::InvalidServiceArgs(message.to_string());
+    let chains = match args.first() {
+        Some(Field::List(chains) | Field::Array(chains)) => &chains.0,
+        Some(_) => return Err(invalid("the first argument is not a list of chains")),
+        None => return Err(invalid("no chains requested")),
+    };
+    chains
+        .iter()
+        .map(|chain| match chain {
+            Field::String(name) => std::str::from_utf8(&name.0 .0)
+                .map(ChainName::new)
+                .map_err(|_| invalid("a chain name is not UTF-8")),
+            _ => Err(invalid("a chain is not a string")),
+        })
+        .collect()
+}
+
+/// Checks that every chain of `config` is one of the `service_chains`.
+pub fn validate_service_chains(
+    config: &WarpRouteConfig,
+    service_chains: &HashSet<ChainName>,
+) -> Result<(), WarpRouteJobError> {
+    match config
+        .chain_names()
+        .into_iter()
+        .find(|chain| !service_chains.contains(*chain))
+    {
+        Some(chain) => Err(WarpRouteJobError::ChainNotInService {
+            chain: chain.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks that every chain of `chain_filter` is a chain of `config`.
+pub fn validate_chain_filter(
+    config: &WarpRouteConfig,
+    chain_filter: &HashSet<ChainName>,
+) -> Result<(), WarpRouteJobError> {
+    let chains = config.chain_names();
+    let mut unknown: Vec<&ChainName> = chain_filter
+        .iter()
+        .filter(|chain| !chains.contains(&chain.as_str()))
+        .collect();
+    unknown.sort_unstable();
+    match unknown.first() {
+        Some(chain) => Err(WarpRouteJobError::ChainNotInRoute {
+            chain: chain.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Collects the chains a job acts on, checking that there is at least one and, with
+/// `service_chains` set, that each is one of them.
+pub fn validate_job_chains(
+    chains: impl IntoIterator<Item = ChainName>,
+    service_chains: Option<&HashSet<ChainName>>,
+) -> Result<BTreeSet<ChainName>, WarpRouteJobError> {
+    let chains: BTreeSet<ChainName> = chains.into_iter().collect();
+    if chains.is_empty() {
+        return Err(WarpRouteJobError::NoChains);
+    }
+    match service_chains
+        .and_then(|service_chains| chains.iter().find(|chain| !service_chains.contains(*chain)))
+    {
+        Some(chain) => Err(WarpRouteJobError::ChainNotInService {
+            chain: chain.to_string(),
+        }),
+        None => Ok(chains),
+    }
+}
+
+/// Checks that the registry knows every chain of `config`.
+pub fn validate_known_chains(
+    config: &WarpRouteConfig,
+    known_chains: &HashSet<String>,
+) -> Result<(), WarpRouteJobError> {
+    match config
+        .chain_names()
+        .into_iter()
+        .find(|chain| !known_chains.contains(*chain))
+    {
+        Some(name) => Err(WarpRouteJobError::UnknownChain {
+            name: name.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks that no chain of `config` is a well-known chain of another network than `network`.
+///
+/// Chains missing from the well-known ones (see [`network_for_chain`]) are let through, the
+/// registry being the judge of those.
+pub fn validate_network(
+    config: &WarpRouteConfig,
+    network: Network,
+) -> Result<(), WarpRouteJobError> {
+    match config
+        .chain_names()
+        .into_iter()
+        .find(|chain| network_for_chain(chain).is_some_and(|other| other != network))
+    {
+        Some(chain) => Err(WarpRouteJobError::NetworkMismatch {
+            chain: chain.to_string(),
+            network,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// A chain whose warp route is owned by another account than its core contracts, see
+/// [`validate_owner_consistency`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerMismatch {
+    pub chain: String,
+    pub warp_owner: ChainAddress,
+    pub core_owner: Address,
+}
+
+impl std::fmt::Display for OwnerMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}`: the route is owned by {}, the core contracts by {}",
+            self.chain, self.warp_owner, self.core_owner
+        )
+    }
+}
+
+/// The chains of `warp` not owned by the owner of `core`, which usually means control of the
+/// route was split by mistake. An empty list means the owners are consistent.
+///
+/// The owner of `core` is an EVM account, so chains with a non-EVM owner can't be compared with
+/// it and are left out, see [`non_evm_owned_chains`].
+pub fn validate_owner_consistency(warp: &WarpRouteConfig, core: &CoreConfig) -> Vec<OwnerMismatch> {
+    warp.iter_chains()
+        .filter(|(_, config)| {
+            config
+                .owner()
+                .as_evm()
+                .is_some_and(|owner| owner != core.owner())
+        })
+        .map(|(chain, config)| OwnerMismatch {
+            chain: chain.to_string(),
+            warp_owner: config.owner().clone(),
+            core_owner: core.owner(),
+        })
+        .collect()
+}
+
+/// The chains of `warp` owned by a non-EVM account, which [`validate_owner_consistency`] can't
+/// check.
+pub fn non_evm_owned_chains(warp: &WarpRouteConfig) -> Vec<String> {
+    warp.iter_chains()
+        .filter(|(_, config)| config.owner().as_evm().is_none())
+        .map(|(chain, _)| chain.to_string())
+        .collect()
+}
+
+/// The chains of `config` that pass [`DeployOptions::chain_filter`], sorted by name.
+fn filtered_chains<'a>(config: &'a WarpRouteConfig, options: &DeployOptions) -> Vec<&'a str> {
+    let mut chains = config.chain_names();
+    if let Some(chain_filter) = &options.chain_filter {
+        chains.retain(|chain| chain_filter.contains(*chain));
+    }
+    chains
+}
+
+/// How many times [`reconcile_chain`] retries by default.
+pub const DEFAULT_RECONCILE_RETRIES: u32 = 2;
+
+/// Brings the `kind` config of `chain` to what `modify` makes of the config read from it, see
+/// [`read_modify_apply`].
+///
+/// Another actor may change the config between the read and the apply, failing the apply. Up to
+/// `retries` times, a failed apply is retried from a fresh read, so that `modify` works on the
+/// current config rather than a stale one. Any other failure is returned right away. The outcome
+/// is counted in [`counters::CHAIN_APPLIES`], retries aside.
+async fn reconcile_chain<F>(
+    runner: &mut CommandRunner,
+    kind: &str,
+    chain: &str,
+    modify: F,
+    retries: u32,
+) -> Result<(), WarpRouteJobError>
+where
+    F: Fn(String) -> Result<String, WarpRouteJobError>,
+{
+    let apply_name = format!("run {kind} apply --chain {chain}");
+    let mut attempt = 0;
+    let result = loop {
+        match read_modify_apply(runner, kind, chain, &modify).await {
+            Err(WarpRouteJobError::Runner(RunnerError::CommandFailed { name, stderr, .. }))
+                if name == apply_name && attempt < retries =>
+            {
+                attempt += 1;
+                info!(
+                    kind,
+                    chain, attempt, "Apply failed, re-reading the config: {stderr}"
+                );
+            }
+            result => break result,
+        }
+    };
+    if !runner.is_dry_run() {
+        counters::record_chain_apply(chain, result.is_ok());
+    }
+    result
+}
+
+/// Runs `hyperlane {kind} read` on `chain`, passes its output through `modify` and feeds the
+/// result to `hyperlane {kind} apply` on the same chain.
+///
+/// The apply is skipped when the result is equivalent to what was read, as it would submit a
+/// transaction that changes nothing.
+async fn read_modify_apply<F>(
+    runner: &mut CommandRunner,
+    kind: &str,
+    chain: &str,
+    modify: F,
+) -> Result<(), WarpRouteJobError>
+where
+    F: FnOnce(String) -> Result<String, WarpRouteJobError>,
+{
+    // Read the chain's config
+    let read_name = format!("run {kind} read --chain {chain}");
+    let read_command = runner.cli().read(kind, chain).to_string();
+    let read_output = runner
+        .run_and_focus_multiple(vec![(&read_name, &read_command)])
+        .await?
+        .remove(&read_name)
+        .unwrap_or_default();
+
+    // Apply the modified config
+    let input = modify(read_output.clone())?;
+    match pending_changes(kind, &read_output, &input) {
+        Some(changes) if changes.is_empty() => {
+            info!(kind, chain, "No changes, skipping apply");
+            return Ok(());
+        }
+        Some(changes) => {
+            for change in changes {
+                info!(kind, chain, %change, "Pending change");
+            }
+        }
+        None => info!(kind, chain, "Could not compare configs, applying anyway"),
+    }
+    let apply_name = format!("run {kind} apply --chain {chain}");
+    let apply_command = runner.cli().apply(kind, chain, &input).to_string();
+    runner
+        .run_and_focus_multiple(vec![(&apply_name, &apply_command)])
+        .await?;
+    Ok(())
+}
+
+#[sdk::job(
+    id = 1,
+    params(new_owner, chains, label),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<JobCalled, Arc<HyperlaneContext>>,
+        pre_processor = pre_process_call,
+        post_processor = services_post_processor,
+    ),
+)]
+pub async fn transfer_ownership(
+    ctx: Arc<HyperlaneContext>,
+    new_owner: String,
+    chains: Vec<String>,
+    label: Option<String>,
+) -> Result<Vec<u8>, WarpRouteJobError> {
+    let service_id = ctx.env.service_id().unwrap_or_default();
+    let span = tracing::info_span!(
+        "transfer_ownership",
+        service_id,
+        label = label.as_deref().unwrap_or(DEFAULT_LABEL)
     );
+    let job = async {
+        let new_owner = new_owner
+            .parse()
+            .map_err(|_| WarpRouteJobError::InvalidAddress(new_owner))?;
+        let chains = validate_job_chains(
+            chains.iter().map(ChainName::new),
+            ctx.service_chains.as_ref(),
+        )?;
+        let _lock = ctx.deployment_locks.lock(service_id).await;
+        ctx.prepare_job().await?;
+        ctx.validate_known_chains(&chains).await?;
+        let work_dir = ctx.service_work_dir(label.as_deref())?;
+        let mut runner = ctx.runner(&work_dir);
+        let results =
+            transfer_ownership_on_chains(&mut runner, new_owner, &chains, ctx.reconcile_retries)
+                .await;
+        Ok::<_, WarpRouteJobError>(serde_json::to_vec(&results)?)
+    };
+    ctx.run_call(1, job.instrument(span)).await
+}
 
-    // Apply Tangle core config
-    let tangle_apply_command = (
-        "run core apply --chain tangletestnet",
-        format!(
-            "hyperlane core apply --chain tangletestnet --input '{}'",
-            outputs["run core read --chain tangletestnet"]
-        ),
+/// Hands the core and warp route contracts on each of `chains` over to `new_owner`.
+///
+/// A failure on one chain doesn't stop the others; the returned map records which chains
+/// were transferred successfully. Applies are retried as [`reconcile_chain`] does.
+pub async fn transfer_ownership_on_chains(
+    runner: &mut CommandRunner,
+    new_owner: Address,
+    chains: &BTreeSet<ChainName>,
+    retries: u32,
+) -> BTreeMap<String, bool> {
+    let mut results = BTreeMap::new();
+    for chain in chains {
+        let result = transfer_ownership_on_chain(runner, new_owner, chain, retries).await;
+        if let Err(e) = &result {
+            error!(chain = %chain, error = %e, "Failed to transfer ownership");
+        }
+        results.insert(chain.to_string(), result.is_ok());
+    }
+    results
+}
+
+async fn transfer_ownership_on_chain(
+    runner: &mut CommandRunner,
+    new_owner: Address,
+    chain: &str,
+    retries: u32,
+) -> Result<(), WarpRouteJobError> {
+    let modify_core = |read_output: String| -> Result<String, WarpRouteJobError> {
+        let mut core_config = CoreConfig::from_read_output(&read_output)?;
+        core_config.update_owner(new_owner)?;
+        Ok(core_config.to_yaml()?)
+    };
+    reconcile_chain(runner, "core", chain, modify_core, retries).await?;
+    let modify_warp = |read_output: String| -> Result<String, WarpRouteJobError> {
+        let mut warp_route_config = WarpRouteConfig::from_yaml(&read_output)?;
+        warp_route_config.update_owner(new_owner);
+        Ok(warp_route_config.to_yaml()?)
+    };
+    reconcile_chain(runner, "warp", chain, modify_warp, retries).await
+}
+
+/// Brings the core contracts on each chain of `core_configs`, a YAML map of chain name to core
+/// config, to that chain's config through `hyperlane core read` and `hyperlane core apply`
+/// alone, without deploying anything.
+///
+/// Meant for changes to deployed contracts only, such as a new owner or hook. The JSON encoded
+/// map returned records which chains were reconciled successfully, see
+/// [`reconcile_core_config_on_chains`].
+#[sdk::job(
+    id = 3,
+    params(core_configs, label),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<JobCalled, Arc<HyperlaneContext>>,
+        pre_processor = pre_process_call,
+        post_processor = services_post_processor,
+    ),
+)]
+pub async fn reconcile_core_config(
+    ctx: Arc<HyperlaneContext>,
+    core_configs: Vec<u8>,
+    label: Option<String>,
+) -> Result<Vec<u8>, WarpRouteJobError> {
+    let service_id = ctx.env.service_id().unwrap_or_default();
+    let span = tracing::info_span!(
+        "reconcile_core_config",
+        service_id,
+        label = label.as_deref().unwrap_or(DEFAULT_LABEL)
     );
-    run_and_focus_multiple(
-        &mut manager,
-        vec![(tangle_apply_command.0, &tangle_apply_command.1)],
-    )
-    .await
-    .unwrap();
+    let job = async {
+        let core_configs = CoreConfig::per_chain_from_payload(&core_configs, ctx.max_payload_size)?;
+        for core_config in core_configs.values() {
+            core_config.validate()?;
+        }
+        let chains =
+            validate_job_chains(core_configs.keys().cloned(), ctx.service_chains.as_ref())?;
+        let _lock = ctx.deployment_locks.lock(service_id).await;
+        ctx.prepare_job().await?;
+        ctx.validate_known_chains(&chains).await?;
+        let work_dir = ctx.service_work_dir(label.as_deref())?;
+        let mut runner = ctx.runner(&work_dir);
+        let results =
+            reconcile_core_config_on_chains(&mut runner, &core_configs, ctx.reconcile_retries)
+                .await;
+        Ok::<_, WarpRouteJobError>(serde_json::to_vec(&results)?)
+    };
+    ctx.run_call(3, job.instrument(span)).await
+}
 
-    Ok(0)
+/// Applies each config of `core_configs` to the core contracts on its chain, skipping the chains
+/// already up to date.
+///
+/// A failure on one chain doesn't stop the others; the returned map records which chains
+/// were reconciled successfully. Applies are retried as [`reconcile_chain`] does.
+pub async fn reconcile_core_config_on_chains(
+    runner: &mut CommandRunner,
+    core_configs: &BTreeMap<ChainName, CoreConfig>,
+    retries: u32,
+) -> BTreeMap<String, bool> {
+    let mut results = BTreeMap::new();
+    for (chain, core_config) in core_configs {
+        let modify =
+            |_: String| -> Result<String, WarpRouteJobError> { Ok(core_config.to_yaml()?) };
+        let result = reconcile_chain(runner, "core", chain, modify, retries).await;
+        if let Err(e) = &result {
+            error!(chain = %chain, error = %e, "Failed to reconcile the core config");
+        }
+        results.insert(chain.to_string(), result.is_ok());
+    }
+    results
+}
+
+/// What `hyperlane {kind} apply` would change on a chain whose config is `current`, or `None`
+/// if either document isn't a valid `kind` config.
+fn pending_changes(kind: &str, current: &str, desired: &str) -> Option<Vec<FieldChange>> {
+    match kind {
+        "core" => {
+            let current = CoreConfig::from_read_output(current).ok()?;
+            Some(current.diff(&CoreConfig::from_yaml(desired).ok()?))
+        }
+        "warp" => {
+            let current = WarpRouteConfig::from_yaml(current).ok()?;
+            Some(diff_serialized(
+                &current,
+                &WarpRouteConfig::from_yaml(desired).ok()?,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Marks `phase` as complete, persisting the state unless `runner` is in dry-run mode.
+async fn complete_phase(
+    runner: &CommandRunner,
+    store: &dyn StateStore,
+    service_id: u64,
+    state: &mut DeploymentState,
+    phase: DeployPhase,
+) -> Result<(), StateError> {
+    state.mark_phase_complete(phase);
+    if !runner.is_dry_run() {
+        store.save(service_id, state).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::confirmation::tests::{mock_polling, MockCodeProvider};
+    use crate::runner::tests::MockExecutor;
+    use crate::runner::{dry_run_output, shell_quote};
+    use crate::state::MemoryStateStore;
+
+    const THREE_CHAIN_CONFIG: &str = r#"
+    holesky:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "collateral"
+      token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    sepolia:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    tangletestnet:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    "#;
+
+    /// `command` with the path of each [`TempConfigFile`] replaced by its stem in angle brackets,
+    /// e.g. `--config <warp-route-deployment>`, since the paths differ between runs.
+    fn mask_temp_configs(command: &str) -> String {
+        let is_id = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+        command
+            .split(' ')
+            .map(|word| {
+                let name = Path::new(word).file_name().and_then(|name| name.to_str());
+                let mut parts = name
+                    .and_then(|name| name.strip_suffix(".yaml"))
+                    .unwrap_or_default()
+                    .rsplitn(3, '-');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(id), Some(pid), Some(stem))
+                        if word.starts_with('/') && is_id(id) && is_id(pid) =>
+                    {
+                        format!("<{stem}>")
+                    }
+                    _ => word.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_spawns_no_processes() {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        let executor = MockExecutor::default();
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), true);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        assert!(executor.commands().is_empty());
+
+        let mut expected = vec![
+            "hyperlane registry init".to_string(),
+            "hyperlane core init --advanced --config configs/core-config.yaml".to_string(),
+            "hyperlane core deploy --config configs/core-config.yaml --yes".to_string(),
+            "hyperlane warp deploy --config <warp-route-deployment> --yes".to_string(),
+        ];
+        for chain in ["holesky", "sepolia", "tangletestnet"] {
+            let read_command = format!("hyperlane core read --chain {chain}");
+            let apply_command = format!(
+                "hyperlane core apply --chain {chain} --input {} --yes",
+                shell_quote(&dry_run_output(&read_command))
+            );
+            expected.push(read_command);
+            expected.push(apply_command);
+        }
+
+        let issued: Vec<String> = runner
+            .history()
+            .iter()
+            .map(|(_, command)| mask_temp_configs(command))
+            .collect();
+        assert_eq!(issued, expected);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_issues_commands_in_order() {
+        let store = Arc::new(MemoryStateStore::default());
+        let options = DeployOptions::new(store.clone(), 0);
+        let mut executor =
+            MockExecutor::default().with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+        for chain in ["holesky", "sepolia", "tangletestnet"] {
+            executor =
+                executor.with_output(format!("run core read --chain {chain}"), CORE_READ_OUTPUT);
+        }
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        // The core configs read back are already up to date, so nothing is applied
+        let commands: Vec<String> = executor
+            .commands()
+            .iter()
+            .map(|command| mask_temp_configs(command))
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                "hyperlane registry init",
+                "hyperlane core init --advanced --config configs/core-config.yaml",
+                "hyperlane core deploy --config configs/core-config.yaml --yes",
+                "hyperlane warp deploy --config <warp-route-deployment> --yes",
+                "hyperlane core read --chain holesky",
+                "hyperlane core read --chain sepolia",
+                "hyperlane core read --chain tangletestnet",
+            ]
+        );
+        let state = store.load(0).await.unwrap();
+        assert!(state.deployed_route().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_warp_config_is_written_before_warp_deploy() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::WarpDeploy];
+        let executor =
+            MockExecutor::default().with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false)
+            .with_work_dir(work_dir.path());
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        let commands = executor.commands();
+        let configs_dir = work_dir.path().join("configs");
+        assert!(commands[0].contains(&*configs_dir.to_string_lossy()));
+        assert_eq!(
+            commands
+                .iter()
+                .map(|command| mask_temp_configs(command))
+                .collect::<Vec<_>>(),
+            ["hyperlane warp deploy --config <warp-route-deployment> --yes",]
+        );
+        // The command reads the config, which is removed once it has run
+        let written = executor.config_files();
+        assert_eq!(written.len(), 1);
+        assert_eq!(
+            WarpRouteConfig::from_yaml(&written[0]).unwrap(),
+            WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap()
+        );
+        assert_eq!(std::fs::read_dir(configs_dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_identical_config_is_not_deployed_again() {
+        let store = Arc::new(MemoryStateStore::default());
+        let mut options = DeployOptions::new(store.clone(), 0);
+        options.phase_order = vec![DeployPhase::RegistryInit, DeployPhase::WarpDeploy];
+        let deploy = |config: String| {
+            let options = options.clone();
+            async move {
+                let executor = MockExecutor::default()
+                    .with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+                let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false);
+                deploy_warp_route(&mut runner, config.as_bytes(), None, &options)
+                    .await
+                    .unwrap();
+                executor.commands()
+            }
+        };
+        let deploys = |commands: &[String]| {
+            commands
+                .iter()
+                .filter(|command| command.starts_with("hyperlane warp deploy"))
+                .count()
+        };
+
+        assert_eq!(deploys(&deploy(THREE_CHAIN_CONFIG.to_string()).await), 1);
+        let deployed_hash = store.load(0).await.unwrap().deployed_config_hash();
+        assert_eq!(
+            deployed_hash,
+            Some(
+                WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG)
+                    .unwrap()
+                    .content_hash()
+                    .unwrap()
+            )
+        );
+
+        assert_eq!(deploys(&deploy(THREE_CHAIN_CONFIG.to_string()).await), 0);
+        assert_eq!(
+            store.load(0).await.unwrap().deployed_config_hash(),
+            deployed_hash
+        );
+
+        // A changed config is a different route
+        let changed = THREE_CHAIN_CONFIG.replace("synthetic", "native");
+        assert_eq!(deploys(&deploy(changed).await), 1);
+        assert_ne!(
+            store.load(0).await.unwrap().deployed_config_hash(),
+            deployed_hash
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changed_config_is_applied_again_after_its_redeploy() {
+        let store = Arc::new(MemoryStateStore::default());
+        let mut options = DeployOptions::new(store.clone(), 0);
+        options.phase_order = vec![
+            DeployPhase::RegistryInit,
+            DeployPhase::WarpDeploy,
+            DeployPhase::WarpApply,
+        ];
+        options.chain_filter = Some(HashSet::from(["holesky".into()]));
+        let warp_reads = |config: String| {
+            let options = options.clone();
+            async move {
+                let executor = MockExecutor::default()
+                    .with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT)
+                    .with_output("run warp read --chain holesky", THREE_CHAIN_CONFIG);
+                let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+                deploy_warp_route(&mut runner, config.as_bytes(), None, &options)
+                    .await
+                    .unwrap();
+                runner
+                    .history()
+                    .iter()
+                    .filter(|(name, _)| name == "run warp read --chain holesky")
+                    .count()
+            }
+        };
+
+        assert_eq!(warp_reads(THREE_CHAIN_CONFIG.to_string()).await, 1);
+        assert_eq!(warp_reads(THREE_CHAIN_CONFIG.to_string()).await, 0);
+
+        let changed = THREE_CHAIN_CONFIG.replace("synthetic", "native");
+        assert_eq!(warp_reads(changed.clone()).await, 1);
+        let state = store.load(0).await.unwrap();
+        assert!(state.is_phase_complete(DeployPhase::WarpApply));
+        assert_eq!(warp_reads(changed).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_core_deployment_is_confirmed_before_reading_it() {
+        let provider = Arc::new(MockCodeProvider::new(2));
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::RegistryInit, DeployPhase::CoreDeploy];
+        options.confirmation = Some(mock_polling(provider.clone()));
+        let executor = MockExecutor::default().with_output(
+            "run registry addresses",
+            "holesky:\n  mailbox: \"0x46f7C5D896bbeC89bE1B19e4485e59b4Be49e9Cc\"\n",
+        );
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            executor.commands().last().map(String::as_str),
+            Some("hyperlane registry addresses")
+        );
+        // Only holesky has a registered mailbox: two empty polls, then the deployed code
+        assert_eq!(provider.polls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_chain_filter_restricts_read_and_apply() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.chain_filter = Some(HashSet::from(["sepolia".into()]));
+        options.phase_order = vec![
+            DeployPhase::RegistryInit,
+            DeployPhase::CoreApply,
+            DeployPhase::WarpApply,
+        ];
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = runner
+            .history()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "run registry init",
+                "run core read --chain sepolia",
+                "run core apply --chain sepolia",
+                "run warp read --chain sepolia",
+                "run warp apply --chain sepolia",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_filter_rejects_unknown_chains() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.chain_filter = Some(HashSet::from(["sepolia".into(), "arbitrum".into()]));
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::ChainNotInRoute { chain }) if chain == "arbitrum"
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_config_runs_no_commands() {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result = deploy_warp_route(&mut runner, b"{}", None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::Config(ConfigError::InsufficientChains {
+                count: 0
+            }))
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_config_runs_no_commands() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.max_payload_size = THREE_CHAIN_CONFIG.len() - 1;
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::Config(ConfigError::PayloadTooLarge { size, limit }))
+                if size == THREE_CHAIN_CONFIG.len() && limit == size - 1
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_relayer_runs_no_commands() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.relayer_allowlist = Some(vec![Address::repeat_byte(0x11)]);
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::Config(ConfigError::UnauthorizedRelayer { chain, .. }))
+                if chain == "holesky"
+        ));
+        assert!(runner.history().is_empty());
+
+        let relayer = "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+            .parse()
+            .unwrap();
+        options.relayer_allowlist = Some(vec![relayer]);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_mailbox_is_only_fatal_on_request() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.reject_shared_mailboxes = true;
+
+        // Every chain of the three chain route has the same mailbox
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::Config(ConfigError::DuplicateMailboxAcrossChains { chains, .. }))
+                if chains == ["holesky", "sepolia", "tangletestnet"]
+        ));
+        assert!(runner.history().is_empty());
+
+        options.reject_shared_mailboxes = false;
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_validate_owner_consistency() {
+        let core = CoreConfig::from_yaml(CORE_READ_OUTPUT).unwrap();
+        let warp = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        assert!(validate_owner_consistency(&warp, &core).is_empty());
+
+        let owner = Address::repeat_byte(0x11);
+        let (head, tail) = THREE_CHAIN_CONFIG.split_once("sepolia:").unwrap();
+        let tail = tail.replacen(
+            r#"owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e""#,
+            &format!(r#"owner: "{owner}""#),
+            1,
+        );
+        let warp = WarpRouteConfig::from_yaml(&format!("{head}sepolia:{tail}")).unwrap();
+        assert_eq!(
+            validate_owner_consistency(&warp, &core),
+            [OwnerMismatch {
+                chain: "sepolia".into(),
+                warp_owner: owner.into(),
+                core_owner: core.owner(),
+            }]
+        );
+        assert!(non_evm_owned_chains(&warp).is_empty());
+
+        // A non-EVM owner can't be compared with the core owner, so it is reported separately
+        let cosmos = "neutron1sjzzd4gwkggy6hrrs8kxxatexzcuz3jecsxm3wqgregkulzj8r7qlnuef4";
+        let tail = tail.replacen(
+            &format!(r#"owner: "{owner}""#),
+            &format!(r#"owner: "{cosmos}""#),
+            1,
+        );
+        let warp = WarpRouteConfig::from_yaml(&format!("{head}sepolia:{tail}")).unwrap();
+        assert!(validate_owner_consistency(&warp, &core).is_empty());
+        assert_eq!(non_evm_owned_chains(&warp), ["sepolia"]);
+    }
+
+    #[tokio::test]
+    async fn test_owner_consistency_is_only_checked_on_request() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::RegistryInit];
+        let mut warp = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        warp.update_owner(Address::repeat_byte(0x11));
+        let config = warp.to_yaml().unwrap();
+        let core_config = Some(CORE_READ_OUTPUT.as_bytes());
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(&mut runner, config.as_bytes(), core_config, &options)
+            .await
+            .unwrap();
+
+        options.check_owner_consistency = true;
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result = deploy_warp_route(&mut runner, config.as_bytes(), core_config, &options).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::InconsistentOwners { mismatches }) if mismatches.len() == 3
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chains_unknown_to_registry_are_rejected() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.known_chains = Some(HashSet::from([
+            "holesky".to_string(),
+            "tangletestnet".to_string(),
+        ]));
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::UnknownChain { name }) if name == "sepolia"
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chains_of_another_network_are_rejected() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.network = Some(Network::Mainnet);
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::NetworkMismatch { chain, network: Network::Mainnet })
+                if chain == "holesky"
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[test]
+    fn test_validate_network() {
+        let config = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        validate_network(&config, Network::Testnet).unwrap();
+
+        let mixed = TWO_CHAIN_CONFIG.replace("holesky:", "ethereum:");
+        let config = WarpRouteConfig::from_yaml(&mixed).unwrap();
+        validate_network(&config, Network::Mainnet).unwrap_err();
+        validate_network(&config, Network::Testnet).unwrap_err();
+
+        // Chains outside the well-known ones are left to the registry
+        let config = WarpRouteConfig::from_yaml(&mixed.replace("sepolia:", "mychain:")).unwrap();
+        validate_network(&config, Network::Mainnet).unwrap();
+        assert!(matches!(
+            validate_network(&config, Network::Testnet),
+            Err(WarpRouteJobError::NetworkMismatch { chain, network: Network::Testnet })
+                if chain == "ethereum"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_stops_the_deployment() {
+        let store = Arc::new(MemoryStateStore::default());
+        let options = DeployOptions::new(store.clone(), 0);
+        let token = CancellationToken::new();
+        let executor = MockExecutor::default().with_cancel_on("run core deploy", token.clone());
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false)
+            .with_cancellation(token);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(result, Err(WarpRouteJobError::Cancelled)));
+        assert_eq!(
+            executor.commands(),
+            [
+                "hyperlane registry init",
+                "hyperlane core init --advanced --config configs/core-config.yaml",
+                "hyperlane core deploy --config configs/core-config.yaml --yes",
+            ]
+        );
+        let state = store.load(0).await.unwrap();
+        assert!(state.is_phase_complete(DeployPhase::RegistryInit));
+        assert!(!state.is_phase_complete(DeployPhase::CoreDeploy));
+    }
+
+    #[tokio::test]
+    async fn test_restart_skips_completed_phases() {
+        let store = Arc::new(MemoryStateStore::default());
+
+        // The previous run crashed right after `hyperlane core deploy`
+        let mut crashed = DeploymentState::default();
+        crashed.mark_phase_complete(DeployPhase::RegistryInit);
+        crashed.mark_phase_complete(DeployPhase::CoreDeploy);
+        store.save(0, &crashed).await.unwrap();
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            None,
+            &DeployOptions::new(store, 0),
+        )
+        .await
+        .unwrap();
+
+        let issued: Vec<String> = runner
+            .history()
+            .iter()
+            .map(|(_, command)| mask_temp_configs(command))
+            .collect();
+        assert_eq!(
+            issued[0],
+            "hyperlane warp deploy --config <warp-route-deployment> --yes"
+        );
+        assert!(!issued.iter().any(|command| {
+            command.starts_with("hyperlane registry init")
+                || command.starts_with("hyperlane core init")
+                || command.starts_with("hyperlane core deploy")
+        }));
+    }
+
+    const CORE_READ_OUTPUT: &str = r#"
+    defaultHook:
+      address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "merkleTreeHook"
+    defaultIsm:
+      address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "trustedRelayerIsm"
+    owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    requiredHook:
+      address: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      beneficiary: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      maxProtocolFee: "100000000000000000"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      protocolFee: "0"
+      type: "protocolFee"
+    "#;
+
+    /// The `--input` an issued apply command carried, unquoted.
+    fn apply_input(command: &str) -> String {
+        let start = command.find("--input '").unwrap() + "--input '".len();
+        let end = command.rfind('\'').unwrap();
+        command[start..end].replace(r"'\''", "'")
+    }
+
+    #[tokio::test]
+    async fn test_core_apply_hands_over_to_final_owner() {
+        let final_owner = Address::repeat_byte(0x22);
+        let read = "run core read --chain holesky";
+        let apply = "run core apply --chain holesky";
+        let deploy = |final_owner| async move {
+            let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            options.phase_order = vec![DeployPhase::CoreApply];
+            options.chain_filter = Some(HashSet::from(["holesky".into()]));
+            options.final_owner = final_owner;
+            let executor = MockExecutor::default().with_output(read, CORE_READ_OUTPUT);
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+                .await
+                .unwrap();
+            runner.history().to_vec()
+        };
+
+        let history = deploy(Some(final_owner)).await;
+        let (_, core_apply) = history.iter().find(|(name, _)| name == apply).unwrap();
+        let mut expected = CoreConfig::from_read_output(CORE_READ_OUTPUT).unwrap();
+        expected.update_owner(final_owner).unwrap();
+        let applied = CoreConfig::from_yaml(&apply_input(core_apply)).unwrap();
+        assert_eq!(applied.owner(), final_owner);
+        assert_eq!(applied, expected);
+
+        // Without a final owner the config read back is already up to date
+        let history = deploy(None).await;
+        assert!(history.iter().all(|(name, _)| name != apply));
+    }
+
+    #[tokio::test]
+    async fn test_warp_apply_restores_the_jobs_chain_config() {
+        let read = "run warp read --chain holesky";
+        let apply = "run warp apply --chain holesky";
+        let config = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        let holesky = config.chain_config("holesky").unwrap().clone();
+        let deploy = |read_output: String| async move {
+            let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            options.phase_order = vec![DeployPhase::WarpApply];
+            options.chain_filter = Some(HashSet::from(["holesky".into()]));
+            let executor = MockExecutor::default().with_output(read, &read_output);
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+                .await
+                .unwrap();
+            runner.history().to_vec()
+        };
+
+        // The deployed router was handed to another owner, and knows its remote routers
+        let mut deployed =
+            WarpRouteConfig::try_from(BTreeMap::from([("holesky".to_string(), holesky.clone())]))
+                .unwrap();
+        let routers = BTreeMap::from([("sepolia".to_string(), Address::repeat_byte(0x33))]);
+        deployed.enroll_routers("holesky", &routers).unwrap();
+        let mut drifted = deployed.clone();
+        drifted.update_owner(Address::repeat_byte(0x11));
+
+        let history = deploy(drifted.to_yaml().unwrap()).await;
+        let (_, warp_apply) = history.iter().find(|(name, _)| name == apply).unwrap();
+        let applied = WarpRouteConfig::from_yaml(&apply_input(warp_apply)).unwrap();
+        assert_eq!(applied, deployed);
+        assert_eq!(
+            applied.chain_config("holesky").unwrap().owner(),
+            holesky.owner()
+        );
+        assert!(applied.enrollment_problems("holesky", &routers).is_empty());
+
+        // A router matching the job's config is left alone
+        let history = deploy(deployed.to_yaml().unwrap()).await;
+        assert!(history.iter().any(|(name, _)| name == read));
+        assert!(history.iter().all(|(name, _)| name != apply));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_deployments_and_applies_are_counted() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let apply = "run core apply --chain holesky";
+        let deploy = |executor: MockExecutor| async move {
+            let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            options.phase_order = vec![DeployPhase::CoreApply];
+            options.chain_filter = Some(HashSet::from(["holesky".into()]));
+            options.final_owner = Some(Address::repeat_byte(0x22));
+            let executor = executor.with_output("run core read --chain holesky", CORE_READ_OUTPUT);
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await
+        };
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                deploy(MockExecutor::default()).await.unwrap();
+                deploy(MockExecutor::default().with_failure(apply, 1))
+                    .await
+                    .unwrap_err();
+            });
+        });
+
+        let recorded: Vec<(String, Vec<String>, u64)> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .filter_map(|(key, _, _, value)| match value {
+                DebugValue::Counter(count) => Some((
+                    key.key().name().to_string(),
+                    key.key()
+                        .labels()
+                        .map(|label| format!("{}={}", label.key(), label.value()))
+                        .collect(),
+                    count,
+                )),
+                _ => None,
+            })
+            .collect();
+        let count = |name: &str, labels: &[&str]| {
+            recorded
+                .iter()
+                .find(|(key, key_labels, _)| key == name && key_labels == labels)
+                .map_or(0, |(_, _, count)| *count)
+        };
+        for result in ["success", "failure"] {
+            assert_eq!(
+                count(
+                    counters::DEPLOYMENTS,
+                    &[
+                        &format!("result={result}"),
+                        "token_type=collateral,synthetic"
+                    ]
+                ),
+                1
+            );
+            assert_eq!(
+                count(
+                    counters::CHAIN_APPLIES,
+                    &["chain=holesky", &format!("result={result}")]
+                ),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_ownership_applies_new_owner() {
+        let new_owner = Address::repeat_byte(0x11);
+        let chains = BTreeSet::from(["holesky".into(), "tangletestnet".into()]);
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let warp_route_config = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        for chain in &chains {
+            runner = runner
+                .with_dry_run_output(format!("run core read --chain {chain}"), CORE_READ_OUTPUT)
+                .with_dry_run_output(
+                    format!("run warp read --chain {chain}"),
+                    serde_yaml::to_string(&warp_route_config).unwrap(),
+                );
+        }
+
+        let results = transfer_ownership_on_chains(
+            &mut runner,
+            new_owner,
+            &chains,
+            DEFAULT_RECONCILE_RETRIES,
+        )
+        .await;
+        assert_eq!(
+            results,
+            BTreeMap::from([
+                ("holesky".to_string(), true),
+                ("tangletestnet".to_string(), true)
+            ])
+        );
+
+        for chain in &chains {
+            let (_, core_apply) = runner
+                .history()
+                .iter()
+                .find(|(name, _)| *name == format!("run core apply --chain {chain}"))
+                .unwrap();
+            let core_config = CoreConfig::from_yaml(&apply_input(core_apply)).unwrap();
+            assert_eq!(core_config.owner(), new_owner);
+
+            let (_, warp_apply) = runner
+                .history()
+                .iter()
+                .find(|(name, _)| *name == format!("run warp apply --chain {chain}"))
+                .unwrap();
+            let mut expected = warp_route_config.clone();
+            expected.update_owner(new_owner);
+            assert_eq!(
+                WarpRouteConfig::from_yaml(&apply_input(warp_apply)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_core_config_only_reads_and_applies() {
+        let mut desired = CoreConfig::from_read_output(CORE_READ_OUTPUT).unwrap();
+        desired.update_owner(Address::repeat_byte(0x33)).unwrap();
+        let core_configs = BTreeMap::from([
+            (ChainName::new("holesky"), desired.clone()),
+            (ChainName::new("sepolia"), desired.clone()),
+        ]);
+        let executor = MockExecutor::default()
+            .with_output("run core read --chain holesky", CORE_READ_OUTPUT)
+            .with_output(
+                "run core read --chain sepolia",
+                &serde_yaml::to_string(&desired).unwrap(),
+            );
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+
+        let results =
+            reconcile_core_config_on_chains(&mut runner, &core_configs, DEFAULT_RECONCILE_RETRIES)
+                .await;
+        assert_eq!(
+            results,
+            BTreeMap::from([("holesky".to_string(), true), ("sepolia".to_string(), true)])
+        );
+
+        // Sepolia is already up to date, so only holesky is applied to
+        let names: Vec<_> = runner
+            .history()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "run core read --chain holesky",
+                "run core apply --chain holesky",
+                "run core read --chain sepolia",
+            ]
+        );
+        assert!(runner
+            .history()
+            .iter()
+            .all(|(_, command)| !command.contains(" deploy")));
+        let (_, apply) = &runner.history()[1];
+        assert_eq!(CoreConfig::from_yaml(&apply_input(apply)).unwrap(), desired);
+    }
+
+    /// [`CORE_READ_OUTPUT`] with the hook and ISM contracts at addresses made of `byte`, as
+    /// core contracts deployed on another chain would be.
+    fn core_read_output_at(byte: u8) -> String {
+        CORE_READ_OUTPUT.replace(
+            r#"address: "0x742d35cc6634c0532925a3b844bc454e4438f44e""#,
+            &format!(r#"address: "{}""#, Address::repeat_byte(byte)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_core_config_applies_each_chains_own_config() {
+        let holesky = CoreConfig::from_read_output(&core_read_output_at(0x11)).unwrap();
+        let sepolia = CoreConfig::from_read_output(&core_read_output_at(0x22)).unwrap();
+        assert_ne!(holesky, sepolia);
+        let payload = serde_yaml::to_string(&BTreeMap::from([
+            ("holesky", &holesky),
+            ("Sepolia", &sepolia),
+        ]))
+        .unwrap();
+        let mut core_configs = CoreConfig::per_chain_from_payload(
+            payload.as_bytes(),
+            hyperlane::DEFAULT_MAX_PAYLOAD_SIZE,
+        )
+        .unwrap();
+        assert_eq!(core_configs[&ChainName::new("sepolia")], sepolia);
+        for core_config in core_configs.values_mut() {
+            core_config
+                .update_owner(Address::repeat_byte(0x33))
+                .unwrap();
+        }
+
+        let executor = MockExecutor::default()
+            .with_output("run core read --chain holesky", &holesky.to_yaml().unwrap())
+            .with_output("run core read --chain sepolia", &sepolia.to_yaml().unwrap());
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        reconcile_core_config_on_chains(&mut runner, &core_configs, DEFAULT_RECONCILE_RETRIES)
+            .await;
+
+        for (chain, core_config) in &core_configs {
+            let (_, apply) = runner
+                .history()
+                .iter()
+                .find(|(name, _)| *name == format!("run core apply --chain {chain}"))
+                .unwrap();
+            assert_eq!(
+                &CoreConfig::from_yaml(&apply_input(apply)).unwrap(),
+                core_config
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_core_apply_keeps_each_chains_hook_addresses() {
+        let mut existing = CoreConfig::from_read_output(CORE_READ_OUTPUT).unwrap();
+        existing.update_owner(Address::repeat_byte(0x33)).unwrap();
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::CoreApply];
+        options.chain_filter = Some(HashSet::from(["holesky".into(), "sepolia".into()]));
+        let executor = MockExecutor::default()
+            .with_output("run core read --chain holesky", &core_read_output_at(0x11))
+            .with_output("run core read --chain sepolia", &core_read_output_at(0x22));
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        deploy_warp_route(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            Some(existing.to_yaml().unwrap().as_bytes()),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        for (chain, byte) in [("holesky", 0x11), ("sepolia", 0x22)] {
+            let (_, apply) = runner
+                .history()
+                .iter()
+                .find(|(name, _)| *name == format!("run core apply --chain {chain}"))
+                .unwrap();
+            let mut expected = CoreConfig::from_read_output(&core_read_output_at(byte)).unwrap();
+            expected.update_owner(Address::repeat_byte(0x33)).unwrap();
+            assert_eq!(
+                CoreConfig::from_yaml(&apply_input(apply)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_ownership_reports_failed_chains() {
+        // Without a canned read output the placeholder can't be parsed as a core config
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+            .with_dry_run_output("run core read --chain holesky", CORE_READ_OUTPUT)
+            .with_dry_run_output("run warp read --chain holesky", THREE_CHAIN_CONFIG);
+        let chains = BTreeSet::from(["holesky".into(), "sepolia".into()]);
+
+        let results = transfer_ownership_on_chains(
+            &mut runner,
+            Address::repeat_byte(0x11),
+            &chains,
+            DEFAULT_RECONCILE_RETRIES,
+        )
+        .await;
+        assert!(results["holesky"]);
+        assert!(!results["sepolia"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_apply_is_retried_after_reading_again() {
+        let read = "run warp read --chain holesky";
+        let apply = "run warp apply --chain holesky";
+        let new_owner = Address::repeat_byte(0x11);
+        let modify = |read_output: String| -> Result<String, WarpRouteJobError> {
+            let mut warp_route_config = WarpRouteConfig::from_yaml(&read_output)?;
+            warp_route_config.update_owner(new_owner);
+            Ok(warp_route_config.to_yaml()?)
+        };
+
+        let executor = MockExecutor::default()
+            .with_output(read, THREE_CHAIN_CONFIG)
+            .with_failure_once(apply, 1);
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false);
+        reconcile_chain(&mut runner, "warp", "holesky", modify, 1)
+            .await
+            .unwrap();
+        let names: Vec<&str> = runner
+            .history()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, [read, apply, read, apply]);
+
+        // Without retries the first failure is final
+        let executor = MockExecutor::default()
+            .with_output(read, THREE_CHAIN_CONFIG)
+            .with_failure_once(apply, 1);
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        assert!(matches!(
+            reconcile_chain(&mut runner, "warp", "holesky", modify, 0).await,
+            Err(WarpRouteJobError::Runner(RunnerError::CommandFailed { name, .. })) if name == apply
+        ));
+        assert_eq!(runner.history().len(), 2);
+    }
+
+    /// The output of `hyperlane warp read` on `chain` of the three chain route, with the
+    /// routers of `enrolled` enrolled.
+    fn warp_read_output(chain: &str, route: &DeployedRoute, enrolled: &[&str]) -> String {
+        let remote_routers: String = enrolled
+            .iter()
+            .map(|remote| {
+                format!(
+                    "    \"{}\":\n      address: \"{}\"\n",
+                    hyperlane::domain_id_for_chain(remote).unwrap(),
+                    alloy_primitives::hex::encode_prefixed(
+                        route.chains[*remote].router.into_word()
+                    )
+                )
+            })
+            .collect();
+        format!(
+            r#"
+{chain}:
+  interchainSecurityModule:
+    relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    type: "trustedRelayerIsm"
+  isNft: false
+  mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+  type: "synthetic"
+  remoteRouters:
+{remote_routers}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_command_sequences_are_reproducible() {
+        let route = DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap();
+        let chains = ["holesky", "sepolia", "tangletestnet"];
+        let run = || async {
+            let mut executor = MockExecutor::default();
+            for chain in chains {
+                let enrolled: Vec<&str> = chains.into_iter().filter(|&c| c != chain).collect();
+                executor = executor
+                    .with_output(format!("run core read --chain {chain}"), CORE_READ_OUTPUT)
+                    .with_output(
+                        format!("run warp read --chain {chain}"),
+                        &warp_read_output(chain, &route, &enrolled),
+                    );
+            }
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            let config = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+            let chains = config
+                .chain_names()
+                .into_iter()
+                .map(ChainName::new)
+                .collect();
+            transfer_ownership_on_chains(
+                &mut runner,
+                Address::repeat_byte(0x11),
+                &chains,
+                DEFAULT_RECONCILE_RETRIES,
+            )
+            .await;
+            runner.history().to_vec()
+        };
+
+        // Each map built along the way is seeded differently, so any iteration over a hashed
+        // map would show up as a difference between the runs
+        let first = run().await;
+        assert_eq!(first.len(), 12);
+        assert_eq!(first, run().await);
+        assert_eq!(first[0].0, "run core read --chain holesky");
+        assert_eq!(first[11].0, "run warp apply --chain tangletestnet");
+    }
+
+    #[tokio::test]
+    async fn test_verify_route_checks_every_enrollment() {
+        let route = DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap();
+        let chains = ["holesky", "sepolia", "tangletestnet"];
+        let runner_with = |misconfigured: Option<(&str, &str)>| {
+            let mut executor = MockExecutor::default();
+            for chain in chains {
+                let enrolled: Vec<&str> = chains
+                    .into_iter()
+                    .filter(|&remote| remote != chain && misconfigured != Some((chain, remote)))
+                    .collect();
+                executor = executor.with_output(
+                    format!("run warp read --chain {chain}"),
+                    &warp_read_output(chain, &route, &enrolled),
+                );
+            }
+            CommandRunner::with_executor(Box::new(executor), false)
+        };
+
+        verify_route(&mut runner_with(None), &route).await.unwrap();
+
+        let result =
+            verify_route(&mut runner_with(Some(("tangletestnet", "holesky"))), &route).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::VerificationFailed { problems })
+                if problems == ["`tangletestnet` has no router enrolled for `holesky`"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_cli() {
+        let executor = MockExecutor::default().with_output("run version", "5.3.0\n");
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        assert_eq!(check_cli(&mut runner).await.unwrap(), Version::new(5, 3, 0));
+        assert_eq!(runner.history()[0].1, "hyperlane --version");
+
+        let executor = MockExecutor::default().with_output("run version", "4.1.0\n");
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        assert!(matches!(
+            check_cli(&mut runner).await,
+            Err(WarpRouteJobError::CliVersionUnsupported { version, minimum })
+                if version == Version::new(4, 1, 0) && minimum == MIN_CLI_VERSION
+        ));
+
+        let executor = MockExecutor::default().with_failed_output(
+            "run version",
+            127,
+            "",
+            "sh: 1: /opt/hyperlane: not found",
+        );
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false)
+            .with_cli(HyperlaneCli::default().with_binary("/opt/hyperlane"));
+        assert!(matches!(
+            check_cli(&mut runner).await,
+            Err(WarpRouteJobError::CliNotFound { binary }) if binary == "/opt/hyperlane"
+        ));
+
+        let executor = MockExecutor::default().with_output("run version", "unknown\n");
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        assert!(matches!(
+            check_cli(&mut runner).await,
+            Err(WarpRouteJobError::UnknownCliVersion { output }) if output == "unknown\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_extend_route_enrolls_the_new_chain_both_ways() {
+        let store = Arc::new(MemoryStateStore::default());
+        let options = DeployOptions::new(store.clone(), 0);
+        let route = DeployedRoute::from_deploy_output(TWO_CHAIN_DEPLOY_OUTPUT).unwrap();
+        let mut state = DeploymentState::default();
+        state.set_deployed_route(route.clone());
+        store.save(0, &state).await.unwrap();
+
+        let new_chain = WarpRouteConfig::from_yaml(
+            r#"
+    tangletestnet:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    "#,
+        )
+        .unwrap();
+        let deploy_output = r#"
+    tokens:
+      - chainName: tangletestnet
+        standard: EvmHypSynthetic
+        addressOrDenom: "0x9fe46736679d2d9a65f0992f2272de9f3c7fa6e0"
+"#;
+        let mut extended = route.clone();
+        extended.chains.extend(
+            DeployedRoute::from_deploy_output(deploy_output)
+                .unwrap()
+                .chains,
+        );
+        let executor = MockExecutor::default()
+            .with_output("run warp deploy", deploy_output)
+            .with_output(
+                "run warp read --chain holesky",
+                &warp_read_output("holesky", &extended, &["sepolia"]),
+            )
+            .with_output(
+                "run warp read --chain sepolia",
+                &warp_read_output("sepolia", &extended, &["holesky"]),
+            )
+            .with_output(
+                "run warp read --chain tangletestnet",
+                &warp_read_output("tangletestnet", &extended, &[]),
+            );
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+
+        let result = extend_route(&mut runner, route.route_id(), &new_chain, &options)
+            .await
+            .unwrap();
+        assert_eq!(result, extended);
+        let names: Vec<&str> = runner
+            .history()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "run warp deploy",
+                "run warp read --chain holesky",
+                "run warp apply --chain holesky",
+                "run warp read --chain sepolia",
+                "run warp apply --chain sepolia",
+                "run warp read --chain tangletestnet",
+                "run warp apply --chain tangletestnet",
+            ]
+        );
+        let routers: BTreeMap<String, Address> = extended
+            .chains
+            .iter()
+            .map(|(chain, addresses)| (chain.clone(), addresses.router))
+            .collect();
+        for (name, command) in runner.history() {
+            let Some(chain) = name.strip_prefix("run warp apply --chain ") else {
+                continue;
+            };
+            let applied = WarpRouteConfig::from_yaml(&apply_input(command)).unwrap();
+            assert!(applied.enrollment_problems(chain, &routers).is_empty());
+        }
+        let state = store.load(0).await.unwrap();
+        assert_eq!(state.deployed_route(), Some(&extended));
+
+        // The route is now identified by its new id, and already has the chain
+        let result = extend_route(&mut runner, route.route_id(), &new_chain, &options).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::RouteNotFound { route_id }) if route_id == route.route_id()
+        ));
+        let result = extend_route(&mut runner, extended.route_id(), &new_chain, &options).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::ChainAlreadyInRoute { chain }) if chain == "tangletestnet"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chains_outside_service_are_rejected() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.service_chains = Some(HashSet::from(["holesky".into(), "sepolia".into()]));
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::ChainNotInService { chain }) if chain == "tangletestnet"
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_service_chains_come_from_the_request_args() {
+        use sdk::tangle_subxt::tangle_testnet_runtime::api::runtime_types::bounded_collections::bounded_vec::BoundedVec;
+        use sdk::tangle_subxt::tangle_testnet_runtime::api::runtime_types::tangle_primitives::services::field::BoundedString;
+
+        let chain_arg =
+            |name: &str| Field::String(BoundedString(BoundedVec(name.as_bytes().to_vec())));
+        let args = [Field::List(BoundedVec(vec![
+            chain_arg("Holesky"),
+            chain_arg("sepolia"),
+        ]))];
+        let service_chains = service_chains_from_args(&args).unwrap();
+        assert_eq!(
+            service_chains,
+            HashSet::from(["holesky".into(), "sepolia".into()])
+        );
+
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.service_chains = Some(service_chains);
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::ChainNotInService { chain }) if chain == "tangletestnet"
+        ));
+
+        for args in [
+            Vec::new(),
+            vec![chain_arg("holesky")],
+            vec![Field::List(BoundedVec(vec![Field::Bool(true)]))],
+        ] {
+            assert!(matches!(
+                service_chains_from_args(&args),
+                Err(WarpRouteJobError::InvalidServiceArgs(_))
+            ));
+        }
+    }
+
+    const THREE_CHAIN_DEPLOY_OUTPUT: &str = r#"
+✅ Warp contract deployment complete
+    tokens:
+      - chainName: holesky
+        standard: EvmHypCollateral
+        addressOrDenom: "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+        collateralAddressOrDenom: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      - chainName: sepolia
+        standard: EvmHypSynthetic
+        addressOrDenom: "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512"
+      - chainName: tangletestnet
+        standard: EvmHypSynthetic
+        addressOrDenom: "0x9fe46736679d2d9a65f0992f2272de9f3c7fa6e0"
+"#;
+
+    #[tokio::test]
+    async fn test_partial_warp_deploy_reports_failed_chains() {
+        let store = Arc::new(MemoryStateStore::default());
+        let mut options = DeployOptions::new(store.clone(), 0);
+        options.phase_order = vec![DeployPhase::WarpDeploy];
+        let sepolia = "      - chainName: sepolia\n        standard: EvmHypSynthetic\n        \
+                       addressOrDenom: \"0xe7f1725e7734ce288f8367e1bb143e90bb3f0512\"\n";
+        let stdout = THREE_CHAIN_DEPLOY_OUTPUT.replace(sepolia, "");
+        assert_ne!(stdout, THREE_CHAIN_DEPLOY_OUTPUT);
+        let executor = MockExecutor::default().with_failed_output(
+            "run warp deploy",
+            1,
+            &stdout,
+            "Error: failed to deploy to sepolia: insufficient funds for gas",
+        );
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        let Err(WarpRouteJobError::PartialDeployment(deployment)) = &result else {
+            panic!("expected a partial deployment, got {result:?}");
+        };
+        assert_eq!(
+            deployment.deployed.chains.keys().collect::<Vec<_>>(),
+            ["holesky", "tangletestnet"]
+        );
+        assert_eq!(deployment.failed_chains(), ["sepolia"]);
+        assert!(deployment.failed["sepolia"].contains("insufficient funds"));
+
+        let state = store.load(0).await.unwrap();
+        assert!(!state.is_phase_complete(DeployPhase::WarpDeploy));
+        assert!(state.deployed_route().is_none());
+    }
+
+    const TWO_CHAIN_CONFIG: &str = r#"
+    holesky:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "collateral"
+      token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+    sepolia:
+      interchainSecurityModule:
+        relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        type: "trustedRelayerIsm"
+      isNft: false
+      mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      type: "synthetic"
+    "#;
+
+    const TWO_CHAIN_DEPLOY_OUTPUT: &str = r#"
+✅ Warp contract deployment complete
+    tokens:
+      - chainName: holesky
+        standard: EvmHypCollateral
+        addressOrDenom: "0xcf7ed3acca5a467e9e704c703e8d87f634fb0fc9"
+        collateralAddressOrDenom: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+      - chainName: sepolia
+        standard: EvmHypSynthetic
+        addressOrDenom: "0xdc64a140aa3e981100a9beca4e685f962f0cf6c9"
+"#;
+
+    async fn dry_run_report(config: &str, deploy_output: &str) -> DeploymentReport {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+            .with_dry_run_output("run warp deploy", deploy_output);
+        let mut report = DeploymentReport::default();
+        deploy_warp_route_with_report(&mut runner, config.as_bytes(), None, &options, &mut report)
+            .await
+            .unwrap();
+        report
+    }
+
+    #[tokio::test]
+    async fn test_deploy_returns_deployed_route() {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+            .with_dry_run_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+
+        let route = deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            route,
+            DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap()
+        );
+        assert_eq!(route.chains.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_readback_compares_the_deployed_route_with_its_config() {
+        let route = DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap();
+        let deploy = |read_back: WarpRouteConfig| async move {
+            let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            options.phase_order = vec![DeployPhase::WarpDeploy];
+            options.check_readback = true;
+            let read_output = read_back.to_yaml().unwrap();
+            let mut executor =
+                MockExecutor::default().with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+            for chain in read_back.chain_names() {
+                executor =
+                    executor.with_output(format!("run warp read --chain {chain}"), &read_output);
+            }
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await
+        };
+
+        // The routers the deployment enrolled aren't part of the submitted config
+        let routers: BTreeMap<String, Address> = route
+            .chains
+            .iter()
+            .map(|(chain, addresses)| (chain.clone(), addresses.router))
+            .collect();
+        let mut deployed = WarpRouteConfig::from_yaml(THREE_CHAIN_CONFIG).unwrap();
+        for chain in routers.keys() {
+            deployed.enroll_routers(chain, &routers).unwrap();
+        }
+        assert_eq!(deploy(deployed.clone()).await.unwrap(), Some(route));
+
+        deployed.update_owner(Address::repeat_byte(0x11));
+        let result = deploy(deployed).await;
+        let Err(WarpRouteJobError::PostDeployMismatch { mismatches }) = result else {
+            panic!("Expected a mismatch, got {result:?}");
+        };
+        assert_eq!(mismatches.len(), 3);
+        assert!(mismatches[0].starts_with("`holesky`: owner: "));
+    }
+
+    #[tokio::test]
+    async fn test_job_result_identifies_route() {
+        let three_chains = dry_run_report(THREE_CHAIN_CONFIG, THREE_CHAIN_DEPLOY_OUTPUT)
+            .await
+            .route;
+        let two_chains = dry_run_report(TWO_CHAIN_CONFIG, TWO_CHAIN_DEPLOY_OUTPUT)
+            .await
+            .route;
+        assert_ne!(three_chains, two_chains);
+        assert_eq!(
+            three_chains,
+            dry_run_report(THREE_CHAIN_CONFIG, THREE_CHAIN_DEPLOY_OUTPUT)
+                .await
+                .route
+        );
+
+        let summary = three_chains.unwrap();
+        let route = DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap();
+        assert_eq!(summary.route_id, route.route_id());
+        assert_eq!(summary.chains, route.chains);
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_call_returns_the_cached_result() {
+        let store = state::MemoryCallStore::default();
+        let key = CallKey {
+            service_id: 0,
+            call_id: 12,
+        };
+        let mut results = Vec::new();
+        let mut commands_run = Vec::new();
+        for _ in 0..2 {
+            // A fresh state, so that only the cached result keeps the call from deploying again
+            let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            options.phase_order = vec![DeployPhase::WarpDeploy];
+            let executor =
+                MockExecutor::default().with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            let job = async {
+                let mut report = DeploymentReport::default();
+                deploy_warp_route_with_report(
+                    &mut runner,
+                    THREE_CHAIN_CONFIG.as_bytes(),
+                    None,
+                    &options,
+                    &mut report,
+                )
+                .await?;
+                Ok::<_, WarpRouteJobError>(serde_json::to_vec(&report)?)
+            };
+            results.push(run_once(&store, key, job).await.unwrap());
+            commands_run.push(runner.history().len());
+        }
+        assert_eq!(commands_run, [2, 0]);
+        assert_eq!(results[0], results[1]);
+        assert_eq!(
+            store.completed(key).await.unwrap(),
+            Some(results[0].clone())
+        );
+    }
+
+    /// A `hyperlane` CLI appending the arguments of each invocation to `invocations.log` next to
+    /// it, that knows the chains of [`THREE_CHAIN_CONFIG`] and does nothing else.
+    const FAKE_CLI: &str = r#"#!/bin/sh
+echo "$*" >>"$(dirname "$0")/invocations.log"
+case "$*" in
+  --version) echo 5.3.0 ;;
+  "registry list"*) printf '│ holesky │\n│ sepolia │\n│ tangletestnet │\n' ;;
+esac
+"#;
+
+    /// A context running the [`FAKE_CLI`] written to `dir`, keeping its work and calls there.
+    fn fake_cli_context(dir: &Path) -> HyperlaneContext {
+        use std::os::unix::fs::PermissionsExt;
+
+        let binary = dir.join("hyperlane");
+        std::fs::write(&binary, FAKE_CLI).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        HyperlaneContext {
+            env: Default::default(),
+            dry_run: false,
+            service_chains: None,
+            quiet: true,
+            balance_check: None,
+            cost_cap: None,
+            relayer_allowlist: None,
+            validator_registry: None,
+            reject_shared_mailboxes: false,
+            check_readback: false,
+            check_owner_consistency: false,
+            pre_deploy_commands: Vec::new(),
+            post_deploy_commands: Vec::new(),
+            confirmation: None,
+            reconcile_retries: DEFAULT_RECONCILE_RETRIES,
+            max_payload_size: hyperlane::DEFAULT_MAX_PAYLOAD_SIZE,
+            network: Network::Testnet,
+            registry_url: None,
+            hyperlane_bin: binary.to_string_lossy().into_owned(),
+            rpc_overrides: HashMap::new(),
+            signing_key_file: None,
+            gas_overrides: GasOverrides::default(),
+            prompt_timeout: runner::DEFAULT_PROMPT_TIMEOUT,
+            capture_limit: runner::DEFAULT_CAPTURE_LIMIT,
+            base_dir: dir.join("work"),
+            registry_artifacts_dir: None,
+            command_log: None,
+            deployment_locks: DeploymentLocks::default(),
+            call_store: Arc::new(state::FileCallStore::new(dir.join("calls"))),
+            pending_calls: PendingCalls::default(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_job_call_runs_no_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Arc::new(fake_cli_context(dir.path()));
+        let invocations = || {
+            std::fs::read_to_string(dir.path().join("invocations.log"))
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        let mut results = Vec::new();
+        let mut commands_run = Vec::new();
+        for _ in 0..2 {
+            // The same call, delivered twice, as the pre-processor would queue it
+            ctx.pending_calls.push(0, 12);
+            let before = invocations();
+            let result = operate_a_warp_route(
+                Arc::clone(&ctx),
+                THREE_CHAIN_CONFIG.as_bytes().to_vec(),
+                false,
+                None,
+                Some(vec!["registryInit".to_string()]),
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            results.push(result);
+            commands_run.push(invocations() - before);
+        }
+        assert!(commands_run[0] > 0);
+        assert_eq!(commands_run[1], 0);
+        assert_eq!(results[0], results[1]);
+
+        // Another call of the same job runs again
+        ctx.pending_calls.push(0, 13);
+        let before = invocations();
+        operate_a_warp_route(
+            Arc::clone(&ctx),
+            THREE_CHAIN_CONFIG.as_bytes().to_vec(),
+            false,
+            None,
+            Some(vec!["registryInit".to_string()]),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(invocations() > before);
+    }
+
+    #[tokio::test]
+    async fn test_ownership_and_core_jobs_check_their_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ctx = fake_cli_context(dir.path());
+        ctx.service_chains = Some(HashSet::from(["holesky".into(), "mainnet".into()]));
+        let ctx = Arc::new(ctx);
+        let owner = Address::repeat_byte(0x11).to_string();
+        let transfer = |chains: &[&str]| {
+            let chains = chains.iter().map(|chain| chain.to_string()).collect();
+            transfer_ownership(Arc::clone(&ctx), owner.clone(), chains, None)
+        };
+
+        assert!(matches!(
+            transfer(&[]).await,
+            Err(WarpRouteJobError::NoChains)
+        ));
+        assert!(matches!(
+            transfer(&["holesky", "sepolia"]).await,
+            Err(WarpRouteJobError::ChainNotInService { chain }) if chain == "sepolia"
+        ));
+        assert!(matches!(
+            reconcile_core_config(Arc::clone(&ctx), b"{}".to_vec(), None).await,
+            Err(WarpRouteJobError::NoChains)
+        ));
+        // None of these touched the CLI
+        assert!(!dir.path().join("invocations.log").exists());
+
+        // Chain names are normalized before they are checked against the registry
+        assert!(matches!(
+            transfer(&[" Mainnet"]).await,
+            Err(WarpRouteJobError::UnknownChain { name }) if name == "mainnet"
+        ));
+        let result = transfer(&["Holesky", "holesky"]).await.unwrap();
+        let results: BTreeMap<String, bool> = serde_json::from_slice(&result).unwrap();
+        assert_eq!(results.into_keys().collect::<Vec<_>>(), ["holesky"]);
+    }
+
+    #[tokio::test]
+    async fn test_jobs_write_rpc_overrides_into_the_service_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ctx = fake_cli_context(dir.path());
+        ctx.rpc_overrides = HashMap::from([(
+            "holesky".to_string(),
+            "https://holesky.example.com".to_string(),
+        )]);
+        let ctx = Arc::new(ctx);
+        let metadata = ctx
+            .service_registry_dir()
+            .join("chains/holesky/metadata.yaml");
+
+        // Invalid arguments fail the job before it touches the registry
+        let result =
+            transfer_ownership(Arc::clone(&ctx), "0xnope".to_string(), Vec::new(), None).await;
+        assert!(matches!(result, Err(WarpRouteJobError::InvalidAddress(_))));
+        assert!(!metadata.exists());
+
+        operate_a_warp_route(
+            Arc::clone(&ctx),
+            THREE_CHAIN_CONFIG.as_bytes().to_vec(),
+            false,
+            None,
+            Some(vec!["registryInit".to_string()]),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(std::fs::read_to_string(&metadata)
+            .unwrap()
+            .contains("https://holesky.example.com"));
+        assert_eq!(
+            ctx.service_registry_dir(),
+            dir.path().join("work").join("0").join(".registry")
+        );
+        // Every command reads the service's registry rather than `~/.hyperlane`
+        let invocations = std::fs::read_to_string(dir.path().join("invocations.log")).unwrap();
+        let overrides = format!("--overrides {}", ctx.service_registry_dir().display());
+        assert!(invocations
+            .lines()
+            .filter(|line| *line != "--version")
+            .all(|line| line.ends_with(&overrides)));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_hooks_run_around_the_phases() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::WarpDeploy];
+        options.pre_deploy_commands = vec![("top up".into(), "./faucet.sh".into())];
+        options.post_deploy_commands = vec![("notify".into(), "./notify.sh".into())];
+        let executor = MockExecutor::default()
+            .with_output("top up", "funded")
+            .with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT)
+            .with_output("notify", "sent");
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let mut report = DeploymentReport::default();
+        deploy_warp_route_with_report(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            None,
+            &options,
+            &mut report,
+        )
+        .await
+        .unwrap();
+
+        let names: Vec<&str> = runner
+            .history()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["top up", "run warp deploy", "notify"]);
+        assert_eq!(runner.history()[0].1, "./faucet.sh");
+        assert_eq!(
+            report.pre_deploy_hooks,
+            [HookReport {
+                name: "top up".into(),
+                output: CapturedOutput {
+                    compressed: false,
+                    data: "funded".into(),
+                },
+            }]
+        );
+        assert_eq!(report.post_deploy_hooks[0].output.decode().unwrap(), "sent");
+
+        // A failing pre-deploy command stops the deployment before it starts
+        let executor = MockExecutor::default().with_failure("top up", 1);
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+        assert!(result.is_err());
+        assert_eq!(runner.history().len(), 1);
+        assert!(options
+            .state_store
+            .load(0)
+            .await
+            .unwrap()
+            .deployed_route()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_report_compresses_long_hook_output() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::WarpDeploy];
+        options.post_deploy_commands = vec![("dump logs".into(), "./dump-logs.sh".into())];
+        let logs = "Relaying message 0x01 from holesky to sepolia\n".repeat(1000);
+        let executor = MockExecutor::default()
+            .with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT)
+            .with_output("dump logs", &logs);
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let mut report = DeploymentReport::default();
+        deploy_warp_route_with_report(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            None,
+            &options,
+            &mut report,
+        )
+        .await
+        .unwrap();
+
+        let output = &report.post_deploy_hooks[0].output;
+        assert!(output.compressed);
+        let result = serde_json::to_vec(&report).unwrap();
+        assert!(result.len() < logs.len() / 10);
+        let decoded: DeploymentReport = serde_json::from_slice(&result).unwrap();
+        assert_eq!(decoded.post_deploy_hooks[0].output.decode().unwrap(), logs);
+    }
+
+    #[tokio::test]
+    async fn test_report_lists_chains_phases_and_commands() {
+        let store = Arc::new(MemoryStateStore::default());
+        let mut completed = DeploymentState::default();
+        completed.mark_phase_complete(DeployPhase::RegistryInit);
+        store.save(0, &completed).await.unwrap();
+
+        let mut options = DeployOptions::new(store, 0);
+        options.chain_filter = Some(HashSet::from(["holesky".into(), "sepolia".into()]));
+        let mut executor =
+            MockExecutor::default().with_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+        for chain in ["holesky", "sepolia"] {
+            executor =
+                executor.with_output(format!("run core read --chain {chain}"), CORE_READ_OUTPUT);
+        }
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let mut report = DeploymentReport::default();
+        deploy_warp_route_with_report(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            None,
+            &options,
+            &mut report,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.chains, ["holesky", "sepolia"]);
+        assert_eq!(report.skipped_chains, ["tangletestnet"]);
+        let phases: Vec<_> = report
+            .phases
+            .iter()
+            .map(|phase| (phase.phase, phase.status))
+            .collect();
+        assert_eq!(
+            phases,
+            [
+                (DeployPhase::RegistryInit, PhaseStatus::Skipped),
+                (DeployPhase::CoreDeploy, PhaseStatus::Completed),
+                (DeployPhase::WarpDeploy, PhaseStatus::Completed),
+                (DeployPhase::CoreApply, PhaseStatus::Completed),
+            ]
+        );
+        let commands: Vec<&str> = report
+            .commands
+            .iter()
+            .map(|command| command.name.as_str())
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                "run core init advanced",
+                "run core deploy",
+                "run warp deploy",
+                "run core read --chain holesky",
+                "run core read --chain sepolia",
+            ]
+        );
+        let route = DeployedRoute::from_deploy_output(THREE_CHAIN_DEPLOY_OUTPUT).unwrap();
+        assert_eq!(report.route, Some(route.summary()));
+
+        let json = serde_json::to_vec(&report).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<DeploymentReport>(&json).unwrap(),
+            report
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_continues_past_failed_routes() {
+        let routes = vec![
+            ("broken".to_string(), b"not a warp route config".to_vec()),
+            ("three".to_string(), THREE_CHAIN_CONFIG.as_bytes().to_vec()),
+        ];
+        let report = deploy_batch(routes, |_, config| async move {
+            let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+            let mut runner = CommandRunner::new(GadgetProcessManager::new(), true)
+                .with_dry_run_output("run warp deploy", THREE_CHAIN_DEPLOY_OUTPUT);
+            let mut report = DeploymentReport::default();
+            deploy_warp_route_with_report(&mut runner, &config, None, &options, &mut report)
+                .await?;
+            Ok(report)
+        })
+        .await;
+
+        assert_eq!(report.succeeded(), 1);
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.routes[0].label, "broken");
+        assert!(report.routes[0].error.is_some());
+        assert_eq!(report.routes[1].label, "three");
+        let route = report.routes[1].report.as_ref().unwrap();
+        assert_eq!(route.chains, ["holesky", "sepolia", "tangletestnet"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_ends_the_batch() {
+        let routes = vec![
+            ("first".to_string(), Vec::new()),
+            ("second".to_string(), Vec::new()),
+        ];
+        let mut deployed = Vec::new();
+        let report = deploy_batch(routes, |label, _| {
+            deployed.push(label);
+            async { Err(WarpRouteJobError::Cancelled) }
+        })
+        .await;
+
+        assert_eq!(deployed, ["first"]);
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn test_quiet_mode_does_not_dump_configs() {
+        let config = WarpRouteConfig::try_from(THREE_CHAIN_CONFIG.as_bytes()).unwrap();
+
+        let quiet = describe_configs(&config, None, true);
+        assert_eq!(quiet.len(), 1);
+        assert!(!quiet[0].contains("mailbox"));
+        assert!(quiet[0].contains("tangletestnet"));
+
+        let verbose = describe_configs(&config, None, false);
+        assert_eq!(
+            verbose,
+            vec![format!("Deserialized WarpRouteConfig: {config:?}")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_underfunded_deployer_runs_no_commands() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.balance_check = Some(preflight::tests::mock_balance_check(&[
+            ("holesky", 1_000),
+            ("sepolia", 1_000),
+        ]));
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::InsufficientBalance { chain, .. }) if chain == "tangletestnet"
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cost_over_cap_blocks_deploy() {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.cost_cap = Some(preflight::tests::fixed_cost_cap(2_000, 1_000));
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::CostExceedsCap { .. })
+        ));
+        assert!(runner.history().is_empty());
+    }
+
+    /// Runs only [`DeployPhase::CoreApply`], with every chain's core config read as
+    /// [`CORE_READ_OUTPUT`].
+    async fn run_core_apply(existing_core_config: Option<&str>) -> CommandRunner {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::RegistryInit, DeployPhase::CoreApply];
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        for chain in ["holesky", "sepolia", "tangletestnet"] {
+            runner = runner
+                .with_dry_run_output(format!("run core read --chain {chain}"), CORE_READ_OUTPUT);
+        }
+        deploy_warp_route(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            existing_core_config.map(str::as_bytes),
+            &options,
+        )
+        .await
+        .unwrap();
+        runner
+    }
+
+    /// The commands [`DeployPhase::CoreDeploy`] issues for `existing_core_config`.
+    async fn run_core_deploy(existing_core_config: Option<&str>) -> Vec<String> {
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        options.phase_order = vec![DeployPhase::RegistryInit, DeployPhase::CoreDeploy];
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(
+            &mut runner,
+            THREE_CHAIN_CONFIG.as_bytes(),
+            existing_core_config.map(str::as_bytes),
+            &options,
+        )
+        .await
+        .unwrap();
+        runner
+            .history()
+            .iter()
+            .skip(1)
+            .map(|(_, command)| command.clone())
+            .collect()
+    }
+
+    /// [`run_core_deploy`], with the config paths masked by [`mask_temp_configs`].
+    async fn run_core_deploy_masked(existing_core_config: Option<&str>) -> Vec<String> {
+        let commands = run_core_deploy(existing_core_config).await;
+        commands
+            .iter()
+            .map(|command| mask_temp_configs(command))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_core_deploy_uses_existing_core_config() {
+        assert_eq!(
+            run_core_deploy_masked(Some(CORE_READ_OUTPUT)).await,
+            ["hyperlane core init --advanced --config <existing-core-config>"]
+        );
+
+        let undeployed = CORE_READ_OUTPUT.replace(
+            "address: \"0x742d35cc6634c0532925a3b844bc454e4438f44e\"",
+            "address: \"0x0000000000000000000000000000000000000000\"",
+        );
+        assert_eq!(
+            run_core_deploy_masked(Some(&undeployed)).await,
+            [
+                "hyperlane core init --advanced --config <existing-core-config>",
+                "hyperlane core deploy --config <existing-core-config> --yes",
+            ]
+        );
+
+        assert_eq!(
+            run_core_deploy_masked(None).await,
+            [
+                "hyperlane core init --advanced --config configs/core-config.yaml",
+                "hyperlane core deploy --config configs/core-config.yaml --yes"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_core_init_has_no_placeholders() {
+        for existing_core_config in [None, Some(CORE_READ_OUTPUT)] {
+            let commands = run_core_deploy(existing_core_config).await;
+            let core_init = commands
+                .iter()
+                .find(|command| command.starts_with("hyperlane core init"))
+                .unwrap();
+            assert!(!core_init.contains('['), "{core_init}");
+            let config_path = core_init.rsplit(' ').next().unwrap();
+            assert!(config_path.ends_with(".yaml"), "{core_init}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_core_apply_skipped_when_unchanged() {
+        let runner = run_core_apply(Some(CORE_READ_OUTPUT)).await;
+        assert!(!runner
+            .history()
+            .iter()
+            .any(|(_, command)| command.starts_with("hyperlane core apply")));
+        assert_eq!(
+            runner
+                .history()
+                .iter()
+                .filter(|(_, command)| command.starts_with("hyperlane core read"))
+                .count(),
+            3
+        );
+
+        let runner = run_core_apply(None).await;
+        assert!(!runner
+            .history()
+            .iter()
+            .any(|(_, command)| command.starts_with("hyperlane core apply")));
+    }
+
+    #[tokio::test]
+    async fn test_core_apply_runs_when_changed() {
+        let desired = CORE_READ_OUTPUT.replace("protocolFee: \"0\"", "protocolFee: \"1000\"");
+        let runner = run_core_apply(Some(&desired)).await;
+        let applies: Vec<String> = runner
+            .history()
+            .iter()
+            .filter(|(name, _)| name.starts_with("run core apply"))
+            .map(|(_, command)| apply_input(command))
+            .collect();
+        assert_eq!(applies.len(), 3);
+        for input in applies {
+            assert_eq!(
+                CoreConfig::from_yaml(&input).unwrap(),
+                CoreConfig::from_yaml(&desired).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_input_with_quotes_is_escaped() {
+        let input = "owner: '0x11'\nname: \"it's\"; rm -rf /";
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        read_modify_apply(&mut runner, "warp", "holesky", |_| Ok(input.to_string()))
+            .await
+            .unwrap();
+
+        let (_, apply_command) = runner.history().last().unwrap();
+        assert_eq!(
+            apply_command,
+            r#"hyperlane warp apply --chain holesky --input 'owner: '\''0x11'\''
+name: "it'\''s"; rm -rf /' --yes"#
+        );
+        assert_eq!(apply_input(apply_command), input);
+    }
+
+    /// Records the fields of every event emitted while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct EventCapture(Arc<std::sync::Mutex<Vec<BTreeMap<String, String>>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for EventCapture {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = FieldRecorder::default();
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+    }
+
+    #[derive(Default)]
+    struct FieldRecorder(BTreeMap<String, String>);
+
+    impl tracing::field::Visit for FieldRecorder {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phases_emit_structured_events() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = EventCapture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let mut options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 7);
+        options.phase_order = vec![DeployPhase::RegistryInit];
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        let events = capture.0.lock().unwrap();
+        let has_event = |expected: &[(&str, &str)]| {
+            events.iter().any(|fields| {
+                expected
+                    .iter()
+                    .all(|(name, value)| fields.get(*name).map(String::as_str) == Some(*value))
+            })
+        };
+        assert!(has_event(&[
+            ("message", "Phase complete"),
+            ("service_id", "7"),
+            ("phase", "RegistryInit"),
+        ]));
+        assert!(has_event(&[("command", "run registry init")]));
+    }
+
+    #[tokio::test]
+    async fn test_failing_command_fails_the_deployment() {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        // `false registry init` exits 1
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false)
+            .with_cli(HyperlaneCli::default().with_binary("false"));
+        let result =
+            deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options).await;
+
+        assert!(matches!(
+            result,
+            Err(WarpRouteJobError::Runner(RunnerError::CommandFailed {
+                code: Some(1),
+                ..
+            }))
+        ));
+        assert_eq!(runner.history().len(), 1);
+        let state = options.state_store.load(0).await.unwrap();
+        assert!(!state.is_phase_complete(DeployPhase::RegistryInit));
+    }
+
+    #[tokio::test]
+    async fn test_registry_init_skipped_when_registry_exists() {
+        let registry_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(registry_dir.path().join("chains").join("holesky")).unwrap();
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        ensure_registry(&mut runner, Some(registry_dir.path()))
+            .await
+            .unwrap();
+        assert!(runner.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registry_init_runs_without_registry() {
+        let registry_dir = tempfile::tempdir().unwrap();
+
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        ensure_registry(&mut runner, Some(registry_dir.path()))
+            .await
+            .unwrap();
+        assert_eq!(
+            runner.history(),
+            [(
+                "run registry init".to_string(),
+                "hyperlane registry init".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_service_work_dir_is_distinct_per_label() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let default = service_work_dir(base_dir.path(), 7, None).unwrap();
+        let staging = service_work_dir(base_dir.path(), 7, Some("staging")).unwrap();
+        let other_service = service_work_dir(base_dir.path(), 8, None).unwrap();
+
+        assert_eq!(default, base_dir.path().join("7").join(DEFAULT_LABEL));
+        assert_eq!(staging, base_dir.path().join("7").join("staging"));
+        assert_ne!(default, other_service);
+        assert!(default.is_dir() && staging.is_dir() && other_service.is_dir());
+
+        // No label shares the service's registry
+        assert!(!service_registry_dir(base_dir.path(), 7).starts_with(&default));
+        for label in ["", "..", "../8/default", "a/b", ".hidden", ".registry"] {
+            assert!(matches!(
+                service_work_dir(base_dir.path(), 7, Some(label)),
+                Err(WarpRouteJobError::InvalidLabel(_))
+            ));
+        }
+    }
+
+    /// Compares `actual` line by line against the golden file at `path` (relative to the crate
+    /// root), failing with a diff on mismatch. Set `UPDATE_GOLDEN=1` to rewrite the file instead.
+    fn assert_matches_golden(path: &str, actual: &[&str]) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual.join("\n") + "\n").unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap();
+        let expected: Vec<&str> = expected.lines().collect();
+        if expected == actual {
+            return;
+        }
+        let mut diff = String::new();
+        for i in 0..expected.len().max(actual.len()) {
+            match (expected.get(i), actual.get(i)) {
+                (Some(expected), Some(actual)) if expected == actual => {
+                    diff += &format!("  {expected}\n");
+                }
+                (expected, actual) => {
+                    if let Some(expected) = expected {
+                        diff += &format!("- {expected}\n");
+                    }
+                    if let Some(actual) = actual {
+                        diff += &format!("+ {actual}\n");
+                    }
+                }
+            }
+        }
+        panic!(
+            "issued commands differ from {} (rerun with UPDATE_GOLDEN=1 to accept):\n{diff}",
+            path.display()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deploy_commands_match_golden_file() {
+        let options = DeployOptions::new(Arc::new(MemoryStateStore::default()), 0);
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        deploy_warp_route(&mut runner, THREE_CHAIN_CONFIG.as_bytes(), None, &options)
+            .await
+            .unwrap();
+
+        let issued: Vec<String> = runner
+            .history()
+            .iter()
+            .map(|(_, command)| mask_temp_configs(command))
+            .collect();
+        let issued: Vec<&str> = issued.iter().map(String::as_str).collect();
+        assert_matches_golden("testdata/expected_commands.txt", &issued);
+    }
 }
@@ -1,3 +1,11 @@
+use blueprint::confirmation::ConfirmationPolling;
+use blueprint::hyperlane::validators::{RpcValidatorRegistry, ValidatorRegistry};
+use blueprint::hyperlane::{Network, Registry};
+use blueprint::preflight::{BalanceCheck, CostCap, RpcEndpoints};
+use blueprint::runner::{
+    GasOverrides, KeyFile, SigningKey, DEFAULT_CAPTURE_LIMIT, DEFAULT_PROMPT_TIMEOUT,
+};
+use blueprint::state::FileCallStore;
 use color_eyre::Result;
 use gadget_sdk as sdk;
 pub use hyperlane_relayer_blueprint as blueprint;
@@ -5,18 +13,137 @@ use sdk::ctx::TangleClientContext;
 use sdk::info;
 use sdk::job_runner::MultiJobRunner;
 use sdk::tangle_subxt::subxt::tx::Signer;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 #[sdk::main(env)]
 async fn main() -> Result<()> {
     color_eyre::install()?;
 
-    let ctx = Arc::new(blueprint::HyperlaneContext { env });
+    let base_dir = std::env::var_os("HYPERLANE_WORK_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env.data_dir.clone())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let signing_key = std::env::var_os("HYPERLANE_KEY_FILE")
+        .map(|path| std::fs::read_to_string(path).map(|key| SigningKey::new(key.trim())))
+        .transpose()?;
+    let network: Network = std::env::var("HYPERLANE_NETWORK")
+        .ok()
+        .map(|network| network.parse())
+        .transpose()?
+        .unwrap_or_default();
+    // The CLI and the preflight and confirmation checks talk to the same endpoints
+    let rpc_endpoints = RpcEndpoints::from_env()?;
+    let rpc_overrides = rpc_endpoints
+        .as_ref()
+        .map(|endpoints| endpoints.rpc_urls().clone())
+        .unwrap_or_default();
+    Registry::validate_rpc_overrides(&rpc_overrides)?;
+    let mut ctx = blueprint::HyperlaneContext {
+        env,
+        dry_run: std::env::var_os("DRY_RUN").is_some(),
+        // Read from the service's instance on Tangle below
+        service_chains: None,
+        quiet: std::env::var_os("VERBOSE").is_none(),
+        balance_check: BalanceCheck::from_env(rpc_endpoints.as_ref(), signing_key.as_ref())?,
+        cost_cap: CostCap::from_env(rpc_endpoints.as_ref())?,
+        relayer_allowlist: std::env::var("RELAYER_ALLOWLIST")
+            .ok()
+            .map(|relayers| {
+                relayers
+                    .split(',')
+                    .map(|relayer| relayer.trim().parse())
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()?,
+        validator_registry: RpcValidatorRegistry::from_env(rpc_endpoints.as_ref())?
+            .map(|registry| Arc::new(registry) as Arc<dyn ValidatorRegistry>),
+        reject_shared_mailboxes: std::env::var_os("REJECT_SHARED_MAILBOXES").is_some(),
+        check_readback: std::env::var_os("CHECK_READBACK").is_some(),
+        check_owner_consistency: std::env::var_os("CHECK_OWNER_CONSISTENCY").is_some(),
+        pre_deploy_commands: hook_commands("PRE_DEPLOY_COMMANDS")?,
+        post_deploy_commands: hook_commands("POST_DEPLOY_COMMANDS")?,
+        confirmation: ConfirmationPolling::from_env(rpc_endpoints.as_ref())?,
+        reconcile_retries: std::env::var("RECONCILE_RETRIES")
+            .ok()
+            .map(|retries| retries.parse())
+            .transpose()?
+            .unwrap_or(blueprint::DEFAULT_RECONCILE_RETRIES),
+        max_payload_size: std::env::var("MAX_CONFIG_PAYLOAD_BYTES")
+            .ok()
+            .map(|size| size.parse())
+            .transpose()?
+            .unwrap_or(blueprint::hyperlane::DEFAULT_MAX_PAYLOAD_SIZE),
+        network,
+        registry_url: std::env::var(network.registry_url_var()).ok(),
+        hyperlane_bin: std::env::var("HYPERLANE_BIN")
+            .unwrap_or_else(|_| blueprint::runner::DEFAULT_BINARY.to_string()),
+        rpc_overrides,
+        // Read by the signed commands, so the key stays off their command lines
+        signing_key_file: signing_key
+            .as_ref()
+            .map(|key| KeyFile::new(&base_dir, key))
+            .transpose()?,
+        gas_overrides: GasOverrides {
+            max_fee_per_gas: std::env::var("MAX_FEE_PER_GAS")
+                .ok()
+                .map(|fee| fee.parse())
+                .transpose()?,
+            max_priority_fee_per_gas: std::env::var("MAX_PRIORITY_FEE_PER_GAS")
+                .ok()
+                .map(|fee| fee.parse())
+                .transpose()?,
+        },
+        prompt_timeout: std::env::var("PROMPT_TIMEOUT_SECS")
+            .ok()
+            .map(|secs| secs.parse::<u64>().map(Duration::from_secs))
+            .transpose()?
+            .unwrap_or(DEFAULT_PROMPT_TIMEOUT),
+        capture_limit: std::env::var("CAPTURE_LIMIT_BYTES")
+            .ok()
+            .map(|bytes| bytes.parse())
+            .transpose()?
+            .unwrap_or(DEFAULT_CAPTURE_LIMIT),
+        call_store: Arc::new(FileCallStore::new(base_dir.join("calls"))),
+        pending_calls: Default::default(),
+        base_dir,
+        registry_artifacts_dir: std::env::var_os("REGISTRY_ARTIFACTS_DIR").map(PathBuf::from),
+        command_log: std::env::var_os("COMMAND_LOG").map(PathBuf::from),
+        deployment_locks: Default::default(),
+        cancellation: CancellationToken::new(),
+    };
 
     let client = ctx.tangle_client().await?;
+    let service_chains = ctx.load_service_chains(&client).await?;
+    info!("Serving the chains {service_chains:?}");
+    ctx.service_chains = Some(service_chains);
+    let ctx = Arc::new(ctx);
     let signer = ctx.env.first_sr25519_signer()?;
 
     let start_warp_route = blueprint::OperateAWarpRouteEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id.unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
+
+    let start_warp_routes = blueprint::OperateWarpRoutesEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id.unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
+
+    let transfer_ownership = blueprint::TransferOwnershipEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id.unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
+
+    let reconcile_core_config = blueprint::ReconcileCoreConfigEventHandler {
         ctx: Arc::clone(&ctx),
         service_id: ctx.env.service_id.unwrap(),
         signer: signer.clone(),
@@ -25,10 +152,32 @@ async fn main() -> Result<()> {
 
     info!("Starting the event watcher for {} ...", signer.account_id());
 
-    MultiJobRunner::new(ctx.env.clone())
+    let jobs = MultiJobRunner::new(ctx.env.clone())
         .job(start_warp_route)
-        .run()
-        .await?;
+        .job(transfer_ownership)
+        .job(start_warp_routes)
+        .job(reconcile_core_config)
+        .run();
+    tokio::select! {
+        result = jobs => result?,
+        result = tokio::signal::ctrl_c() => {
+            result?;
+            info!("Shutting down, cancelling running jobs ...");
+            ctx.cancellation.cancel();
+            ctx.deployment_locks.wait_idle().await;
+        }
+    }
 
     Ok(())
 }
+
+/// The hook commands in the env var `var`, a JSON array of `[name, command]` pairs, e.g.
+/// `[["top up", "./faucet.sh"]]`. Unset means no commands.
+fn hook_commands(var: &str) -> Result<Vec<(String, String)>> {
+    std::env::var(var)
+        .ok()
+        .map(|commands| serde_json::from_str(&commands))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| color_eyre::eyre::eyre!("Invalid {var}: {e}"))
+}
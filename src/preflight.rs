@@ -0,0 +1,530 @@
+use crate::hyperlane::{ChainName, WarpRouteConfig};
+use crate::runner::SigningKey;
+use crate::WarpRouteJobError;
+use alloy_primitives::{Address, U256};
+use alloy_provider::{Provider, RootProvider};
+use alloy_rpc_client::RpcClient;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_transport_http::Http;
+use async_trait::async_trait;
+use gadget_sdk::info;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Balance required by default before deploying to a chain: 0.01 of the native token.
+pub const DEFAULT_MIN_BALANCE: U256 = U256::from_limbs([10_000_000_000_000_000, 0, 0, 0]);
+
+#[derive(Error, Debug)]
+pub enum PreflightError {
+    #[error("No RPC URL configured for chain `{chain}`")]
+    MissingRpcUrl { chain: String },
+    #[error("RPC error on `{chain}`: {message}")]
+    Rpc { chain: String, message: String },
+    #[error("No deployer key, set HYPERLANE_KEY_FILE or HYP_KEY")]
+    MissingKey,
+    #[error("Invalid HYP_KEY: {0}")]
+    InvalidKey(String),
+    #[error("Invalid {name}: {value}")]
+    InvalidSetting { name: &'static str, value: String },
+    #[error("Invalid RPC header `{header}` for chain `{chain}`")]
+    InvalidHeader { chain: String, header: String },
+}
+
+/// The JSON-RPC endpoint of each chain, along with any HTTP headers (e.g. API keys) the
+/// endpoint requires.
+#[derive(Clone, Default)]
+pub struct RpcEndpoints {
+    rpc_urls: HashMap<String, String>,
+    rpc_headers: Option<HashMap<String, HashMap<String, String>>>,
+}
+
+impl RpcEndpoints {
+    pub fn new(
+        rpc_urls: HashMap<String, String>,
+        rpc_headers: Option<HashMap<String, HashMap<String, String>>>,
+    ) -> Self {
+        Self {
+            rpc_urls,
+            rpc_headers,
+        }
+    }
+
+    /// Reads `RPC_URLS` (`chain=url,...`) and, optionally, `RPC_HEADERS` (a JSON object of
+    /// chain to header map), with the chain names normalized as [`ChainName`]s.
+    ///
+    /// Returns `None` when `RPC_URLS` is unset.
+    pub fn from_env() -> Result<Option<Self>, PreflightError> {
+        let Ok(rpc_urls) = std::env::var("RPC_URLS") else {
+            return Ok(None);
+        };
+        let rpc_urls = rpc_urls
+            .split(',')
+            .map(|entry| {
+                entry
+                    .split_once('=')
+                    .map(|(chain, url)| (ChainName::new(chain).to_string(), url.trim().to_string()))
+                    .ok_or_else(|| PreflightError::InvalidSetting {
+                        name: "RPC_URLS",
+                        value: entry.to_string(),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let rpc_headers = match std::env::var("RPC_HEADERS") {
+            Ok(headers) => {
+                let headers: HashMap<String, HashMap<String, String>> =
+                    serde_json::from_str(&headers).map_err(|e| {
+                        // The value holds secrets, so only the parse error is reported
+                        PreflightError::InvalidSetting {
+                            name: "RPC_HEADERS",
+                            value: e.to_string(),
+                        }
+                    })?;
+                Some(
+                    headers
+                        .into_iter()
+                        .map(|(chain, headers)| (ChainName::new(chain).to_string(), headers))
+                        .collect(),
+                )
+            }
+            Err(_) => None,
+        };
+
+        let endpoints = Self::new(rpc_urls, rpc_headers);
+        // Reject malformed headers at startup rather than on the first deployment
+        for chain in endpoints.rpc_urls.keys() {
+            endpoints.header_map(chain)?;
+        }
+        Ok(Some(endpoints))
+    }
+
+    /// The RPC URL of each chain, by chain name.
+    pub fn rpc_urls(&self) -> &HashMap<String, String> {
+        &self.rpc_urls
+    }
+
+    /// The headers to send with every request to `chain`'s endpoint.
+    pub fn header_map(&self, chain: &str) -> Result<HeaderMap, PreflightError> {
+        let mut header_map = HeaderMap::new();
+        let Some(headers) = self.rpc_headers.as_ref().and_then(|h| h.get(chain)) else {
+            return Ok(header_map);
+        };
+        for (name, value) in headers {
+            let invalid = || PreflightError::InvalidHeader {
+                chain: chain.to_string(),
+                header: name.clone(),
+            };
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+            let mut value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+            value.set_sensitive(true);
+            header_map.insert(name, value);
+        }
+        Ok(header_map)
+    }
+
+    /// A provider for `chain`'s endpoint that sends its configured headers.
+    pub fn provider(
+        &self,
+        chain: &str,
+    ) -> Result<RootProvider<Http<reqwest::Client>>, PreflightError> {
+        let rpc_error = |message: String| PreflightError::Rpc {
+            chain: chain.to_string(),
+            message,
+        };
+        let url = self
+            .rpc_urls
+            .get(chain)
+            .ok_or_else(|| PreflightError::MissingRpcUrl {
+                chain: chain.to_string(),
+            })?;
+        let url = url
+            .parse()
+            .map_err(|_| rpc_error(format!("invalid RPC URL `{url}`")))?;
+        let client = reqwest::Client::builder()
+            .default_headers(self.header_map(chain)?)
+            .build()
+            .map_err(|e| rpc_error(e.to_string()))?;
+        Ok(RootProvider::new(RpcClient::new(
+            Http::with_client(client, url),
+            false,
+        )))
+    }
+}
+
+impl Debug for RpcEndpoints {
+    /// Header values usually hold API keys, so only their names are shown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header_names: Option<HashMap<&String, Vec<&String>>> =
+            self.rpc_headers.as_ref().map(|headers| {
+                headers
+                    .iter()
+                    .map(|(chain, headers)| (chain, headers.keys().collect()))
+                    .collect()
+            });
+        f.debug_struct("RpcEndpoints")
+            .field("rpc_urls", &self.rpc_urls)
+            .field("rpc_headers", &header_names)
+            .finish()
+    }
+}
+
+/// Looks up native token balances.
+#[async_trait]
+pub trait BalanceProvider: Debug + Send + Sync {
+    async fn balance(&self, chain: &str, address: Address) -> Result<U256, PreflightError>;
+}
+
+/// Queries balances over JSON-RPC, with one endpoint per chain.
+#[derive(Clone, Debug, Default)]
+pub struct RpcBalanceProvider {
+    endpoints: RpcEndpoints,
+}
+
+impl RpcBalanceProvider {
+    pub fn new(endpoints: RpcEndpoints) -> Self {
+        Self { endpoints }
+    }
+}
+
+#[async_trait]
+impl BalanceProvider for RpcBalanceProvider {
+    async fn balance(&self, chain: &str, address: Address) -> Result<U256, PreflightError> {
+        self.endpoints
+            .provider(chain)?
+            .get_balance(address)
+            .await
+            .map_err(|e| PreflightError::Rpc {
+                chain: chain.to_string(),
+                message: e.to_string(),
+            })
+    }
+}
+
+/// Settings for [`check_balances`].
+#[derive(Clone, Debug)]
+pub struct BalanceCheck {
+    pub provider: Arc<dyn BalanceProvider>,
+    /// The account the `hyperlane` CLI deploys from.
+    pub deployer: Address,
+    pub min_balance: U256,
+}
+
+impl BalanceCheck {
+    /// Builds a check over the chains of `endpoints`, for the account of `key` or else
+    /// `HYP_KEY`, requiring `MIN_DEPLOYER_BALANCE` wei or [`DEFAULT_MIN_BALANCE`].
+    ///
+    /// Returns `None` without `endpoints`.
+    pub fn from_env(
+        endpoints: Option<&RpcEndpoints>,
+        key: Option<&SigningKey>,
+    ) -> Result<Option<Self>, PreflightError> {
+        let Some(endpoints) = endpoints else {
+            return Ok(None);
+        };
+
+        let key = match key {
+            Some(key) => key.expose().to_string(),
+            None => std::env::var("HYP_KEY").map_err(|_| PreflightError::MissingKey)?,
+        };
+        let signer: PrivateKeySigner = key
+            .parse()
+            .map_err(|e| PreflightError::InvalidKey(format!("{e}")))?;
+        let min_balance = setting_from_env("MIN_DEPLOYER_BALANCE")?.unwrap_or(DEFAULT_MIN_BALANCE);
+
+        Ok(Some(Self {
+            provider: Arc::new(RpcBalanceProvider::new(endpoints.clone())),
+            deployer: signer.address(),
+            min_balance,
+        }))
+    }
+}
+
+/// Parses the environment variable `name`, if set.
+fn setting_from_env<T: FromStr>(name: &'static str) -> Result<Option<T>, PreflightError> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| PreflightError::InvalidSetting { name, value }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Estimates what deploying a route will cost, in wei.
+#[async_trait]
+pub trait CostEstimator: Debug + Send + Sync {
+    async fn estimate_cost(&self, config: &WarpRouteConfig) -> Result<U256, PreflightError>;
+}
+
+/// Gas used to deploy the core and warp contracts on one chain, by default.
+pub const DEFAULT_DEPLOYMENT_GAS: u64 = 15_000_000;
+
+/// Prices a fixed gas budget per chain at each chain's current gas price.
+#[derive(Clone, Debug)]
+pub struct GasPriceCostEstimator {
+    endpoints: RpcEndpoints,
+    gas_per_chain: u64,
+}
+
+impl GasPriceCostEstimator {
+    pub fn new(endpoints: RpcEndpoints, gas_per_chain: u64) -> Self {
+        Self {
+            endpoints,
+            gas_per_chain,
+        }
+    }
+}
+
+#[async_trait]
+impl CostEstimator for GasPriceCostEstimator {
+    async fn estimate_cost(&self, config: &WarpRouteConfig) -> Result<U256, PreflightError> {
+        let mut total = U256::ZERO;
+        for chain in config.chain_names() {
+            let gas_price = self
+                .endpoints
+                .provider(chain)?
+                .get_gas_price()
+                .await
+                .map_err(|e| PreflightError::Rpc {
+                    chain: chain.to_string(),
+                    message: e.to_string(),
+                })?;
+            total += U256::from(gas_price) * U256::from(self.gas_per_chain);
+        }
+        Ok(total)
+    }
+}
+
+/// Settings for [`check_cost`].
+#[derive(Clone, Debug)]
+pub struct CostCap {
+    pub estimator: Arc<dyn CostEstimator>,
+    pub max_total_cost_wei: U256,
+}
+
+impl CostCap {
+    /// Caps the projected cost at `MAX_TOTAL_COST_WEI`, estimated with a
+    /// [`GasPriceCostEstimator`] over the chains of `endpoints` and `DEPLOYMENT_GAS_PER_CHAIN`
+    /// (or [`DEFAULT_DEPLOYMENT_GAS`]).
+    ///
+    /// Returns `None` when `MAX_TOTAL_COST_WEI` is unset.
+    pub fn from_env(endpoints: Option<&RpcEndpoints>) -> Result<Option<Self>, PreflightError> {
+        let Some(max_total_cost_wei) = setting_from_env("MAX_TOTAL_COST_WEI")? else {
+            return Ok(None);
+        };
+        let endpoints = endpoints.cloned().ok_or(PreflightError::InvalidSetting {
+            name: "RPC_URLS",
+            value: "(unset), required by MAX_TOTAL_COST_WEI".to_string(),
+        })?;
+        let gas_per_chain =
+            setting_from_env("DEPLOYMENT_GAS_PER_CHAIN")?.unwrap_or(DEFAULT_DEPLOYMENT_GAS);
+
+        Ok(Some(Self {
+            estimator: Arc::new(GasPriceCostEstimator::new(endpoints, gas_per_chain)),
+            max_total_cost_wei,
+        }))
+    }
+}
+
+/// Checks that the projected cost of deploying the route stays within `cap`.
+pub async fn check_cost(config: &WarpRouteConfig, cap: &CostCap) -> Result<(), WarpRouteJobError> {
+    let projected = cap.estimator.estimate_cost(config).await?;
+    info!("Projected deployment cost: {projected} wei");
+    if projected > cap.max_total_cost_wei {
+        return Err(WarpRouteJobError::CostExceedsCap {
+            projected,
+            cap: cap.max_total_cost_wei,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the deployer holds at least `check.min_balance` on every chain of the route.
+pub async fn check_balances(
+    config: &WarpRouteConfig,
+    check: &BalanceCheck,
+) -> Result<(), WarpRouteJobError> {
+    for chain in config.chain_names() {
+        let balance = check.provider.balance(chain, check.deployer).await?;
+        if balance < check.min_balance {
+            return Err(WarpRouteJobError::InsufficientBalance {
+                chain: chain.to_string(),
+                address: check.deployer,
+                balance,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const DEPLOYER: Address = Address::new([0x42; 20]);
+
+    #[derive(Debug)]
+    struct MockBalanceProvider {
+        balances: HashMap<String, U256>,
+    }
+
+    #[async_trait]
+    impl BalanceProvider for MockBalanceProvider {
+        async fn balance(&self, chain: &str, _address: Address) -> Result<U256, PreflightError> {
+            Ok(self.balances.get(chain).copied().unwrap_or_default())
+        }
+    }
+
+    pub(crate) fn mock_balance_check(balances: &[(&str, u64)]) -> BalanceCheck {
+        BalanceCheck {
+            provider: Arc::new(MockBalanceProvider {
+                balances: balances
+                    .iter()
+                    .map(|(chain, balance)| (chain.to_string(), U256::from(*balance)))
+                    .collect(),
+            }),
+            deployer: DEPLOYER,
+            min_balance: U256::from(1_000),
+        }
+    }
+
+    fn two_chain_config() -> WarpRouteConfig {
+        WarpRouteConfig::from_yaml(
+            r#"
+            holesky:
+              interchainSecurityModule:
+                relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+                type: "trustedRelayerIsm"
+              isNft: false
+              mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              type: "collateral"
+              token: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+            tangletestnet:
+              interchainSecurityModule:
+                relayer: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+                type: "trustedRelayerIsm"
+              isNft: false
+              mailbox: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              interchainGasPaymaster: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              owner: "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+              type: "synthetic"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn endpoints_with_headers(headers: &[(&str, &str)]) -> RpcEndpoints {
+        RpcEndpoints::new(
+            HashMap::from([
+                (
+                    "holesky".to_string(),
+                    "https://rpc.example/holesky".to_string(),
+                ),
+                (
+                    "sepolia".to_string(),
+                    "https://rpc.example/sepolia".to_string(),
+                ),
+            ]),
+            Some(HashMap::from([(
+                "holesky".to_string(),
+                headers
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            )])),
+        )
+    }
+
+    #[test]
+    fn test_rpc_headers_are_applied_per_chain() {
+        let endpoints = endpoints_with_headers(&[("X-Api-Key", "s3cret")]);
+
+        let holesky = endpoints.header_map("holesky").unwrap();
+        assert_eq!(holesky.len(), 1);
+        assert_eq!(holesky["x-api-key"], "s3cret");
+        assert!(holesky["x-api-key"].is_sensitive());
+        assert!(endpoints.header_map("sepolia").unwrap().is_empty());
+
+        endpoints.provider("holesky").unwrap();
+        assert!(matches!(
+            endpoints.provider("tangletestnet"),
+            Err(PreflightError::MissingRpcUrl { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_rpc_headers_are_rejected() {
+        let endpoints = endpoints_with_headers(&[("X Api Key", "s3cret")]);
+        assert!(matches!(
+            endpoints.header_map("holesky"),
+            Err(PreflightError::InvalidHeader { chain, header })
+                if chain == "holesky" && header == "X Api Key"
+        ));
+        assert!(endpoints.provider("holesky").is_err());
+
+        let endpoints = endpoints_with_headers(&[("X-Api-Key", "line\nbreak")]);
+        assert!(endpoints.header_map("holesky").is_err());
+    }
+
+    #[test]
+    fn test_rpc_header_values_are_redacted() {
+        let endpoints = endpoints_with_headers(&[("X-Api-Key", "s3cret")]);
+        let debug = format!("{endpoints:?}");
+        assert!(debug.contains("X-Api-Key"));
+        assert!(!debug.contains("s3cret"));
+    }
+
+    #[derive(Debug)]
+    struct FixedCostEstimator(U256);
+
+    #[async_trait]
+    impl CostEstimator for FixedCostEstimator {
+        async fn estimate_cost(&self, _config: &WarpRouteConfig) -> Result<U256, PreflightError> {
+            Ok(self.0)
+        }
+    }
+
+    pub(crate) fn fixed_cost_cap(projected: u64, cap: u64) -> CostCap {
+        CostCap {
+            estimator: Arc::new(FixedCostEstimator(U256::from(projected))),
+            max_total_cost_wei: U256::from(cap),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cost_within_cap_passes() {
+        check_cost(&two_chain_config(), &fixed_cost_cap(1_000, 1_000))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cost_over_cap_is_rejected() {
+        assert!(matches!(
+            check_cost(&two_chain_config(), &fixed_cost_cap(1_001, 1_000)).await,
+            Err(WarpRouteJobError::CostExceedsCap { projected, cap })
+                if projected == U256::from(1_001) && cap == U256::from(1_000)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_funded_deployer_passes() {
+        let check = mock_balance_check(&[("holesky", 1_000), ("tangletestnet", 5_000)]);
+        check_balances(&two_chain_config(), &check).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_low_balance_is_rejected() {
+        let check = mock_balance_check(&[("holesky", 1_000), ("tangletestnet", 999)]);
+        assert!(matches!(
+            check_balances(&two_chain_config(), &check).await,
+            Err(WarpRouteJobError::InsufficientBalance { chain, address, balance })
+                if chain == "tangletestnet" && address == DEPLOYER && balance == U256::from(999)
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::capture::CapturedOutput;
+use crate::hyperlane::output::RouteSummary;
+use crate::state::DeployPhase;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What a run of [`operate_a_warp_route`](crate::operate_a_warp_route) did, returned as its job
+/// result.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentReport {
+    /// The chains of the route this operator read and applied configs on.
+    pub chains: Vec<String>,
+    /// The chains of the route left to other operators by the chain filter.
+    pub skipped_chains: Vec<String>,
+    /// Every phase of the run, in order, up to the first that failed.
+    pub phases: Vec<PhaseReport>,
+    /// Every command the run issued, in order.
+    pub commands: Vec<CommandReport>,
+    /// The deployed contracts, unless the warp route hasn't been deployed yet.
+    pub route: Option<RouteSummary>,
+    /// The output of each pre-deploy command, see
+    /// [`DeployOptions::pre_deploy_commands`](crate::DeployOptions::pre_deploy_commands).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_deploy_hooks: Vec<HookReport>,
+    /// The output of each post-deploy command, which only run once every phase has completed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_deploy_hooks: Vec<HookReport>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseReport {
+    pub phase: DeployPhase,
+    pub status: PhaseStatus,
+    pub duration_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PhaseStatus {
+    Completed,
+    /// Completed by an earlier run.
+    Skipped,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookReport {
+    pub name: String,
+    /// Compressed if long, see [`CapturedOutput::new`].
+    pub output: CapturedOutput,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandReport {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+impl DeploymentReport {
+    pub fn record_phase(&mut self, phase: DeployPhase, status: PhaseStatus, duration: Duration) {
+        self.phases.push(PhaseReport {
+            phase,
+            status,
+            duration_ms: millis(duration),
+        });
+    }
+
+    pub fn record_command(&mut self, name: impl Into<String>, duration: Duration) {
+        self.commands.push(CommandReport {
+            name: name.into(),
+            duration_ms: millis(duration),
+        });
+    }
+}
+
+/// The result of each route of a batch, see
+/// [`operate_warp_routes`](crate::operate_warp_routes).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub routes: Vec<RouteResult>,
+}
+
+/// Either the report of a route, or the error it failed with.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteResult {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<DeploymentReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RouteResult {
+    pub fn succeeded(label: String, report: DeploymentReport) -> Self {
+        Self {
+            label,
+            report: Some(report),
+            error: None,
+        }
+    }
+
+    pub fn failed(label: String, error: String) -> Self {
+        Self {
+            label,
+            report: None,
+            error: Some(error),
+        }
+    }
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> usize {
+        self.routes
+            .iter()
+            .filter(|route| route.error.is_none())
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.routes.len() - self.succeeded()
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
@@ -1,6 +1,54 @@
+use crate::cli::HyperlaneCli;
+use async_trait::async_trait;
 use gadget_sdk::executor::process::manager::GadgetProcessManager;
-use std::collections::HashMap;
+use gadget_sdk::executor::process::Error as ProcessError;
+use gadget_sdk::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Error, Debug)]
+pub enum RunnerError {
+    #[error("Process error: {0}")]
+    Process(#[from] ProcessError),
+    #[error("Service `{0}` was already focused to completion")]
+    AlreadyFocused(String),
+    #[error(
+        "Command `{name}` failed with {}: {stderr}",
+        code.map_or("no exit status".to_string(), |code| format!("exit code {code}"))
+    )]
+    CommandFailed {
+        name: String,
+        /// `None` if the command was killed before it could exit.
+        code: Option<i32>,
+        /// What the command printed before failing, e.g. the progress of a partial deployment.
+        stdout: String,
+        stderr: String,
+    },
+    #[error("Cancelled")]
+    Cancelled,
+    #[error(
+        "Command `{name}` printed nothing for {}s, it is likely waiting on a prompt{}",
+        idle.as_secs(),
+        last_line.as_ref().map_or(String::new(), |line| format!(" after `{line}`"))
+    )]
+    UnexpectedPrompt {
+        name: String,
+        idle: Duration,
+        /// The last line the command printed, often the question it is waiting on.
+        last_line: Option<String>,
+    },
+}
 
 /// Function to run multiple commands and focus on the output of each command.
 ///
@@ -45,3 +93,1697 @@ pub async fn run_and_focus_multiple<'a>(
     }
     Ok(outputs)
 }
+
+/// What a command printed and how it exited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub stdout: String,
+    /// `None` if the command was killed before it could exit.
+    pub code: Option<i32>,
+    pub stderr: String,
+    /// Whether the command printed bytes that aren't UTF-8, which were replaced with U+FFFD in
+    /// `stdout` and `stderr`, see [`SANITIZE_UTF8`].
+    pub invalid_utf8: bool,
+    /// Whether `stdout` or `stderr` was cut short at the capture limit, see
+    /// [`ProcessExecutor::with_capture_limit`].
+    pub truncated: bool,
+}
+
+/// A line a command printed to stdout, see [`CommandRunner::run_and_stream_multiple`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputLine {
+    /// The name the command was run under.
+    pub command: String,
+    pub line: String,
+}
+
+/// Runs the shell commands of a [`CommandRunner`].
+///
+/// [`ProcessExecutor`] spawns real processes; tests can substitute an executor that records the
+/// commands and returns canned outputs.
+#[async_trait]
+pub trait CommandExecutor: Debug + Send + Sync {
+    /// Runs `command` with `sh` to completion. A non-zero exit is reported in the output, not
+    /// as an error.
+    ///
+    /// If `cancel` is cancelled while the command runs, the command is killed along with every
+    /// process it started, and [`RunnerError::Cancelled`] is returned.
+    async fn run(
+        &mut self,
+        name: &str,
+        command: &str,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, RunnerError>;
+
+    /// Same as [`CommandExecutor::run`], also sending every line of stdout to `lines`.
+    ///
+    /// By default the lines are only sent once the command has completed; executors that can
+    /// should send each line as soon as it is printed.
+    async fn run_streaming(
+        &mut self,
+        name: &str,
+        command: &str,
+        cancel: &CancellationToken,
+        lines: &UnboundedSender<OutputLine>,
+    ) -> Result<CommandOutput, RunnerError> {
+        let output = self.run(name, command, cancel).await?;
+        send_lines(lines, name, &output.stdout);
+        Ok(output)
+    }
+}
+
+/// Sends each line of `stdout` to `lines`. Lines nobody is listening for anymore are dropped.
+fn send_lines(lines: &UnboundedSender<OutputLine>, name: &str, stdout: &str) {
+    for line in stdout.lines() {
+        let _ = lines.send(OutputLine {
+            command: name.to_string(),
+            line: line.to_string(),
+        });
+    }
+}
+
+/// Runs commands as `sh` processes managed by a [`GadgetProcessManager`].
+///
+/// Processes get no stdin, so a prompt reading one fails rather than waiting forever. One that
+/// waits on the terminal regardless is caught by the prompt timeout, see
+/// [`ProcessExecutor::with_prompt_timeout`].
+#[derive(Debug)]
+pub struct ProcessExecutor {
+    manager: GadgetProcessManager,
+    focused: HashSet<String>,
+    prompt_timeout: Option<Duration>,
+    capture_limit: Option<usize>,
+}
+
+impl ProcessExecutor {
+    pub fn new(manager: GadgetProcessManager) -> Self {
+        Self {
+            manager,
+            focused: HashSet::new(),
+            prompt_timeout: None,
+            capture_limit: None,
+        }
+    }
+
+    /// Kills commands that print nothing for `timeout`, failing with
+    /// [`RunnerError::UnexpectedPrompt`]: a command blocked on a prompt makes no progress.
+    pub fn with_prompt_timeout(mut self, timeout: Duration) -> Self {
+        self.prompt_timeout = Some(timeout);
+        self
+    }
+
+    /// Keeps at most `bytes` of the stdout, and of the stderr, of each command, setting
+    /// [`CommandOutput::truncated`] if there was more. The rest is read and discarded as the
+    /// command prints it, so a command flooding its output neither blocks on a full pipe nor
+    /// grows the memory of the blueprint.
+    pub fn with_capture_limit(mut self, bytes: usize) -> Self {
+        self.capture_limit = Some(bytes);
+        self
+    }
+
+    pub fn manager(&self) -> &GadgetProcessManager {
+        &self.manager
+    }
+
+    async fn spawn(&mut self, name: &str, command: &str) -> Result<String, RunnerError> {
+        let command = with_exit_status(command, self.capture_limit);
+        let service = self.manager.run(name.to_string(), &command).await?;
+        // A fresh process now lives behind this handle, so it may be focused again
+        self.focused.remove(&service);
+        Ok(service)
+    }
+
+    /// Focuses `service` until its output is exhausted.
+    ///
+    /// A handle can only be focused once per spawned process; a second focus is an
+    /// orchestration bug and returns [`RunnerError::AlreadyFocused`] rather than waiting on a
+    /// stream that has already been drained.
+    async fn focus_to_completion(&mut self, service: String) -> Result<String, RunnerError> {
+        if !self.focused.insert(service.clone()) {
+            return Err(RunnerError::AlreadyFocused(service));
+        }
+        Ok(self.manager.focus_service_to_completion(service).await?)
+    }
+
+    /// Kills the process tree of the command running as `service`.
+    async fn kill(&mut self, service: &str, pid: u32) {
+        kill_process_tree(pid).await;
+        self.manager.children.remove(service);
+    }
+
+    /// Kills the process tree of the cancelled command `name`, running as `service`.
+    async fn cancel(&mut self, name: &str, service: &str, pid: u32) -> RunnerError {
+        info!(command = name, pid, "Cancelled, killing the command");
+        self.kill(service, pid).await;
+        RunnerError::Cancelled
+    }
+}
+
+#[async_trait]
+impl CommandExecutor for ProcessExecutor {
+    async fn run(
+        &mut self,
+        name: &str,
+        command: &str,
+        cancel: &CancellationToken,
+    ) -> Result<CommandOutput, RunnerError> {
+        // Only the stream shows whether the command is still making progress
+        if self.prompt_timeout.is_some() {
+            let (lines, _) = tokio::sync::mpsc::unbounded_channel();
+            return self.run_streaming(name, command, cancel, &lines).await;
+        }
+        let service = self.spawn(name, command).await?;
+        let pid = self.manager.children[&service].pid.as_u32();
+        let output = tokio::select! {
+            output = self.focus_to_completion(service.clone()) => output?,
+            () = cancel.cancelled() => return Err(self.cancel(name, &service, pid).await),
+        };
+        Ok(split_exit_status(&decode_manager_output(&output)))
+    }
+
+    /// Reads the process's output stream directly rather than through
+    /// [`GadgetProcessManager::focus_service_to_completion`], which only returns once the
+    /// process has exited.
+    async fn run_streaming(
+        &mut self,
+        name: &str,
+        command: &str,
+        cancel: &CancellationToken,
+        lines: &UnboundedSender<OutputLine>,
+    ) -> Result<CommandOutput, RunnerError> {
+        let service = self.spawn(name, command).await?;
+        if !self.focused.insert(service.clone()) {
+            return Err(RunnerError::AlreadyFocused(service));
+        }
+        let process = self
+            .manager
+            .children
+            .get_mut(&service)
+            .ok_or_else(|| ProcessError::ServiceNotFound(service.clone()))?;
+        let pid = process.pid.as_u32();
+        let mut stream = process
+            .stream
+            .take()
+            .ok_or(ProcessError::StreamError(process.pid))?;
+
+        let prompt_timeout = self.prompt_timeout;
+        let read_stdout = async {
+            let mut stdout: Vec<String> = Vec::new();
+            let mut exited = false;
+            loop {
+                let message = match prompt_timeout {
+                    Some(idle) => match tokio::time::timeout(idle, stream.recv()).await {
+                        Ok(message) => message,
+                        Err(_) => {
+                            return Err(RunnerError::UnexpectedPrompt {
+                                name: name.to_string(),
+                                idle,
+                                last_line: stdout.last().cloned(),
+                            })
+                        }
+                    },
+                    None => stream.recv().await,
+                };
+                match message {
+                    Ok(message) => {
+                        let Some(line) = message.strip_prefix("stdout: ") else {
+                            continue;
+                        };
+                        // The marker and what follows, the exit status and stderr, aren't output
+                        exited |= line.starts_with(EXIT_STATUS_MARKER);
+                        if !exited {
+                            send_lines(lines, name, line);
+                        }
+                        stdout.push(line.to_string());
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        error!(
+                            command = name,
+                            skipped, "Output stream lagged, lines were lost"
+                        );
+                    }
+                    Err(RecvError::Closed) => break Ok(stdout),
+                }
+            }
+        };
+        let stdout = tokio::select! {
+            stdout = read_stdout => stdout,
+            () = cancel.cancelled() => return Err(self.cancel(name, &service, pid).await),
+        };
+        let stdout = match stdout {
+            Ok(stdout) => stdout,
+            Err(e) => {
+                error!(command = name, pid, "{e}, killing the command");
+                self.kill(&service, pid).await;
+                return Err(e);
+            }
+        };
+        Ok(split_exit_status(&stdout.join("\n")))
+    }
+}
+
+/// Sends `SIGTERM` to `pid` and all of its descendants.
+///
+/// Killing only the `sh` the process manager spawned would leave the `hyperlane` process it
+/// started running as an orphan, so the whole tree is walked with `pgrep`, children first.
+async fn kill_process_tree(pid: u32) {
+    let script = format!(
+        "kill_tree() {{ for child in $(pgrep -P \"$1\"); do kill_tree \"$child\"; done; \
+         kill -TERM \"$1\" 2>/dev/null; }}; kill_tree {pid}"
+    );
+    let status = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&script)
+        .status()
+        .await;
+    if let Err(e) = status {
+        error!(pid, "Failed to kill the process tree: {e}");
+    }
+}
+
+/// Runs commands through a [`CommandExecutor`], keeping a record of every command issued.
+///
+/// When `dry_run` is set, no process is ever spawned. Each command is logged and a placeholder
+/// output (see [`dry_run_output`]) is returned in its place, so callers that feed the output of
+/// one command into the next still have something to work with.
+///
+/// `hyperlane` commands should be built with the runner's [`CommandRunner::cli`], so that they
+/// use the configured binary, registry and signing key. Every process is started inside the
+/// working directory, if one is set.
+///
+/// Commands are run by `sh`, so any value taken from a config or a command output must be
+/// passed through [`shell_quote`] before it is interpolated into one. A command that exits with
+/// a non-zero status fails with [`RunnerError::CommandFailed`], and the commands after it are
+/// not run, unless the batch is run under [`ErrorPolicy::ContinueAndCollect`]. Cancellation
+/// (see [`CommandRunner::with_cancellation`]) always stops the batch, failing it with
+/// [`RunnerError::Cancelled`].
+#[derive(Debug)]
+pub struct CommandRunner {
+    executor: Box<dyn CommandExecutor>,
+    cancellation: CancellationToken,
+    cli: HyperlaneCli,
+    work_dir: Option<PathBuf>,
+    dry_run: bool,
+    dry_run_outputs: HashMap<String, String>,
+    history: Vec<(String, String)>,
+    durations: Vec<(String, Duration)>,
+    command_log: Option<CommandLog>,
+}
+
+impl CommandRunner {
+    /// A runner spawning processes through `manager`, see [`ProcessExecutor`].
+    pub fn new(manager: GadgetProcessManager, dry_run: bool) -> Self {
+        Self::with_executor(Box::new(ProcessExecutor::new(manager)), dry_run)
+    }
+
+    pub fn with_executor(executor: Box<dyn CommandExecutor>, dry_run: bool) -> Self {
+        Self {
+            executor,
+            cancellation: CancellationToken::new(),
+            cli: HyperlaneCli::default(),
+            work_dir: None,
+            dry_run,
+            dry_run_outputs: HashMap::new(),
+            history: Vec::new(),
+            durations: Vec::new(),
+            command_log: None,
+        }
+    }
+
+    /// Stops running commands once `token` is cancelled, killing the one running at the time.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Builds the `hyperlane` commands with `cli`, see [`CommandRunner::cli`].
+    pub fn with_cli(mut self, cli: HyperlaneCli) -> Self {
+        self.cli = cli;
+        self
+    }
+
+    /// Starts every process inside `work_dir`, where the CLI keeps its registry and artifacts.
+    pub fn with_work_dir(mut self, work_dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = Some(work_dir.into());
+        self
+    }
+
+    /// Writes a [`CommandRecord`] of every command run to `log`. Dry runs write none.
+    pub fn with_command_log(mut self, log: CommandLog) -> Self {
+        self.command_log = Some(log);
+        self
+    }
+
+    /// Returns `output` instead of the usual placeholder when the command named `name` is run in
+    /// dry-run mode, e.g. to give a `read` command something parseable to return.
+    pub fn with_dry_run_output(
+        mut self,
+        name: impl Into<String>,
+        output: impl Into<String>,
+    ) -> Self {
+        self.dry_run_outputs.insert(name.into(), output.into());
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn work_dir(&self) -> Option<&Path> {
+        self.work_dir.as_deref()
+    }
+
+    /// What the `hyperlane` commands to run are built with.
+    pub fn cli(&self) -> &HyperlaneCli {
+        &self.cli
+    }
+
+    /// The shell command handed to the process manager to run `command` in the working
+    /// directory.
+    pub fn shell_command(&self, command: &str) -> String {
+        match &self.work_dir {
+            Some(work_dir) => format!(
+                "cd {} && {command}",
+                shell_quote(&work_dir.to_string_lossy())
+            ),
+            None => command.to_string(),
+        }
+    }
+
+    /// Every command issued through this runner so far, in order, as `(name, command)` pairs.
+    pub fn history(&self) -> &[(String, String)] {
+        &self.history
+    }
+
+    /// How long each command run through this runner so far took, in order, by name. Commands
+    /// that failed are included, those never run because an earlier one failed are not.
+    pub fn command_durations(&self) -> &[(String, Duration)] {
+        &self.durations
+    }
+
+    /// The [`CommandRunner::command_durations`] added up by command name.
+    pub fn timings(&self) -> CommandTimings {
+        let mut timings = CommandTimings::default();
+        for (name, duration) in &self.durations {
+            timings.record(name, *duration);
+        }
+        timings
+    }
+
+    /// Same as [`run_and_focus_multiple`], but honours dry-run mode and records the commands.
+    pub async fn run_and_focus_multiple<'a>(
+        &mut self,
+        commands: Vec<(&'a str, &'a str)>,
+    ) -> Result<HashMap<String, String>, RunnerError> {
+        self.run_commands(commands, None, ErrorPolicy::AbortOnFirst)
+            .await
+            .map(|batch| batch.outputs)
+    }
+
+    /// Same as [`CommandRunner::run_and_focus_multiple`], with `policy` deciding whether a
+    /// failing command stops the batch, e.g. to go on with the commands of the other chains
+    /// when those of one chain fail.
+    ///
+    /// Cancellation stops the batch whatever the policy.
+    pub async fn run_and_focus_multiple_with_policy<'a>(
+        &mut self,
+        commands: Vec<(&'a str, &'a str)>,
+        policy: ErrorPolicy,
+    ) -> Result<BatchOutputs, RunnerError> {
+        self.run_commands(commands, None, policy).await
+    }
+
+    /// Same as [`CommandRunner::run_and_focus_multiple`], also sending every line the commands
+    /// print to stdout to `lines` as it is printed, e.g. to log the progress of a long
+    /// `core deploy`.
+    ///
+    /// The full outputs are still returned once every command has completed.
+    pub async fn run_and_stream_multiple<'a>(
+        &mut self,
+        commands: Vec<(&'a str, &'a str)>,
+        lines: UnboundedSender<OutputLine>,
+    ) -> Result<HashMap<String, String>, RunnerError> {
+        self.run_commands(commands, Some(&lines), ErrorPolicy::AbortOnFirst)
+            .await
+            .map(|batch| batch.outputs)
+    }
+
+    async fn run_commands<'a>(
+        &mut self,
+        commands: Vec<(&'a str, &'a str)>,
+        lines: Option<&UnboundedSender<OutputLine>>,
+        policy: ErrorPolicy,
+    ) -> Result<BatchOutputs, RunnerError> {
+        self.history.extend(
+            commands
+                .iter()
+                .map(|(name, command)| (name.to_string(), command.to_string())),
+        );
+
+        let mut batch = BatchOutputs::default();
+        for (name, command) in commands {
+            if self.cancellation.is_cancelled() {
+                return Err(RunnerError::Cancelled);
+            }
+            match self.run_command(name, command, lines).await {
+                Ok(stdout) => {
+                    batch.outputs.insert(name.to_string(), stdout);
+                }
+                Err(e @ RunnerError::Cancelled) => return Err(e),
+                Err(e) if policy == ErrorPolicy::ContinueAndCollect => {
+                    warn!(
+                        command = name,
+                        error = %e,
+                        "Command failed, running the rest of the batch"
+                    );
+                    batch.failures.push((name.to_string(), e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Runs a single command of a batch, returning what it printed to stdout.
+    async fn run_command(
+        &mut self,
+        name: &str,
+        command: &str,
+        lines: Option<&UnboundedSender<OutputLine>>,
+    ) -> Result<String, RunnerError> {
+        let started = Instant::now();
+        let started_at = SystemTime::now();
+        let output = if self.dry_run {
+            info!(command = name, "[dry-run] {command}");
+            let stdout = self
+                .dry_run_outputs
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| dry_run_output(command));
+            if let Some(lines) = lines {
+                send_lines(lines, name, &stdout);
+            }
+            Ok(CommandOutput {
+                stdout,
+                code: Some(0),
+                ..CommandOutput::default()
+            })
+        } else {
+            info!(command = name, "Running command");
+            let command = self.shell_command(command);
+            match lines {
+                Some(lines) => {
+                    self.executor
+                        .run_streaming(name, &command, &self.cancellation, lines)
+                        .await
+                }
+                None => self.executor.run(name, &command, &self.cancellation).await,
+            }
+        };
+        let duration = started.elapsed();
+        self.durations.push((name.to_string(), duration));
+        let logged = self.command_log.is_some() && !self.dry_run;
+        let record = logged.then(|| {
+            let exit_code = output.as_ref().ok().and_then(|output| output.code);
+            CommandRecord {
+                name: name.to_string(),
+                command: command.to_string(),
+                started_at: started_at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_millis().try_into().unwrap_or(u64::MAX)),
+                duration_ms: duration.as_millis().try_into().unwrap_or(u64::MAX),
+                exit_code,
+                ok: exit_code == Some(0),
+            }
+        });
+        if let (Some(record), Some(log)) = (record, &mut self.command_log) {
+            // The command log is for ingestion elsewhere and never fails the command
+            if let Err(e) = log.write(&record) {
+                warn!(command = name, error = %e, "Failed to write the command log");
+            }
+        }
+
+        let output = output?;
+        if output.truncated {
+            warn!(
+                command = name,
+                "The command printed more than the capture limit, its output was truncated"
+            );
+        }
+        if output.invalid_utf8 {
+            warn!(
+                command = name,
+                "The command printed invalid UTF-8, which was replaced with U+FFFD"
+            );
+        }
+        if output.code != Some(0) {
+            return Err(RunnerError::CommandFailed {
+                name: name.to_string(),
+                code: output.code,
+                stdout: output.stdout,
+                stderr: output.stderr,
+            });
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Writes a [`CommandRecord`] per command as newline-delimited JSON, e.g. for a log pipeline, see
+/// [`CommandRunner::with_command_log`]. Unlike the `tracing` logs, its format is stable.
+pub struct CommandLog {
+    writer: Box<dyn Write + Send + Sync>,
+}
+
+impl CommandLog {
+    pub fn new(writer: impl Write + Send + Sync + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+        }
+    }
+
+    /// Appends to the file at `path`, creating it if needed, so that several runners can share
+    /// a log.
+    pub fn append_to(path: &Path) -> std::io::Result<Self> {
+        Ok(Self::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        ))
+    }
+
+    fn write(&mut self, record: &CommandRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // A single write, so that lines of runners sharing a file don't interleave
+        self.writer.write_all(&line)?;
+        self.writer.flush()
+    }
+}
+
+impl Debug for CommandLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandLog").finish_non_exhaustive()
+    }
+}
+
+/// A command a [`CommandRunner`] ran, as written to its [`CommandLog`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommandRecord {
+    pub name: String,
+    /// The command, with the signing key masked.
+    pub command: String,
+    /// When the command started, in milliseconds since the Unix epoch.
+    pub started_at: u64,
+    pub duration_ms: u64,
+    /// `None` if the command was killed, or never started, before it could exit.
+    pub exit_code: Option<i32>,
+    pub ok: bool,
+}
+
+/// What [`CommandRunner::run_and_focus_multiple_with_policy`] does when a command of a batch
+/// fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Fail the batch with the error of the first failing command, not running the rest.
+    #[default]
+    AbortOnFirst,
+    /// Run every command, collecting the failures in [`BatchOutputs::failures`].
+    ContinueAndCollect,
+}
+
+/// The results of a batch of commands.
+#[derive(Debug, Default)]
+pub struct BatchOutputs {
+    /// What each command that succeeded printed to stdout, by name.
+    pub outputs: HashMap<String, String>,
+    /// The commands that failed, by name, in the order they were run. Always empty under
+    /// [`ErrorPolicy::AbortOnFirst`].
+    pub failures: Vec<(String, RunnerError)>,
+}
+
+/// The wall-clock time spent running commands, added up by command name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandTimings(BTreeMap<String, Duration>);
+
+impl CommandTimings {
+    /// Adds `duration` to the time spent on `name`.
+    pub fn record(&mut self, name: &str, duration: Duration) {
+        *self.0.entry(name.to_string()).or_default() += duration;
+    }
+
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.0.get(name).copied()
+    }
+
+    /// The time spent on each command, sorted by command name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Duration)> {
+        self.0
+            .iter()
+            .map(|(name, duration)| (name.as_str(), *duration))
+    }
+
+    pub fn total(&self) -> Duration {
+        self.0.values().sum()
+    }
+}
+
+/// Printed after the output of every spawned command, followed by its exit status and then its
+/// stderr, neither of which the process manager reports on its own.
+const EXIT_STATUS_MARKER: &str = "__hyperlane_blueprint_exit_status=";
+
+/// Printed last by [`SANITIZE_UTF8`] if it replaced any invalid UTF-8.
+const INVALID_UTF8_MARKER: &str = "__hyperlane_blueprint_invalid_utf8";
+
+/// Printed on a line of its own where [`with_exit_status`] cut stdout or stderr short.
+const TRUNCATED_MARKER: &str = "__hyperlane_blueprint_truncated";
+
+/// An `awk` program replacing each byte of its input that isn't part of valid UTF-8 with
+/// U+FFFD, line by line, and printing `marker` at the end if there were any.
+///
+/// The process manager reads output as UTF-8 lines and stops reading at the first line that
+/// isn't, after which a command writing more would be killed by `SIGPIPE`. Run under
+/// `LC_ALL=C`, so that `awk` works on bytes.
+const SANITIZE_UTF8: &str = r#"BEGIN {
+    valid = "^([\001-\177]|[\302-\337][\200-\277]|\340[\240-\277][\200-\277]|[\341-\354\356\357][\200-\277][\200-\277]|\355[\200-\237][\200-\277]|\360[\220-\277][\200-\277][\200-\277]|[\361-\363][\200-\277][\200-\277][\200-\277]|\364[\200-\217][\200-\277][\200-\277])+"
+}
+{
+    rest = $0; line = ""
+    while (rest != "") {
+        if (match(rest, valid)) {
+            line = line substr(rest, 1, RLENGTH); rest = substr(rest, RLENGTH + 1)
+        } else {
+            line = line "\357\277\275"; rest = substr(rest, 2); invalid = 1
+        }
+    }
+    print line; fflush()
+}
+END { if (invalid) print marker }"#;
+
+/// An `awk` program passing the first `limit` bytes of its input through, line by line, and
+/// printing `marker` on a line of its own if there were more.
+///
+/// Its input is at most `limit + 1` bytes of output followed by a newline of its own, which is
+/// printed only if nothing was cut, so that the byte past the limit is what tells the two apart.
+/// Run under `LC_ALL=C`, so that lengths are in bytes.
+const CAPTURE_HEAD: &str = r#"{
+    line = $0 "\n"
+    if (n + length(line) <= limit) {
+        printf "%s", line; n += length(line)
+    } else {
+        printf "%s", substr(line, 1, limit - n); over += n + length(line) - limit; n = limit
+    }
+    fflush()
+}
+END { if (over > 1) printf "\n%s\n", marker; else if (over) print "" }"#;
+
+/// Wraps `command` so that its output is followed by [`EXIT_STATUS_MARKER`], its exit status
+/// and its stderr, see [`split_exit_status`]. All of it is passed through [`SANITIZE_UTF8`].
+///
+/// With a `capture_limit`, only that many bytes of stdout, and of stderr, are kept, followed by
+/// [`TRUNCATED_MARKER`] if there were more. Both are cut at `capture_limit + 1` bytes while the
+/// command prints them, the rest being drained to `/dev/null`: stdout on its way to
+/// [`CAPTURE_HEAD`], stderr on its way to a temp file.
+fn with_exit_status(command: &str, capture_limit: Option<usize>) -> String {
+    let Some(limit) = capture_limit else {
+        return format!(
+            "__stderr=$(mktemp); {{ ( {command}\n) </dev/null 2>\"$__stderr\"; __status=$?; \
+             printf '\\n{EXIT_STATUS_MARKER}%s\\n' \"$__status\"; cat \"$__stderr\"; }} \
+             | LC_ALL=C awk -v marker={INVALID_UTF8_MARKER} '{SANITIZE_UTF8}'; rm -f \"$__stderr\""
+        );
+    };
+    let keep = limit + 1;
+    // The exit status is passed through a file, as `$?` after a pipeline is that of its last
+    // command. Stderr is piped through fd 1 while stdout goes to fd 3, so that both are capped
+    // without `sh` having process substitution. `CAPTURE_HEAD` ends stdout with a newline, so
+    // the exit status marker needs none of its own.
+    format!(
+        "__stderr=$(mktemp); __status=$(mktemp); {{ {{ {{ ( {command}\n) </dev/null 2>&1 1>&3 3>&-; \
+         echo $? >\"$__status\"; }} | {{ head -c {keep} >\"$__stderr\"; cat >/dev/null; }}; }} 3>&1 \
+         | {{ head -c {keep}; echo; cat >/dev/null; }} \
+         | LC_ALL=C awk -v limit={limit} -v marker={TRUNCATED_MARKER} '{CAPTURE_HEAD}'; \
+         printf '{EXIT_STATUS_MARKER}%s\\n' \"$(cat \"$__status\")\"; \
+         head -c {limit} \"$__stderr\"; [ \"$(wc -c <\"$__stderr\")\" -le {limit} ] \
+         || printf '\\n{TRUNCATED_MARKER}\\n'; }} \
+         | LC_ALL=C awk -v marker={INVALID_UTF8_MARKER} '{SANITIZE_UTF8}'; \
+         rm -f \"$__stderr\" \"$__status\""
+    )
+}
+
+/// Recovers what a process printed to stdout from the output of
+/// [`GadgetProcessManager::focus_service_to_completion`].
+///
+/// The manager reports each read as a `Debug`-formatted `Vec<String>` of `"stdout: <line>"` and
+/// `"stderr: <line>"` entries, dropping empty lines. Commands wrapped by [`with_exit_status`]
+/// print their stderr to stdout after the marker, so only the stdout entries are kept.
+fn decode_manager_output(output: &str) -> String {
+    let mut lines = Vec::new();
+    let mut chars = output.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut entry = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => entry.push('\n'),
+                    Some('r') => entry.push('\r'),
+                    Some('t') => entry.push('\t'),
+                    Some('0') => entry.push('\0'),
+                    Some('u') => {
+                        let hex: String = chars
+                            .by_ref()
+                            .skip_while(|&c| c == '{')
+                            .take_while(|&c| c != '}')
+                            .collect();
+                        if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                        {
+                            entry.push(c);
+                        }
+                    }
+                    Some(c) => entry.push(c),
+                    None => break,
+                },
+                c => entry.push(c),
+            }
+        }
+        if let Some(line) = entry.strip_prefix("stdout: ") {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Splits the output of a command wrapped by [`with_exit_status`] into its stdout, exit status
+/// and stderr. The exit status is `None` if the marker is missing, i.e. the shell was killed.
+fn split_exit_status(output: &str) -> CommandOutput {
+    let mut lines: Vec<&str> = output.lines().collect();
+    let invalid_utf8 = lines.last() == Some(&INVALID_UTF8_MARKER);
+    if invalid_utf8 {
+        lines.pop();
+    }
+    let line_count = lines.len();
+    lines.retain(|line| *line != TRUNCATED_MARKER);
+    let truncated = lines.len() != line_count;
+    let Some(marker) = lines
+        .iter()
+        .position(|line| line.starts_with(EXIT_STATUS_MARKER))
+    else {
+        return CommandOutput {
+            stdout: lines.join("\n"),
+            invalid_utf8,
+            truncated,
+            ..CommandOutput::default()
+        };
+    };
+
+    let code = lines[marker][EXIT_STATUS_MARKER.len()..]
+        .trim()
+        .parse()
+        .ok();
+    // The marker is printed on a fresh line, leaving an empty one after output ending in a newline
+    let stdout_end = match marker.checked_sub(1) {
+        Some(last) if lines[last].is_empty() => last,
+        _ => marker,
+    };
+    CommandOutput {
+        stdout: lines[..stdout_end].join("\n"),
+        code,
+        stderr: lines[marker + 1..].join("\n"),
+        invalid_utf8,
+        truncated,
+    }
+}
+
+/// A private key the `hyperlane` CLI signs transactions with. Its `Debug` output is redacted.
+#[derive(Clone)]
+pub struct SigningKey(String);
+
+impl SigningKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SigningKey(<redacted>)")
+    }
+}
+
+/// A [`SigningKey`] written to a file only the blueprint's user can read, for signed commands
+/// to read into `HYP_KEY`, see [`HyperlaneCli::with_key_file`]. Unlike an argument, the key
+/// then shows up in neither `ps` nor the logs. The file is removed again when dropped.
+#[derive(Debug)]
+pub struct KeyFile {
+    path: PathBuf,
+}
+
+impl KeyFile {
+    /// Writes `key` to a file in `dir`, created if missing, named after the ID of the process.
+    pub fn new(dir: &Path, key: &SigningKey) -> std::io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let dir = std::path::absolute(dir)?;
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("signing-key-{}", std::process::id()));
+        // Left behind by an earlier process with the same ID, possibly readable by others
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        // Removes the file again if writing it fails
+        let key_file = Self { path };
+        file.write_all(key.expose().as_bytes())?;
+        Ok(key_file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for KeyFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Fee caps, in wei, for the EIP-1559 transactions of [`HyperlaneCli::signed`] commands, e.g. to
+/// keep `core deploy` from stalling on a congested chain. Unset caps are left to the CLI.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GasOverrides {
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+}
+
+/// A config written to a file of its own for a `hyperlane` command to read, removed again when
+/// dropped, whether the command succeeded or not.
+///
+/// File names combine the ID of the process with a counter, so concurrent jobs never share a
+/// file. The path is absolute, so it stays valid inside the runner's working directory.
+#[derive(Debug)]
+pub struct TempConfigFile {
+    path: PathBuf,
+}
+
+impl TempConfigFile {
+    /// Writes `contents` to a new file in `dir`, created if missing, named after `stem`, e.g.
+    /// `warp-route-deployment-<pid>-<n>.yaml` for a `stem` of `warp-route-deployment` and an
+    /// `extension` of `yaml`.
+    pub fn new(dir: &Path, stem: &str, extension: &str, contents: &[u8]) -> std::io::Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let dir = std::path::absolute(dir)?;
+        std::fs::create_dir_all(&dir)?;
+        loop {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let path = dir.join(format!("{stem}-{}-{id}.{extension}", std::process::id()));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // Removes the file again if writing it fails
+                    let config = Self { path };
+                    file.write_all(contents)?;
+                    return Ok(config);
+                }
+                // Left behind by an earlier process with the same ID
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempConfigFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// How long a command may print nothing before it is taken to be waiting on a prompt, see
+/// [`ProcessExecutor::with_prompt_timeout`]. Deployments print progress for every contract, so
+/// this is well above the time a single transaction takes to confirm.
+pub const DEFAULT_PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How many bytes of the stdout, and of the stderr, of a command are kept, see
+/// [`ProcessExecutor::with_capture_limit`]. The outputs the jobs parse, such as that of
+/// `hyperlane warp read`, are far smaller.
+pub const DEFAULT_CAPTURE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The binary [`HyperlaneCli`] runs unless configured otherwise.
+pub const DEFAULT_BINARY: &str = "hyperlane";
+
+/// Quotes `arg` for `sh`, so it is passed as a single word whatever it contains.
+///
+/// Arguments made up only of characters the shell doesn't interpret are left as they are, to
+/// keep the commands in logs readable.
+pub fn shell_quote(arg: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_plain) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// The placeholder output [`CommandRunner`] returns for `command` in dry-run mode.
+pub fn dry_run_output(command: &str) -> String {
+    format!("<dry-run output of `{command}`>")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the commands it is given, returning the canned stdout registered for a command
+    /// name and empty output otherwise.
+    #[derive(Clone, Debug, Default)]
+    pub(crate) struct MockExecutor {
+        pub(crate) commands: Arc<Mutex<Vec<(String, String)>>>,
+        outputs: HashMap<String, CommandOutput>,
+        failing_once: HashMap<String, i32>,
+        cancel_on: Option<(String, CancellationToken)>,
+        config_files: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockExecutor {
+        pub(crate) fn with_output(mut self, name: impl Into<String>, stdout: &str) -> Self {
+            self.outputs.insert(
+                name.into(),
+                CommandOutput {
+                    stdout: stdout.to_string(),
+                    code: Some(0),
+                    ..CommandOutput::default()
+                },
+            );
+            self
+        }
+
+        /// Makes the command named `name` exit with `code`.
+        pub(crate) fn with_failure(mut self, name: impl Into<String>, code: i32) -> Self {
+            self.outputs.insert(
+                name.into(),
+                CommandOutput {
+                    stdout: String::new(),
+                    code: Some(code),
+                    stderr: "mock failure".to_string(),
+                    ..CommandOutput::default()
+                },
+            );
+            self
+        }
+
+        /// Makes the command named `name` print `stdout` and `stderr`, then exit with `code`.
+        pub(crate) fn with_failed_output(
+            mut self,
+            name: impl Into<String>,
+            code: i32,
+            stdout: &str,
+            stderr: &str,
+        ) -> Self {
+            self.outputs.insert(
+                name.into(),
+                CommandOutput {
+                    stdout: stdout.to_string(),
+                    code: Some(code),
+                    stderr: stderr.to_string(),
+                    ..CommandOutput::default()
+                },
+            );
+            self
+        }
+
+        /// Makes the command named `name` exit with `code` the first time it is run only.
+        pub(crate) fn with_failure_once(mut self, name: impl Into<String>, code: i32) -> Self {
+            self.failing_once.insert(name.into(), code);
+            self
+        }
+
+        /// Cancels `token` while the command named `name` runs, as a shutdown would.
+        pub(crate) fn with_cancel_on(
+            mut self,
+            name: impl Into<String>,
+            token: CancellationToken,
+        ) -> Self {
+            self.cancel_on = Some((name.into(), token));
+            self
+        }
+
+        /// The contents of the `--config` files of the commands run, read while they ran.
+        pub(crate) fn config_files(&self) -> Vec<String> {
+            self.config_files.lock().unwrap().clone()
+        }
+
+        pub(crate) fn commands(&self) -> Vec<String> {
+            let commands = self.commands.lock().unwrap();
+            commands
+                .iter()
+                .map(|(_, command)| command.clone())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl CommandExecutor for MockExecutor {
+        async fn run(
+            &mut self,
+            name: &str,
+            command: &str,
+            cancel: &CancellationToken,
+        ) -> Result<CommandOutput, RunnerError> {
+            let mut commands = self.commands.lock().unwrap();
+            commands.push((name.to_string(), command.to_string()));
+            let config = command
+                .split(' ')
+                .skip_while(|word| *word != "--config")
+                .nth(1);
+            if let Some(Ok(contents)) = config.map(std::fs::read_to_string) {
+                self.config_files.lock().unwrap().push(contents);
+            }
+            if let Some((_, token)) = self.cancel_on.as_ref().filter(|(on, _)| on == name) {
+                token.cancel();
+            }
+            if cancel.is_cancelled() {
+                return Err(RunnerError::Cancelled);
+            }
+            if let Some(code) = self.failing_once.remove(name) {
+                return Ok(CommandOutput {
+                    code: Some(code),
+                    stderr: "mock failure".to_string(),
+                    ..CommandOutput::default()
+                });
+            }
+            Ok(self.outputs.get(name).cloned().unwrap_or(CommandOutput {
+                code: Some(0),
+                ..CommandOutput::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_focusing_twice_is_rejected() {
+        let mut executor = ProcessExecutor::new(GadgetProcessManager::new());
+        let service = executor.spawn("echo", "echo 'Hello World'").await.unwrap();
+
+        assert!(executor.focus_to_completion(service.clone()).await.is_ok());
+        assert!(matches!(
+            executor.focus_to_completion(service).await,
+            Err(RunnerError::AlreadyFocused(name)) if name == "echo"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_kills_the_running_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("finished");
+        let command = format!(
+            "sleep 1 && touch {}",
+            shell_quote(&marker.to_string_lossy())
+        );
+
+        let token = CancellationToken::new();
+        let mut runner =
+            CommandRunner::new(GadgetProcessManager::new(), false).with_cancellation(token.clone());
+        let cancel = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            token.cancel();
+        });
+        let result = runner
+            .run_and_focus_multiple(vec![("sleep", command.as_str()), ("next", "true")])
+            .await;
+        cancel.await.unwrap();
+        assert!(matches!(result, Err(RunnerError::Cancelled)));
+
+        // The `sleep` was killed rather than orphaned, so it never gets to `touch`
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+
+    #[tokio::test]
+    async fn test_command_waiting_on_a_prompt_is_killed() {
+        let dir = tempfile::tempdir().unwrap();
+        let fifo = dir.path().join("answers");
+        let mkfifo = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(mkfifo.success());
+        // Nothing ever writes to the FIFO, so reading from it blocks like an unanswered prompt
+        let prompt = format!(
+            "echo '? Deploy to holesky? (y/N)' && read answer < {} && echo answered",
+            shell_quote(&fifo.to_string_lossy())
+        );
+
+        let executor = ProcessExecutor::new(GadgetProcessManager::new())
+            .with_prompt_timeout(Duration::from_millis(500));
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            runner.run_and_focus_multiple(vec![("prompt", prompt.as_str())]),
+        )
+        .await
+        .expect("the prompt was not detected");
+        assert!(matches!(
+            result,
+            Err(RunnerError::UnexpectedPrompt { name, last_line, .. })
+                if name == "prompt"
+                    && last_line.is_some_and(|line| line.contains("Deploy to holesky?"))
+        ));
+
+        // Prompts reading stdin get no answer and fail straight away
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            runner.run_and_focus_multiple(vec![("stdin", "read answer")]),
+        )
+        .await
+        .expect("the prompt was not detected");
+        assert!(matches!(
+            result,
+            Err(RunnerError::CommandFailed { name, .. }) if name == "stdin"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_utf8_output_is_replaced() {
+        let command = r"printf 'ok \342\234\205\nbad \377\n' && printf 'err \376' >&2 && exit 3";
+        for executor in [
+            ProcessExecutor::new(GadgetProcessManager::new()),
+            ProcessExecutor::new(GadgetProcessManager::new())
+                .with_prompt_timeout(Duration::from_secs(10)),
+        ] {
+            let mut runner = CommandRunner::with_executor(Box::new(executor), false);
+            let result = runner
+                .run_and_focus_multiple(vec![("binary", command)])
+                .await;
+            assert!(matches!(
+                result,
+                Err(RunnerError::CommandFailed { name, code: Some(3), stdout, stderr })
+                    if name == "binary"
+                        && stdout == "ok \u{2705}\nbad \u{fffd}"
+                        && stderr == "err \u{fffd}"
+            ));
+        }
+
+        let mut executor = ProcessExecutor::new(GadgetProcessManager::new());
+        let cancel = CancellationToken::new();
+        let output = executor
+            .run("binary", r"printf '\377'", &cancel)
+            .await
+            .unwrap();
+        assert!(output.invalid_utf8);
+        let output = executor
+            .run("valid", r"printf '\342\234\205'", &cancel)
+            .await
+            .unwrap();
+        assert_eq!(output.stdout, "\u{2705}");
+        assert!(!output.invalid_utf8);
+    }
+
+    #[tokio::test]
+    async fn test_output_beyond_the_capture_limit_is_truncated() {
+        let command = "yes hyperlane | head -c 10000000; yes error | head -c 10000000 >&2";
+        let cancel = CancellationToken::new();
+        for mut executor in [
+            ProcessExecutor::new(GadgetProcessManager::new()).with_capture_limit(1024),
+            ProcessExecutor::new(GadgetProcessManager::new())
+                .with_capture_limit(1024)
+                .with_prompt_timeout(Duration::from_secs(10)),
+        ] {
+            let output = executor.run("flood", command, &cancel).await.unwrap();
+            assert_eq!(output.code, Some(0));
+            assert!(output.truncated);
+            assert_eq!(output.stdout.len(), 1024);
+            assert!(output.stdout.starts_with("hyperlane\nhyperlane\n"));
+            assert_eq!(output.stderr.len(), 1024);
+            assert!(output.stderr.starts_with("error\nerror\n"));
+
+            let output = executor.run("small", "echo ok", &cancel).await.unwrap();
+            assert_eq!(output.stdout, "ok");
+            assert!(!output.truncated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_just_over_the_capture_limit_is_truncated() {
+        let cancel = CancellationToken::new();
+        let mut executor =
+            ProcessExecutor::new(GadgetProcessManager::new()).with_capture_limit(1024);
+        // How many bytes are printed to stdout and stderr, whether stdout then ends in a newline,
+        // and whether that is more than the limit
+        let cases = [
+            (1025, false, true),
+            (1024, true, true),
+            (1024, false, false),
+            (1023, true, false),
+        ];
+        for (i, (bytes, newline, truncated)) in cases.into_iter().enumerate() {
+            let command = format!(
+                "head -c {bytes} /dev/zero | tr '\\0' a; {} head -c {bytes} /dev/zero | tr '\\0' e >&2",
+                if newline { "echo;" } else { "" }
+            );
+            let output = executor
+                .run(&format!("case {i}"), &command, &cancel)
+                .await
+                .unwrap();
+            assert_eq!(output.truncated, truncated, "case {i}");
+            assert_eq!(output.stdout, "a".repeat(bytes.min(1024)), "case {i}");
+            assert_eq!(output.stderr, "e".repeat(bytes.min(1024)), "case {i}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_lines_are_streamed_before_completion() {
+        let (lines, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false);
+        let run = tokio::spawn(async move {
+            runner
+                .run_and_stream_multiple(
+                    vec![("count", "echo one; echo two; sleep 1; echo three")],
+                    lines,
+                )
+                .await
+        });
+
+        for expected in ["one", "two"] {
+            let line = tokio::time::timeout(Duration::from_millis(800), received.recv())
+                .await
+                .expect("line not streamed before the command completed")
+                .unwrap();
+            assert_eq!(
+                line,
+                OutputLine {
+                    command: "count".to_string(),
+                    line: expected.to_string(),
+                }
+            );
+        }
+        assert!(!run.is_finished());
+
+        let outputs = run.await.unwrap().unwrap();
+        assert_eq!(outputs["count"], "one\ntwo\nthree");
+        assert_eq!(received.recv().await.unwrap().line, "three");
+        assert!(received.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_durations_are_recorded() {
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false);
+        runner
+            .run_and_focus_multiple(vec![("sleep", "sleep 0.1"), ("echo", "echo done")])
+            .await
+            .unwrap();
+        runner
+            .run_and_focus_multiple(vec![("sleep", "sleep 0.1")])
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = runner
+            .command_durations()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["sleep", "echo", "sleep"]);
+
+        let timings = runner.timings();
+        assert!(timings.get("sleep").unwrap() >= Duration::from_millis(200));
+        assert!(timings.get("echo").is_some());
+        assert_eq!(timings.get("missing"), None);
+        assert_eq!(
+            timings.total(),
+            timings.iter().map(|(_, duration)| duration).sum()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_executor_failures_fail_the_runner() {
+        let executor = MockExecutor::default().with_failure("deploy", 3);
+        let mut runner = CommandRunner::with_executor(Box::new(executor.clone()), false);
+        let result = runner
+            .run_and_focus_multiple(vec![("deploy", "hyperlane core deploy"), ("next", "true")])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(RunnerError::CommandFailed { code: Some(3), .. })
+        ));
+        assert_eq!(executor.commands(), ["hyperlane core deploy"]);
+    }
+
+    /// A writer whose output stays readable after it was handed over.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_log_has_a_json_line_per_command() {
+        let buffer = SharedBuffer::default();
+        let executor = MockExecutor::default()
+            .with_output("read", "config")
+            .with_failure("apply", 2);
+        let mut runner = CommandRunner::with_executor(Box::new(executor), false)
+            .with_command_log(CommandLog::new(buffer.clone()));
+        runner
+            .run_and_focus_multiple_with_policy(
+                vec![
+                    ("read", "hyperlane warp read"),
+                    ("apply", "hyperlane warp apply"),
+                ],
+                ErrorPolicy::ContinueAndCollect,
+            )
+            .await
+            .unwrap();
+
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        for record in &records {
+            let mut fields: Vec<&str> = record
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            fields.sort_unstable();
+            assert_eq!(
+                fields,
+                [
+                    "command",
+                    "duration_ms",
+                    "exit_code",
+                    "name",
+                    "ok",
+                    "started_at"
+                ]
+            );
+        }
+        let records: Vec<CommandRecord> = records
+            .into_iter()
+            .map(|record| serde_json::from_value(record).unwrap())
+            .collect();
+        assert_eq!(
+            (
+                records[0].name.as_str(),
+                records[0].exit_code,
+                records[0].ok
+            ),
+            ("read", Some(0), true)
+        );
+        assert_eq!(records[0].command, "hyperlane warp read");
+        assert_eq!(
+            (
+                records[1].name.as_str(),
+                records[1].exit_code,
+                records[1].ok
+            ),
+            ("apply", Some(2), false)
+        );
+        assert!(records[1].started_at >= records[0].started_at);
+
+        // Dry runs don't run anything to log
+        let buffer = SharedBuffer::default();
+        let mut runner = CommandRunner::with_executor(Box::new(MockExecutor::default()), true)
+            .with_command_log(CommandLog::new(buffer.clone()));
+        runner
+            .run_and_focus_multiple(vec![("read", "hyperlane warp read")])
+            .await
+            .unwrap();
+        assert!(buffer.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_error_policy_decides_whether_a_failure_stops_the_batch() {
+        let commands = vec![
+            ("holesky", "hyperlane warp apply --chain holesky"),
+            ("sepolia", "hyperlane warp apply --chain sepolia"),
+            (
+                "tangletestnet",
+                "hyperlane warp apply --chain tangletestnet",
+            ),
+        ];
+        let executor = || {
+            MockExecutor::default()
+                .with_output("holesky", "applied holesky")
+                .with_failure("sepolia", 1)
+                .with_output("tangletestnet", "applied tangletestnet")
+        };
+
+        let aborting = executor();
+        let mut runner = CommandRunner::with_executor(Box::new(aborting.clone()), false);
+        let result = runner
+            .run_and_focus_multiple_with_policy(commands.clone(), ErrorPolicy::AbortOnFirst)
+            .await;
+        assert!(matches!(
+            result,
+            Err(RunnerError::CommandFailed { name, .. }) if name == "sepolia"
+        ));
+        assert_eq!(aborting.commands().len(), 2);
+
+        let collecting = executor();
+        let mut runner = CommandRunner::with_executor(Box::new(collecting.clone()), false);
+        let batch = runner
+            .run_and_focus_multiple_with_policy(commands, ErrorPolicy::ContinueAndCollect)
+            .await
+            .unwrap();
+        assert_eq!(collecting.commands().len(), 3);
+        assert_eq!(
+            batch.outputs,
+            HashMap::from([
+                ("holesky".to_string(), "applied holesky".to_string()),
+                (
+                    "tangletestnet".to_string(),
+                    "applied tangletestnet".to_string()
+                ),
+            ])
+        );
+        assert!(matches!(
+            batch.failures.as_slice(),
+            [(name, RunnerError::CommandFailed { code: Some(1), .. })] if name == "sepolia"
+        ));
+    }
+
+    #[test]
+    fn test_commands_run_in_work_dir() {
+        let runner = CommandRunner::new(GadgetProcessManager::new(), true);
+        assert_eq!(runner.shell_command("ls"), "ls");
+
+        let runner = runner.with_work_dir("/srv/service 7/it's");
+        assert_eq!(
+            runner.shell_command("ls"),
+            r"cd '/srv/service 7/it'\''s' && ls"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_reaches_the_cli_through_its_environment() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("hyperlane");
+        std::fs::write(&binary, "#!/bin/sh\necho \"key=$HYP_KEY args=$*\"\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let key_file = KeyFile::new(dir.path(), &SigningKey::new(key)).unwrap();
+        let mode = std::fs::metadata(key_file.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let cli = HyperlaneCli::default()
+            .with_binary(binary.to_string_lossy())
+            .with_key_file(key_file.path());
+        let command = cli.signed(&["warp", "deploy"]).to_string();
+        assert!(!command.contains(key), "{command}");
+        let log = SharedBuffer::default();
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false)
+            .with_cli(cli)
+            .with_command_log(CommandLog::new(log.clone()));
+        let outputs = runner
+            .run_and_focus_multiple(vec![("run warp deploy", command.as_str())])
+            .await
+            .unwrap();
+        assert_eq!(
+            outputs["run warp deploy"].trim(),
+            format!("key={key} args=warp deploy --yes")
+        );
+        assert!(!String::from_utf8(log.0.lock().unwrap().clone())
+            .unwrap()
+            .contains(key));
+        assert!(!format!("{runner:?}").contains(key));
+
+        let path = key_file.path().to_path_buf();
+        drop(key_file);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_quoted_arguments_reach_the_process_intact() {
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false);
+        let arguments = [
+            "holesky",
+            "it's",
+            r#"owner: "0x11" # '$(touch pwned)'; `id` | cat && exit 1"#,
+            "",
+        ];
+        for (i, argument) in arguments.iter().enumerate() {
+            let name = format!("printf {i}");
+            let command = format!("printf '[%s]' {}", shell_quote(argument));
+            let outputs = runner
+                .run_and_focus_multiple(vec![(name.as_str(), command.as_str())])
+                .await
+                .unwrap();
+            assert!(
+                outputs[&name].contains(&format!("[{argument}]")),
+                "{command}"
+            );
+        }
+        assert_eq!(shell_quote("holesky"), "holesky");
+    }
+
+    #[tokio::test]
+    async fn test_non_zero_exit_fails() {
+        let mut runner = CommandRunner::new(GadgetProcessManager::new(), false);
+        let commands = vec![
+            (
+                "fail",
+                "echo partial output; echo 'no such chain' >&2; exit 1",
+            ),
+            ("after", "echo unreachable"),
+        ];
+        match runner.run_and_focus_multiple(commands).await {
+            Err(RunnerError::CommandFailed {
+                name,
+                code,
+                stdout,
+                stderr,
+            }) => {
+                assert_eq!(name, "fail");
+                assert_eq!(code, Some(1));
+                assert_eq!(stdout, "partial output");
+                assert!(stderr.contains("no such chain"), "{stderr}");
+            }
+            other => panic!("expected CommandFailed, got {other:?}"),
+        }
+
+        let outputs = runner
+            .run_and_focus_multiple(vec![("ok", "echo done; echo warning >&2")])
+            .await
+            .unwrap();
+        assert_eq!(outputs["ok"].trim(), "done");
+    }
+
+    #[test]
+    fn test_decode_manager_output() {
+        let output = concat!(
+            r#"["stdout: plain", "stdout: say \"hi\" \\ done", "stderr: warning"]"#,
+            "\n",
+            r#"["stdout: tab\tand \u{1b}[0m color"]"#,
+            "\n[]\n",
+        );
+        assert_eq!(
+            decode_manager_output(output),
+            "plain\nsay \"hi\" \\ done\ntab\tand \u{1b}[0m color"
+        );
+        assert_eq!(decode_manager_output("[]\n"), "");
+    }
+
+    #[test]
+    fn test_split_exit_status() {
+        let output = format!("line 1\nline 2\n\n{EXIT_STATUS_MARKER}2\nerror: boom");
+        assert_eq!(
+            split_exit_status(&output),
+            CommandOutput {
+                stdout: "line 1\nline 2".to_string(),
+                code: Some(2),
+                stderr: "error: boom".to_string(),
+                invalid_utf8: false,
+                truncated: false,
+            }
+        );
+        assert_eq!(
+            split_exit_status("killed midway"),
+            CommandOutput {
+                stdout: "killed midway".to_string(),
+                ..CommandOutput::default()
+            }
+        );
+        let output = format!("bad \u{fffd}\n{EXIT_STATUS_MARKER}0\n{INVALID_UTF8_MARKER}");
+        assert_eq!(
+            split_exit_status(&output),
+            CommandOutput {
+                stdout: "bad \u{fffd}".to_string(),
+                code: Some(0),
+                invalid_utf8: true,
+                ..CommandOutput::default()
+            }
+        );
+        let output = format!(
+            "line 1\nli\n{TRUNCATED_MARKER}\n\n{EXIT_STATUS_MARKER}0\nerr\n{TRUNCATED_MARKER}"
+        );
+        assert_eq!(
+            split_exit_status(&output),
+            CommandOutput {
+                stdout: "line 1\nli".to_string(),
+                code: Some(0),
+                stderr: "err".to_string(),
+                truncated: true,
+                ..CommandOutput::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_processes_run_in_work_dir() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let mut runner =
+            CommandRunner::new(GadgetProcessManager::new(), false).with_work_dir(work_dir.path());
+
+        let outputs = runner
+            .run_and_focus_multiple(vec![("pwd", "pwd")])
+            .await
+            .unwrap();
+        assert!(outputs["pwd"].contains(&*work_dir.path().to_string_lossy()));
+        // The history records the command as issued, without the directory change
+        assert_eq!(runner.history(), [("pwd".to_string(), "pwd".to_string())]);
+    }
+
+    #[test]
+    fn test_temp_config_file_is_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = TempConfigFile::new(dir.path(), "warp", "yaml", b"tokens: []").unwrap();
+        let second = TempConfigFile::new(dir.path(), "warp", "yaml", b"tokens: []").unwrap();
+
+        assert_ne!(first.path(), second.path());
+        assert!(first.path().is_absolute());
+        assert_eq!(std::fs::read(first.path()).unwrap(), b"tokens: []");
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().exists());
+    }
+}
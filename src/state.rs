@@ -0,0 +1,559 @@
+use crate::hyperlane::output::DeployedRoute;
+use alloy_primitives::B256;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::OwnedMutexGuard;
+
+/// The phases of a warp route deployment.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum DeployPhase {
+    /// `hyperlane registry init`
+    RegistryInit,
+    /// `hyperlane core init` and `hyperlane core deploy`
+    CoreDeploy,
+    /// `hyperlane warp deploy`
+    WarpDeploy,
+    /// `hyperlane core read` and `hyperlane core apply` on every chain of the route
+    CoreApply,
+    /// `hyperlane warp read` and `hyperlane warp apply` on every chain of the route
+    WarpApply,
+}
+
+impl DeployPhase {
+    /// The order the job runs its phases in unless told otherwise.
+    pub fn default_order() -> Vec<DeployPhase> {
+        vec![
+            DeployPhase::RegistryInit,
+            DeployPhase::CoreDeploy,
+            DeployPhase::WarpDeploy,
+            DeployPhase::CoreApply,
+        ]
+    }
+
+    /// The phase that has to run before this one, if both are part of a deployment.
+    fn depends_on(self) -> Option<DeployPhase> {
+        match self {
+            DeployPhase::RegistryInit => None,
+            DeployPhase::CoreDeploy | DeployPhase::WarpDeploy => Some(DeployPhase::RegistryInit),
+            DeployPhase::CoreApply => Some(DeployPhase::CoreDeploy),
+            DeployPhase::WarpApply => Some(DeployPhase::WarpDeploy),
+        }
+    }
+}
+
+impl FromStr for DeployPhase {
+    type Err = PhaseOrderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "registryInit" => Ok(DeployPhase::RegistryInit),
+            "coreDeploy" => Ok(DeployPhase::CoreDeploy),
+            "warpDeploy" => Ok(DeployPhase::WarpDeploy),
+            "coreApply" => Ok(DeployPhase::CoreApply),
+            "warpApply" => Ok(DeployPhase::WarpApply),
+            _ => Err(PhaseOrderError::UnknownPhase(s.to_string())),
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PhaseOrderError {
+    #[error("Unknown deploy phase `{0}`")]
+    UnknownPhase(String),
+    #[error("{0:?} appears more than once")]
+    Duplicate(DeployPhase),
+    #[error("{phase:?} has to run after {requires:?}")]
+    OutOfOrder {
+        phase: DeployPhase,
+        requires: DeployPhase,
+    },
+    #[error("Every phase requires RegistryInit to run first")]
+    MissingRegistryInit,
+}
+
+/// Checks that `order` is a sequence of phases the job can actually run.
+///
+/// Phases may be left out or reordered, as long as:
+/// * no phase appears twice,
+/// * [`DeployPhase::RegistryInit`] comes before every other phase, and
+/// * an apply phase comes after the matching deploy phase, if both are present.
+pub fn validate_phase_order(order: &[DeployPhase]) -> Result<(), PhaseOrderError> {
+    for (i, &phase) in order.iter().enumerate() {
+        let earlier = &order[..i];
+        if earlier.contains(&phase) {
+            return Err(PhaseOrderError::Duplicate(phase));
+        }
+
+        if phase != DeployPhase::RegistryInit && !earlier.contains(&DeployPhase::RegistryInit) {
+            return Err(PhaseOrderError::MissingRegistryInit);
+        }
+
+        if let Some(requires) = phase.depends_on() {
+            if order.contains(&requires) && !earlier.contains(&requires) {
+                return Err(PhaseOrderError::OutOfOrder { phase, requires });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Records which [`DeployPhase`]s of a deployment completed, so that a restarted job can pick
+/// up where the previous one stopped instead of redeploying contracts.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DeploymentState {
+    #[serde(rename = "completedPhases")]
+    completed_phases: Vec<DeployPhase>,
+    #[serde(
+        rename = "deployedRoute",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    deployed_route: Option<DeployedRoute>,
+    #[serde(
+        rename = "deployedConfigHash",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    deployed_config_hash: Option<B256>,
+}
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl DeploymentState {
+    /// The state file for `service_id` inside `base_dir`.
+    pub fn path_for_service(base_dir: &Path, service_id: u64) -> PathBuf {
+        base_dir.join(format!("deployment-state-{service_id}.json"))
+    }
+
+    /// Loads the state stored at `path`, or an empty state if nothing has been stored yet.
+    pub async fn load(path: &Path) -> Result<Self, StateError> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stores the state at `path`, replacing the state stored there in one step, see
+    /// [`write_atomically`].
+    pub async fn save(&self, path: &Path) -> Result<(), StateError> {
+        write_atomically(path, &serde_json::to_vec_pretty(self)?).await
+    }
+
+    pub fn is_phase_complete(&self, phase: DeployPhase) -> bool {
+        self.completed_phases.contains(&phase)
+    }
+
+    /// The contracts deployed by [`DeployPhase::WarpDeploy`], once it has run.
+    pub fn deployed_route(&self) -> Option<&DeployedRoute> {
+        self.deployed_route.as_ref()
+    }
+
+    pub fn set_deployed_route(&mut self, route: DeployedRoute) {
+        self.deployed_route = Some(route);
+    }
+
+    /// The [`WarpRouteConfig::content_hash`](crate::hyperlane::WarpRouteConfig::content_hash)
+    /// of the config [`DeployPhase::WarpDeploy`] last deployed. `None` for states saved before
+    /// hashes were recorded.
+    pub fn deployed_config_hash(&self) -> Option<B256> {
+        self.deployed_config_hash
+    }
+
+    pub fn set_deployed_config_hash(&mut self, hash: B256) {
+        self.deployed_config_hash = Some(hash);
+    }
+
+    pub fn mark_phase_complete(&mut self, phase: DeployPhase) {
+        if !self.is_phase_complete(phase) {
+            self.completed_phases.push(phase);
+        }
+    }
+
+    /// Marks `phase` as not yet complete, so that it runs again.
+    pub fn reset_phase(&mut self, phase: DeployPhase) {
+        self.completed_phases
+            .retain(|&completed| completed != phase);
+    }
+}
+
+/// Where [`DeploymentState`]s are persisted between job runs, keyed by service id.
+///
+/// [`FileStateStore`] keeps them on local disk; operators running on ephemeral infrastructure
+/// can implement this over a database to resume deployments after a restart.
+#[async_trait]
+pub trait StateStore: Debug + Send + Sync {
+    /// Loads the state of `service_id`, or an empty state if nothing has been stored yet.
+    async fn load(&self, service_id: u64) -> Result<DeploymentState, StateError>;
+
+    async fn save(&self, service_id: u64, state: &DeploymentState) -> Result<(), StateError>;
+}
+
+/// Stores each service's state in a JSON file inside `base_dir`, see
+/// [`DeploymentState::path_for_service`].
+#[derive(Clone, Debug)]
+pub struct FileStateStore {
+    base_dir: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl StateStore for FileStateStore {
+    async fn load(&self, service_id: u64) -> Result<DeploymentState, StateError> {
+        DeploymentState::load(&DeploymentState::path_for_service(
+            &self.base_dir,
+            service_id,
+        ))
+        .await
+    }
+
+    async fn save(&self, service_id: u64, state: &DeploymentState) -> Result<(), StateError> {
+        state
+            .save(&DeploymentState::path_for_service(
+                &self.base_dir,
+                service_id,
+            ))
+            .await
+    }
+}
+
+/// Keeps states in memory, for tests and dry runs.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    states: Mutex<HashMap<u64, DeploymentState>>,
+}
+
+#[async_trait]
+impl StateStore for MemoryStateStore {
+    async fn load(&self, service_id: u64) -> Result<DeploymentState, StateError> {
+        let states = self.states.lock().unwrap();
+        Ok(states.get(&service_id).cloned().unwrap_or_default())
+    }
+
+    async fn save(&self, service_id: u64, state: &DeploymentState) -> Result<(), StateError> {
+        let mut states = self.states.lock().unwrap();
+        states.insert(service_id, state.clone());
+        Ok(())
+    }
+}
+
+/// Identifies a `JobCalled` event: call `call_id` of service `service_id`. The event listener
+/// may deliver the same call more than once, e.g. after reconnecting.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct CallKey {
+    pub service_id: u64,
+    pub call_id: u64,
+}
+
+/// Remembers the result of every job call that completed, so that a call delivered again isn't
+/// run again, see [`run_once`](crate::run_once).
+#[async_trait]
+pub trait CallStore: Debug + Send + Sync {
+    /// The result the call completed with, or `None` if it hasn't completed.
+    async fn completed(&self, key: CallKey) -> Result<Option<Vec<u8>>, StateError>;
+
+    async fn record(&self, key: CallKey, result: &[u8]) -> Result<(), StateError>;
+}
+
+/// Stores the result of each call in a file inside `base_dir`, so that completed calls are
+/// remembered across restarts.
+#[derive(Clone, Debug)]
+pub struct FileCallStore {
+    base_dir: PathBuf,
+}
+
+impl FileCallStore {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    fn path(&self, key: CallKey) -> PathBuf {
+        self.base_dir
+            .join(format!("call-{}-{}.result", key.service_id, key.call_id))
+    }
+}
+
+#[async_trait]
+impl CallStore for FileCallStore {
+    async fn completed(&self, key: CallKey) -> Result<Option<Vec<u8>>, StateError> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(result) => Ok(Some(result)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn record(&self, key: CallKey, result: &[u8]) -> Result<(), StateError> {
+        write_atomically(&self.path(key), result).await
+    }
+}
+
+/// Writes `contents` to `path`, creating its directory if needed. The file is written in full
+/// next to `path` and then renamed over it, so that a crash never leaves a partial file behind.
+async fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), StateError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension("partial");
+    tokio::fs::write(&partial, contents).await?;
+    tokio::fs::rename(partial, path).await?;
+    Ok(())
+}
+
+/// Keeps call results in memory, for tests.
+#[derive(Debug, Default)]
+pub struct MemoryCallStore {
+    results: Mutex<HashMap<CallKey, Vec<u8>>>,
+}
+
+#[async_trait]
+impl CallStore for MemoryCallStore {
+    async fn completed(&self, key: CallKey) -> Result<Option<Vec<u8>>, StateError> {
+        Ok(self.results.lock().unwrap().get(&key).cloned())
+    }
+
+    async fn record(&self, key: CallKey, result: &[u8]) -> Result<(), StateError> {
+        self.results.lock().unwrap().insert(key, result.to_vec());
+        Ok(())
+    }
+}
+
+/// The ids of the calls [`pre_process_call`](crate::pre_process_call) let through that their
+/// job hasn't picked up yet, by job id, see
+/// [`HyperlaneContext::run_call`](crate::HyperlaneContext::run_call).
+///
+/// The event listener runs the calls of a job one at a time, so each job picks up its calls in
+/// the order they were queued.
+#[derive(Debug, Default)]
+pub struct PendingCalls {
+    calls: Mutex<HashMap<u8, VecDeque<u64>>>,
+}
+
+impl PendingCalls {
+    pub fn push(&self, job_id: u8, call_id: u64) {
+        let mut calls = self.calls.lock().unwrap();
+        calls.entry(job_id).or_default().push_back(call_id);
+    }
+
+    /// The id of the oldest call of `job_id` not picked up yet, if any.
+    pub fn take(&self, job_id: u8) -> Option<u64> {
+        let mut calls = self.calls.lock().unwrap();
+        calls.get_mut(&job_id)?.pop_front()
+    }
+}
+
+/// Per-service locks, so that jobs of one service never run concurrently against its registry
+/// and state.
+#[derive(Debug, Default)]
+pub struct DeploymentLocks {
+    locks: Mutex<HashMap<u64, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl DeploymentLocks {
+    /// Waits until no other job of `service_id` holds the lock, then holds it until the returned
+    /// guard is dropped, including when the job fails.
+    pub async fn lock(&self, service_id: u64) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            Arc::clone(locks.entry(service_id).or_default())
+        };
+        lock.lock_owned().await
+    }
+
+    /// Waits until no job holds a lock, i.e. every running job has finished.
+    pub async fn wait_idle(&self) {
+        let locks: Vec<_> = self.locks.lock().unwrap().values().cloned().collect();
+        for lock in locks {
+            drop(lock.lock().await);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_missing_state_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = DeploymentState::path_for_service(dir.path(), 7);
+        let state = DeploymentState::load(&path).await.unwrap();
+        assert_eq!(state, DeploymentState::default());
+        assert!(!state.is_phase_complete(DeployPhase::CoreDeploy));
+    }
+
+    #[tokio::test]
+    async fn test_call_results_survive_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = CallKey {
+            service_id: 7,
+            call_id: 3,
+        };
+        let store = FileCallStore::new(dir.path());
+        assert_eq!(store.completed(key).await.unwrap(), None);
+        store.record(key, b"{}").await.unwrap();
+
+        let restarted = FileCallStore::new(dir.path());
+        assert_eq!(
+            restarted.completed(key).await.unwrap(),
+            Some(b"{}".to_vec())
+        );
+        let other = CallKey { call_id: 4, ..key };
+        assert_eq!(restarted.completed(other).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = DeploymentState::path_for_service(dir.path(), 7);
+
+        let mut state = DeploymentState::default();
+        state.mark_phase_complete(DeployPhase::CoreDeploy);
+        state.mark_phase_complete(DeployPhase::CoreDeploy);
+        state.set_deployed_config_hash(B256::repeat_byte(0x11));
+        state.save(&path).await.unwrap();
+
+        let loaded = DeploymentState::load(&path).await.unwrap();
+        assert_eq!(loaded, state);
+        assert!(loaded.is_phase_complete(DeployPhase::CoreDeploy));
+        assert!(!loaded.is_phase_complete(DeployPhase::WarpDeploy));
+
+        // Saving again replaces the file, leaving nothing else behind
+        state.mark_phase_complete(DeployPhase::WarpDeploy);
+        state.save(&path).await.unwrap();
+        assert_eq!(DeploymentState::load(&path).await.unwrap(), state);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_state_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStateStore::new(dir.path());
+        assert_eq!(store.load(3).await.unwrap(), DeploymentState::default());
+
+        let mut state = DeploymentState::default();
+        state.mark_phase_complete(DeployPhase::RegistryInit);
+        store.save(3, &state).await.unwrap();
+        assert_eq!(store.load(3).await.unwrap(), state);
+        assert_eq!(
+            DeploymentState::load(&DeploymentState::path_for_service(dir.path(), 3))
+                .await
+                .unwrap(),
+            state
+        );
+        assert_eq!(store.load(4).await.unwrap(), DeploymentState::default());
+    }
+
+    #[test]
+    fn test_default_phase_order_is_valid() {
+        assert_eq!(validate_phase_order(&DeployPhase::default_order()), Ok(()));
+    }
+
+    #[test]
+    fn test_reordered_phases_are_valid() {
+        let warp_first = [
+            DeployPhase::RegistryInit,
+            DeployPhase::WarpDeploy,
+            DeployPhase::WarpApply,
+            DeployPhase::CoreDeploy,
+            DeployPhase::CoreApply,
+        ];
+        assert_eq!(validate_phase_order(&warp_first), Ok(()));
+
+        // Reusing existing cores: nothing is deployed, only applied
+        let reuse_cores = [DeployPhase::RegistryInit, DeployPhase::CoreApply];
+        assert_eq!(validate_phase_order(&reuse_cores), Ok(()));
+    }
+
+    #[test]
+    fn test_illegal_phase_orders_are_rejected() {
+        assert_eq!(
+            validate_phase_order(&[DeployPhase::WarpDeploy, DeployPhase::RegistryInit]),
+            Err(PhaseOrderError::MissingRegistryInit)
+        );
+        assert_eq!(
+            validate_phase_order(&[
+                DeployPhase::RegistryInit,
+                DeployPhase::CoreApply,
+                DeployPhase::CoreDeploy,
+            ]),
+            Err(PhaseOrderError::OutOfOrder {
+                phase: DeployPhase::CoreApply,
+                requires: DeployPhase::CoreDeploy,
+            })
+        );
+        assert_eq!(
+            validate_phase_order(&[DeployPhase::RegistryInit, DeployPhase::RegistryInit]),
+            Err(PhaseOrderError::Duplicate(DeployPhase::RegistryInit))
+        );
+    }
+
+    #[test]
+    fn test_deploy_phase_from_str() {
+        assert_eq!("warpApply".parse(), Ok(DeployPhase::WarpApply));
+        assert_eq!(
+            "warpInit".parse::<DeployPhase>(),
+            Err(PhaseOrderError::UnknownPhase("warpInit".to_string()))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_deployment_locks_serialize_jobs_of_a_service() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let locks = Arc::new(DeploymentLocks::default());
+        let inside = Arc::new(AtomicUsize::new(0));
+        let overlapped = Arc::new(AtomicBool::new(false));
+        let job = |fail: bool| {
+            let (locks, inside, overlapped) = (locks.clone(), inside.clone(), overlapped.clone());
+            tokio::spawn(async move {
+                let _guard = locks.lock(1).await;
+                if inside.fetch_add(1, Ordering::SeqCst) > 0 {
+                    overlapped.store(true, Ordering::SeqCst);
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                inside.fetch_sub(1, Ordering::SeqCst);
+                if fail {
+                    return Err("deployment failed");
+                }
+                Ok(())
+            })
+        };
+
+        let (failed, succeeded) = tokio::join!(job(true), job(false));
+        assert!(failed.unwrap().is_err());
+        succeeded.unwrap().unwrap();
+        assert!(!overlapped.load(Ordering::SeqCst));
+
+        // The lock was released by the failed job, and never blocks other services
+        let _guard = tokio::time::timeout(Duration::from_secs(1), locks.lock(1))
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), locks.lock(2))
+            .await
+            .unwrap();
+    }
+}